@@ -6,8 +6,11 @@ use vulkano::{
     },
     command_buffer::{
         allocator::StandardCommandBufferAllocator, CommandBufferExecFuture, CopyBufferInfo,
+        CopyBufferToImageInfo,
     },
     device::Queue,
+    format::Format,
+    image::{Image, ImageCreateInfo, ImageUsage},
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
     sync::{self, future::FenceSignalFuture, GpuFuture},
     Validated,
@@ -19,6 +22,8 @@ pub type SendBufferFuture = FenceSignalFuture<CommandBufferExecFuture<sync::futu
 #[must_use = "The function returns a future that must be awaited and a buffer that must be used"]
 /// Sends the given data to the device,
 /// returning the destination buffer and the send future.
+///
+/// The destination buffer can be updated with [`write_to_device`] and extended with [`extend_on_device`].
 pub fn send_to_device<T>(
     memory_allocator: &Arc<StandardMemoryAllocator>,
     command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
@@ -49,7 +54,7 @@ where
     let destination_buffer = Buffer::new_unsized(
         memory_allocator.clone(),
         BufferCreateInfo {
-            usage: usage | BufferUsage::TRANSFER_DST,
+            usage: usage | BufferUsage::TRANSFER_SRC | BufferUsage::TRANSFER_DST,
             ..Default::default()
         },
         AllocationCreateInfo {
@@ -80,6 +85,276 @@ where
     Ok((destination_buffer, future))
 }
 
+#[must_use = "The function returns a future that must be awaited"]
+/// Overwrites the given buffer on the device with the given data,
+/// returning the send future.
+///
+/// It is typically used to update a single element of a buffer created by [`send_to_device`].
+pub fn write_to_device<T>(
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+    queue: &Arc<Queue>,
+    destination: Subbuffer<T>,
+    data: T,
+) -> Result<SendBufferFuture, Validated<AllocateBufferError>>
+where
+    T: BufferContents,
+{
+    let staging_buffer = Buffer::from_data(
+        memory_allocator.clone(),
+        BufferCreateInfo {
+            usage: BufferUsage::TRANSFER_SRC,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ..Default::default()
+        },
+        data,
+    )?;
+
+    let mut builder = vulkano::command_buffer::AutoCommandBufferBuilder::primary(
+        command_buffer_allocator,
+        queue.queue_family_index(),
+        vulkano::command_buffer::CommandBufferUsage::OneTimeSubmit,
+    )
+    .unwrap();
+    builder.copy_buffer(CopyBufferInfo::buffers(staging_buffer, destination))?;
+    let command_buffer = builder.build().unwrap();
+
+    let future = sync::now(queue.device().clone())
+        .then_execute(queue.clone(), command_buffer)
+        .unwrap()
+        .then_signal_fence_and_flush()
+        .unwrap();
+
+    Ok(future)
+}
+
+#[must_use = "The function returns a future that must be awaited"]
+/// Overwrites the given slice buffer on the device with the given elements,
+/// returning the send future.
+///
+/// It is typically used to rewrite a whole buffer created by [`send_to_device`],
+/// whose number of elements is unchanged.
+///
+/// ## Panics
+///
+/// This function panics if the number of elements does not match the length of the buffer.
+pub fn write_slice_to_device<T>(
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+    queue: &Arc<Queue>,
+    destination: Subbuffer<[T]>,
+    data: &[T],
+) -> Result<SendBufferFuture, Validated<AllocateBufferError>>
+where
+    T: BufferContents + Copy,
+{
+    assert_eq!(
+        destination.len(),
+        data.len() as u64,
+        "the data must have as many elements as the buffer"
+    );
+
+    let staging_buffer = Buffer::from_iter(
+        memory_allocator.clone(),
+        BufferCreateInfo {
+            usage: BufferUsage::TRANSFER_SRC,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ..Default::default()
+        },
+        data.iter().copied(),
+    )?;
+
+    let mut builder = vulkano::command_buffer::AutoCommandBufferBuilder::primary(
+        command_buffer_allocator,
+        queue.queue_family_index(),
+        vulkano::command_buffer::CommandBufferUsage::OneTimeSubmit,
+    )
+    .unwrap();
+    builder.copy_buffer(CopyBufferInfo::buffers(staging_buffer, destination))?;
+    let command_buffer = builder.build().unwrap();
+
+    let future = sync::now(queue.device().clone())
+        .then_execute(queue.clone(), command_buffer)
+        .unwrap()
+        .then_signal_fence_and_flush()
+        .unwrap();
+
+    Ok(future)
+}
+
+#[must_use = "The function returns a future that must be awaited and a buffer that must be used"]
+/// Sends the given data to the device after the content of `source`,
+/// returning the destination buffer and the send future.
+///
+/// As buffers cannot grow, `source` is copied to a new buffer on the device,
+/// so it must have been created by [`send_to_device`] or by this function.
+/// `data_len` and `fill_buffer` only describe the appended elements.
+///
+/// ## Panics
+///
+/// This function panics if `T` is not a slice, or a struct ending with one.
+pub fn extend_on_device<T>(
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+    queue: &Arc<Queue>,
+    source: &Subbuffer<T>,
+    data_len: u64,
+    usage: BufferUsage,
+    fill_buffer: impl FnOnce(&mut T),
+) -> Result<(Subbuffer<T>, SendBufferFuture), Validated<AllocateBufferError>>
+where
+    T: BufferContents + ?Sized,
+{
+    let element_size = T::LAYOUT
+        .element_size()
+        .expect("extended buffers must be unsized");
+    let source_len = (source.size() - T::LAYOUT.head_size()) / element_size;
+
+    let staging_buffer = Buffer::new_unsized(
+        memory_allocator.clone(),
+        BufferCreateInfo {
+            usage: BufferUsage::TRANSFER_SRC,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ..Default::default()
+        },
+        data_len,
+    )?;
+
+    fill_buffer(&mut staging_buffer.write().unwrap());
+
+    let destination_buffer = Buffer::new_unsized(
+        memory_allocator.clone(),
+        BufferCreateInfo {
+            usage: usage | BufferUsage::TRANSFER_SRC | BufferUsage::TRANSFER_DST,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
+            ..Default::default()
+        },
+        source_len + data_len,
+    )?;
+
+    // The head of the staging buffer, if any, is dropped to keep the elements contiguous
+    let appended_elements = staging_buffer
+        .as_bytes()
+        .clone()
+        .slice(T::LAYOUT.head_size()..);
+    let destination_tail = destination_buffer.as_bytes().clone().slice(source.size()..);
+
+    let mut builder = vulkano::command_buffer::AutoCommandBufferBuilder::primary(
+        command_buffer_allocator,
+        queue.queue_family_index(),
+        vulkano::command_buffer::CommandBufferUsage::OneTimeSubmit,
+    )
+    .unwrap();
+    builder
+        .copy_buffer(CopyBufferInfo::buffers(
+            source.clone(),
+            destination_buffer.clone(),
+        ))?
+        .copy_buffer(CopyBufferInfo::buffers(appended_elements, destination_tail))?;
+    let command_buffer = builder.build().unwrap();
+
+    let future = sync::now(queue.device().clone())
+        .then_execute(queue.clone(), command_buffer)
+        .unwrap()
+        .then_signal_fence_and_flush()
+        .unwrap();
+
+    Ok((destination_buffer, future))
+}
+
+#[must_use = "The function returns a future that must be awaited and an image that must be used"]
+/// Sends the given texels to the device as a sampled 2D image with `layers` array layers,
+/// returning the destination image and the send future.
+///
+/// The texels are given layer by layer, and row by row starting from the top, in the given format.
+///
+/// ## Panics
+///
+/// This function panics if the image cannot be allocated,
+/// or if the number of texels does not match the size of the image.
+pub fn send_image_to_device<T>(
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+    queue: &Arc<Queue>,
+    format: Format,
+    [width, height, layers]: [u32; 3],
+    texels: &[T],
+) -> Result<(Arc<Image>, SendBufferFuture), Validated<AllocateBufferError>>
+where
+    T: BufferContents + Copy,
+{
+    assert_eq!(
+        texels.len(),
+        width as usize * height as usize * layers as usize,
+        "texels must contain width * height * layers elements"
+    );
+
+    let staging_buffer = Buffer::from_iter(
+        memory_allocator.clone(),
+        BufferCreateInfo {
+            usage: BufferUsage::TRANSFER_SRC,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ..Default::default()
+        },
+        texels.iter().copied(),
+    )?;
+
+    let destination_image = Image::new(
+        memory_allocator.clone(),
+        ImageCreateInfo {
+            format,
+            extent: [width, height, 1],
+            array_layers: layers,
+            usage: ImageUsage::SAMPLED | ImageUsage::TRANSFER_DST,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut builder = vulkano::command_buffer::AutoCommandBufferBuilder::primary(
+        command_buffer_allocator,
+        queue.queue_family_index(),
+        vulkano::command_buffer::CommandBufferUsage::OneTimeSubmit,
+    )
+    .unwrap();
+    builder.copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
+        staging_buffer,
+        destination_image.clone(),
+    ))?;
+    let command_buffer = builder.build().unwrap();
+
+    let future = sync::now(queue.device().clone())
+        .then_execute(queue.clone(), command_buffer)
+        .unwrap()
+        .then_signal_fence_and_flush()
+        .unwrap();
+
+    Ok((destination_image, future))
+}
+
 #[must_use = "The function returns a buffer that must be used"]
 /// Creates a new uniform buffer.
 pub fn new_uniform<T>(
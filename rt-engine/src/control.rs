@@ -24,40 +24,164 @@ pub enum Input {
     Yaw(f32),
     /// Pitch.
     Pitch(f32),
+    /// Roll, positive values banking to the right.
+    ///
+    /// Only cameras with a free orientation, such as [`camera::free::Free`], respond to it.
+    Roll(f32),
+    /// Zoom, positive values narrowing the field of view, in scroll lines.
+    ///
+    /// Only cameras with an adjustable field of view, such as [`camera::first_person::FirstPerson`], respond to it.
+    Zoom(f32),
+    /// Move forward, or backward for negative values, at the given fraction of the speed of the camera.
+    ///
+    /// This is the analog counterpart of [`Input::Forward`] and [`Input::Backward`], used by sticks.
+    Advance(f32),
+    /// Move right, or left for negative values, at the given fraction of the speed of the camera.
+    ///
+    /// This is the analog counterpart of [`Input::Right`] and [`Input::Left`], used by sticks.
+    Strafe(f32),
 }
 
+/// The bits of the digital inputs, which are either held or not.
+///
+/// The other bits flag the analog inputs, whose values are summed.
+const DIGITAL_MASK: u16 = 0b11_1111;
+
 /// Represents a set of inputs.
+///
+/// Digital inputs are flags, while analog inputs (yaw, pitch, roll, zoom, advance and strafe) are sums:
+/// accumulating inputs gives the same result in any order.
 #[derive(Default, Debug, Clone, Copy)]
-pub struct Inputs((u8, f32, f32));
+pub struct Inputs((u16, f32, f32, f32, f32, f32, f32));
 
 impl Inputs {
     /// This function accumulates the inputs.
+    ///
+    /// The digital inputs are combined, while the analog values are added.
     pub fn accumulate(&mut self, inputs: Self) {
         self.0.0 |= inputs.0.0;
         self.0.1 += inputs.0.1;
         self.0.2 += inputs.0.2;
+        self.0.3 += inputs.0.3;
+        self.0.4 += inputs.0.4;
+        self.0.5 += inputs.0.5;
+        self.0.6 += inputs.0.6;
     }
 
     /// This function deccumulates the inputs.
+    ///
+    /// Only the digital inputs are released, the analog values being left untouched:
+    /// subtracting them back would not restore the previous sums once they have been replaced,
+    /// and the analog values are meant to be fetched and reset by the controllers instead.
     pub fn deccumulate(&mut self, inputs: Self) {
-        self.0.0 &= !inputs.0.0;
-        self.0.1 -= inputs.0.1;
-        self.0.2 -= inputs.0.2;
+        self.0.0 &= !(inputs.0.0 & DIGITAL_MASK);
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the accumulated yaw, or 0.0 if there is none.
+    pub const fn yaw(&self) -> f32 {
+        self.0.1
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the accumulated pitch, or 0.0 if there is none.
+    pub const fn pitch(&self) -> f32 {
+        self.0.2
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the accumulated roll, or 0.0 if there is none.
+    pub const fn roll(&self) -> f32 {
+        self.0.3
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the accumulated zoom, or 0.0 if there is none.
+    pub const fn zoom(&self) -> f32 {
+        self.0.4
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the accumulated forward movement, or 0.0 if there is none.
+    pub const fn advance(&self) -> f32 {
+        self.0.5
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the accumulated rightward movement, or 0.0 if there is none.
+    pub const fn strafe(&self) -> f32 {
+        self.0.6
+    }
+
+    #[inline]
+    /// Replaces the accumulated yaw.
+    pub const fn set_yaw(&mut self, yaw: f32) {
+        self.0.0 |= 1 << 6;
+        self.0.1 = yaw;
+    }
+
+    #[inline]
+    /// Replaces the accumulated pitch.
+    pub const fn set_pitch(&mut self, pitch: f32) {
+        self.0.0 |= 1 << 7;
+        self.0.2 = pitch;
+    }
+
+    #[inline]
+    /// Replaces the accumulated roll.
+    pub const fn set_roll(&mut self, roll: f32) {
+        self.0.0 |= 1 << 8;
+        self.0.3 = roll;
+    }
+
+    #[inline]
+    /// Replaces the accumulated zoom.
+    pub const fn set_zoom(&mut self, zoom: f32) {
+        self.0.0 |= 1 << 9;
+        self.0.4 = zoom;
+    }
+
+    #[inline]
+    /// Replaces the accumulated forward movement.
+    pub const fn set_advance(&mut self, advance: f32) {
+        self.0.0 |= 1 << 10;
+        self.0.5 = advance;
+    }
+
+    #[inline]
+    /// Replaces the accumulated rightward movement.
+    pub const fn set_strafe(&mut self, strafe: f32) {
+        self.0.0 |= 1 << 11;
+        self.0.6 = strafe;
     }
 }
 
+/// A filter applied every frame to the merged inputs of the controllers,
+/// given along with the time elapsed since the last frame in seconds.
+pub type InputFilter = Box<dyn FnMut(&mut Inputs, f32)>;
+
 // Transparency between Inputs and Input
 impl From<Input> for Inputs {
     fn from(input: Input) -> Self {
         match input {
-            Input::Forward => Self((1 << 0, 0.0, 0.0)),
-            Input::Backward => Self((1 << 1, 0.0, 0.0)),
-            Input::Left => Self((1 << 2, 0.0, 0.0)),
-            Input::Right => Self((1 << 3, 0.0, 0.0)),
-            Input::Up => Self((1 << 4, 0.0, 0.0)),
-            Input::Down => Self((1 << 5, 0.0, 0.0)),
-            Input::Yaw(yaw) => Self((1 << 6, yaw, 0.0)),
-            Input::Pitch(pitch) => Self((1 << 7, 0.0, pitch)),
+            Input::Forward => Self((1 << 0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Input::Backward => Self((1 << 1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Input::Left => Self((1 << 2, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Input::Right => Self((1 << 3, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Input::Up => Self((1 << 4, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Input::Down => Self((1 << 5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Input::Yaw(yaw) => Self((1 << 6, yaw, 0.0, 0.0, 0.0, 0.0, 0.0)),
+            Input::Pitch(pitch) => Self((1 << 7, 0.0, pitch, 0.0, 0.0, 0.0, 0.0)),
+            Input::Roll(roll) => Self((1 << 8, 0.0, 0.0, roll, 0.0, 0.0, 0.0)),
+            Input::Zoom(zoom) => Self((1 << 9, 0.0, 0.0, 0.0, zoom, 0.0, 0.0)),
+            Input::Advance(advance) => Self((1 << 10, 0.0, 0.0, 0.0, 0.0, advance, 0.0)),
+            Input::Strafe(strafe) => Self((1 << 11, 0.0, 0.0, 0.0, 0.0, 0.0, strafe)),
         }
     }
 }
@@ -102,6 +226,96 @@ impl From<Inputs> for Box<[Input]> {
         if inputs.0.0 & (1 << 7) != 0 {
             inputs_vec.push(Input::Pitch(inputs.0.2));
         }
+        if inputs.0.0 & (1 << 8) != 0 {
+            inputs_vec.push(Input::Roll(inputs.0.3));
+        }
+        if inputs.0.0 & (1 << 9) != 0 {
+            inputs_vec.push(Input::Zoom(inputs.0.4));
+        }
+        if inputs.0.0 & (1 << 10) != 0 {
+            inputs_vec.push(Input::Advance(inputs.0.5));
+        }
+        if inputs.0.0 & (1 << 11) != 0 {
+            inputs_vec.push(Input::Strafe(inputs.0.6));
+        }
         inputs_vec.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGITAL: [Input; 6] = [
+        Input::Forward,
+        Input::Backward,
+        Input::Left,
+        Input::Right,
+        Input::Up,
+        Input::Down,
+    ];
+
+    #[test]
+    fn digital_inputs_round_trip() {
+        let start = Inputs::from(&[Input::Forward, Input::Left][..]);
+        for input in DIGITAL {
+            let mut inputs = start;
+            inputs.accumulate(input.into());
+            inputs.deccumulate(input.into());
+            // Releasing an input that was already held releases it for good
+            let mut expected = start;
+            expected.deccumulate(input.into());
+            assert_eq!(inputs.0, expected.0, "{input:?}");
+        }
+
+        let mut inputs = Inputs::default();
+        inputs.accumulate(Inputs::from(&DIGITAL[..]));
+        inputs.deccumulate(Inputs::from(&DIGITAL[..]));
+        assert_eq!(inputs.0, Inputs::default().0);
+    }
+
+    #[test]
+    fn digital_inputs_leave_analog_inputs_untouched() {
+        let mut start = Inputs::default();
+        start.accumulate(Input::Yaw(0.25).into());
+        start.accumulate(Input::Pitch(-1.5).into());
+        start.accumulate(Input::Roll(3.0).into());
+        start.accumulate(Input::Zoom(-2.0).into());
+
+        let mut inputs = start;
+        for input in DIGITAL {
+            inputs.accumulate(input.into());
+        }
+        // Analog values replaced while the keys are held must survive their release
+        inputs.set_pitch(0.5);
+        for input in DIGITAL {
+            inputs.deccumulate(input.into());
+        }
+        start.set_pitch(0.5);
+        assert_eq!(inputs.0, start.0);
+
+        // Deccumulating analog inputs does not subtract them either
+        inputs.deccumulate(Input::Yaw(0.25).into());
+        inputs.deccumulate(Input::Zoom(-2.0).into());
+        assert_eq!(inputs.0, start.0);
+    }
+
+    #[test]
+    fn accumulation_is_order_independent() {
+        let sequence = [
+            Input::Yaw(0.5),
+            Input::Forward,
+            Input::Pitch(0.25),
+            Input::Yaw(-0.125),
+            Input::Right,
+            Input::Zoom(1.0),
+            Input::Pitch(0.75),
+        ];
+        let mut reversed = sequence;
+        reversed.reverse();
+
+        let inputs = Inputs::from(&sequence[..]);
+        assert_eq!(inputs.0, Inputs::from(&reversed[..]).0);
+        assert_eq!(inputs.0, (0b10_1100_1001, 0.375, 1.0, 0.0, 1.0, 0.0, 0.0));
+    }
+}
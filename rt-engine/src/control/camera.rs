@@ -6,7 +6,11 @@
 //! Mention it in the main app struct and it will be automatically handled
 //! by the event loop.
 
+pub mod animation;
 pub mod first_person;
+pub mod free;
+pub mod orbit;
+pub mod shake;
 
 /// Represents a camera.
 ///
@@ -29,8 +33,86 @@ pub trait Camera {
     /// The right vector must be normalized.
     fn right(&self) -> [f32; 3];
 
+    /// Returns the radius of the lens of the camera.
+    ///
+    /// Rays leave from a disk of this radius, which blurs what is not at the focus distance.
+    /// The default implementation returns 0, a pinhole camera for which everything is sharp.
+    fn aperture(&self) -> f32 {
+        0.0
+    }
+    /// Returns the distance from the camera at which the scene is sharp.
+    ///
+    /// It is ignored when the aperture is 0. The default implementation returns 1.
+    fn focus_distance(&self) -> f32 {
+        1.0
+    }
+    /// Returns the vertical field of view of the camera, in degrees.
+    ///
+    /// It only applies to the perspective projection.
+    /// The default implementation returns 90, the field of view of cameras without zoom.
+    fn fov(&self) -> f32 {
+        90.0
+    }
+    /// Returns the distances of the near and far clip planes, along the direction of the camera.
+    ///
+    /// Primary rays ignore the surfaces closer than the near plane, and miss those beyond the far plane.
+    /// The near plane must be non-negative and closer than the far plane, otherwise both are ignored.
+    /// The default implementation returns `(0.0, f32::INFINITY)`, which does not clip anything.
+    fn clip_planes(&self) -> (f32, f32) {
+        (0.0, f32::INFINITY)
+    }
+
+    /// Turns the camera to face the given point, in world space.
+    ///
+    /// A target at the position of the camera leaves it untouched.
+    /// The default implementation does nothing, for cameras that cannot be aimed.
+    fn look_at(&mut self, target: [f32; 3]) {
+        let _ = target;
+    }
+
+    /// Moves the camera to the given position, in world space.
+    ///
+    /// The default implementation does nothing, for cameras that cannot be moved.
+    fn set_position(&mut self, position: [f32; 3]) {
+        let _ = position;
+    }
+
     /// Processes the inputs and updates the camera.
     ///
     /// Typically, this means updating the camera's position, orientation, etc.
     fn process_inputs(&mut self, inputs: super::Inputs, delta_seconds: f32);
 }
+
+#[inline]
+/// Normalizes in-place a 3D vector.
+fn normalize(v: &mut [f32; 3]) {
+    let inv_length = 1.0 / length(*v);
+    v[0] *= inv_length;
+    v[1] *= inv_length;
+    v[2] *= inv_length;
+}
+
+#[must_use]
+#[inline]
+/// Returns the length of a 3D vector.
+fn length(v: [f32; 3]) -> f32 {
+    (v[2].mul_add(v[2], v[0].mul_add(v[0], v[1] * v[1]))).sqrt()
+}
+
+#[must_use]
+#[inline]
+/// Returns the dot product of two 3D vectors.
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[2].mul_add(b[2], a[0].mul_add(b[0], a[1] * b[1]))
+}
+
+#[must_use]
+#[inline]
+/// Returns the cross product of two 3D vectors.
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1].mul_add(b[2], -(a[2] * b[1])),
+        a[2].mul_add(b[0], -(a[0] * b[2])),
+        a[0].mul_add(b[1], -(a[1] * b[0])),
+    ]
+}
@@ -0,0 +1,119 @@
+use super::super::Inputs;
+use super::{length, Camera};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Represents what happens to the inputs of the controllers while the camera is animated,
+/// see [`crate::RayTracingApp::animate_camera_to`].
+pub enum AnimationInput {
+    #[default]
+    /// The inputs are discarded until the animation ends.
+    Ignore,
+    /// The inputs move the camera away from the animated path, which follows it.
+    ///
+    /// The orientation of the camera keeps following the animation, so only the translation
+    /// caused by the inputs is kept.
+    Blend,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Represents a transition of a camera from its pose to a position looking at a target.
+pub(crate) struct CameraAnimation {
+    /// The position of the camera when the animation started.
+    start_position: [f32; 3],
+    /// The point in front of the camera when the animation started.
+    start_target: [f32; 3],
+    /// The position of the camera at the end of the animation.
+    end_position: [f32; 3],
+    /// The point the camera looks at at the end of the animation.
+    end_target: [f32; 3],
+    /// The duration of the animation, in seconds.
+    duration: f32,
+    /// The time elapsed since the start of the animation, in seconds.
+    elapsed: f32,
+    /// What happens to the inputs during the animation.
+    input: AnimationInput,
+    /// The translation of the path by the blended inputs.
+    offset: [f32; 3],
+}
+
+impl CameraAnimation {
+    #[must_use]
+    /// Creates an animation of the given camera to `position`, looking at `target`, over `duration` seconds.
+    pub(crate) fn new(
+        camera: &dyn Camera,
+        position: [f32; 3],
+        target: [f32; 3],
+        duration: f32,
+        input: AnimationInput,
+    ) -> Self {
+        let start_position = camera.position();
+        let direction = camera.direction();
+
+        // The camera starts by looking at a point as far as the target, so that it turns smoothly
+        let target_distance =
+            length(std::array::from_fn(|i| target[i] - start_position[i])).max(1.0);
+        let start_target =
+            std::array::from_fn(|i| direction[i].mul_add(target_distance, start_position[i]));
+
+        Self {
+            start_position,
+            start_target,
+            end_position: position,
+            end_target: target,
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            input,
+            offset: [0.0; 3],
+        }
+    }
+
+    /// Advances the animation by `delta_seconds` and moves the camera accordingly,
+    /// handling the inputs as configured instead of the camera.
+    ///
+    /// Returns whether the animation is over, the camera being left at its end.
+    pub(crate) fn advance(
+        &mut self,
+        camera: &mut dyn Camera,
+        inputs: Inputs,
+        delta_seconds: f32,
+    ) -> bool {
+        if self.input == AnimationInput::Blend {
+            let position = camera.position();
+            camera.process_inputs(inputs, delta_seconds);
+            for ((offset, after), before) in
+                self.offset.iter_mut().zip(camera.position()).zip(position)
+            {
+                *offset += after - before;
+            }
+        }
+
+        self.elapsed += delta_seconds;
+        let progress = if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0)
+        } else {
+            1.0
+        };
+        let eased = ease_in_out(progress);
+
+        let interpolate = |start: [f32; 3], end: [f32; 3]| -> [f32; 3] {
+            std::array::from_fn(|i| (end[i] - start[i]).mul_add(eased, start[i]) + self.offset[i])
+        };
+        let position = interpolate(self.start_position, self.end_position);
+        let target = interpolate(self.start_target, self.end_target);
+
+        // Orbiting cameras move with their target, which is thus set before the position
+        // and faced again from the new position by the other cameras
+        camera.look_at(target);
+        camera.set_position(position);
+        camera.look_at(target);
+
+        progress >= 1.0
+    }
+}
+
+#[must_use]
+#[inline]
+/// Eases the given progress in `[0, 1]`, so that the animation starts and ends smoothly.
+fn ease_in_out(t: f32) -> f32 {
+    t * t * 2.0_f32.mul_add(-t, 3.0)
+}
@@ -1,4 +1,5 @@
 use super::super::{Input, Inputs};
+use super::{cross, dot, length, normalize};
 
 #[derive(Copy, Clone, Debug)]
 /// Represents a first person camera.
@@ -22,44 +23,134 @@ pub struct FirstPerson {
     right: [f32; 3],
     /// The yaw of the camera.
     yaw: f32,
-    /// The pitch of the camera.
+    /// The pitch of the camera, between `-max_pitch` and `max_pitch`.
     pitch: f32,
+    /// The largest angle the camera can look up or down, in degrees.
+    max_pitch: f32,
     /// The speed of the camera.
     speed: f32,
+    /// The current velocity of the camera, in units per second.
+    velocity: [f32; 3],
+    /// The rate at which the velocity reaches the speed of the camera while moving, per second.
+    acceleration: f32,
+    /// The rate at which the velocity decreases once the camera stops moving, per second.
+    damping: f32,
     /// The sensitivity of the camera.
     sensitivity: f32,
+    /// The normalized up vector of the world, around which the camera yaws.
+    world_up: [f32; 3],
+    /// The radius of the lens of the camera.
+    aperture: f32,
+    /// The distance from the camera at which the scene is sharp.
+    focus_distance: f32,
+    /// The vertical field of view of the camera, in degrees.
+    fov: f32,
+    /// The distances of the near and far clip planes.
+    clip_planes: (f32, f32),
 }
 
+/// The narrowest field of view the camera can zoom to, in degrees.
+const MIN_FOV: f32 = 10.0;
+/// The widest field of view the camera can zoom to, in degrees.
+const MAX_FOV: f32 = 120.0;
+/// The change of the field of view for each scroll line, in degrees.
+const ZOOM_STEP: f32 = 5.0;
+/// The largest pitch limit, in degrees, below the vertical at which the yaw is undefined.
+const PITCH_LIMIT: f32 = 89.9;
+
 impl FirstPerson {
     #[must_use]
+    /// Creates a camera at the given position, looking in the direction given by `yaw` and `pitch` in degrees.
+    ///
+    /// The world is Y-up, use [`FirstPerson::set_world_up`] to change it.
+    /// The pitch is clamped between -89 and 89 degrees, see [`FirstPerson::set_max_pitch`].
     pub fn from_position_yaw_pitch(position: [f32; 3], yaw: f32, pitch: f32) -> Self {
-        let direction = [
-            yaw.to_radians().cos() * pitch.to_radians().cos(),
-            pitch.to_radians().sin(),
-            yaw.to_radians().sin() * pitch.to_radians().cos(),
-        ];
-
-        let right = [-yaw.to_radians().sin(), 0.0, yaw.to_radians().cos()];
-
-        let mut up = [
-            right[1].mul_add(direction[2], -(right[2] * direction[1])),
-            right[2].mul_add(direction[0], -(right[0] * direction[2])),
-            right[0].mul_add(direction[1], -(right[1] * direction[0])),
-        ];
-
-        // normalize(&mut self.direction); // This is not necessary, as the direction is normalized by the pitch and yaw.
-        // normalize(&mut self.right); // This is not necessary, as the right vector is normalized by the yaw.
-        normalize(&mut up);
-
-        Self {
+        let mut camera = Self {
             position,
-            direction,
-            up,
-            right,
             yaw,
             pitch,
             ..Default::default()
+        };
+        camera.clamp_pitch();
+        camera.update_vectors();
+        camera
+    }
+
+    /// Sets the up vector of the world, which defaults to `[0.0, 1.0, 0.0]`.
+    ///
+    /// The yaw and pitch of the camera are kept, but are now relative to the new up vector.
+    /// A null vector is ignored.
+    pub fn set_world_up(&mut self, mut world_up: [f32; 3]) {
+        if !length(world_up).is_normal() {
+            tracing::warn!("Ignoring null world up vector");
+            return;
+        }
+
+        normalize(&mut world_up);
+        self.world_up = world_up;
+        self.update_vectors();
+    }
+
+    /// Sets the largest angle the camera can look up or down, in degrees.
+    ///
+    /// It defaults to 89 and is clamped between 0 and 89.9, so that the camera never looks
+    /// straight along the world up vector. The current pitch is clamped to the new range.
+    pub fn set_max_pitch(&mut self, max_pitch: f32) {
+        self.max_pitch = max_pitch.clamp(0.0, PITCH_LIMIT);
+        self.clamp_pitch();
+        self.update_vectors();
+    }
+
+    #[inline]
+    /// Clamps the pitch between `-max_pitch` and `max_pitch`.
+    fn clamp_pitch(&mut self) {
+        self.pitch = self.pitch.clamp(-self.max_pitch, self.max_pitch);
+    }
+
+    /// Recomputes the direction, right and up vectors from the yaw, the pitch and the world up vector.
+    fn update_vectors(&mut self) {
+        let (yaw_sin, yaw_cos) = self.yaw.to_radians().sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.to_radians().sin_cos();
+        let [forward, side] = horizontal_axes(self.world_up);
+
+        self.direction = std::array::from_fn(|i| {
+            pitch_sin.mul_add(
+                self.world_up[i],
+                pitch_cos * yaw_cos.mul_add(forward[i], yaw_sin * side[i]),
+            )
+        });
+        self.right = std::array::from_fn(|i| yaw_cos.mul_add(side[i], -(yaw_sin * forward[i])));
+        self.up = cross(self.right, self.direction);
+
+        // The direction and the right vector are already normalized, as they are built from orthonormal axes.
+        normalize(&mut self.up);
+    }
+
+    /// Turns the camera to face the given direction, which does not need to be normalized.
+    ///
+    /// The yaw and the pitch are recomputed from the direction, the pitch being clamped like the inputs.
+    /// Looking along the world up vector keeps the yaw, which is undefined there. A null direction is ignored.
+    pub fn set_direction(&mut self, mut direction: [f32; 3]) {
+        if !length(direction).is_normal() {
+            tracing::warn!("Ignoring null camera direction");
+            return;
+        }
+        normalize(&mut direction);
+
+        // Clamped, as rounding errors may push the dot product out of the domain of asin
+        self.pitch = dot(direction, self.world_up)
+            .clamp(-1.0, 1.0)
+            .asin()
+            .to_degrees();
+        self.clamp_pitch();
+
+        let [forward, side] = horizontal_axes(self.world_up);
+        let (x, y) = (dot(direction, forward), dot(direction, side));
+        if x.hypot(y) > 1e-6 {
+            self.yaw = y.atan2(x).to_degrees();
         }
+
+        self.update_vectors();
     }
 
     #[inline]
@@ -73,15 +164,90 @@ impl FirstPerson {
     pub fn set_speed(&mut self, speed: f32) {
         self.speed = speed;
     }
+
+    #[inline]
+    /// Sets the rate at which the camera reaches its speed when it starts moving, per second.
+    ///
+    /// The velocity closes the gap to the speed exponentially: with an acceleration of 10,
+    /// the camera reaches 63% of its speed in 0.1 second.
+    /// The default, `f32::INFINITY`, reaches it instantly. Negative values are clamped to 0.
+    pub const fn set_acceleration(&mut self, acceleration: f32) {
+        self.acceleration = acceleration.max(0.0);
+    }
+
+    #[inline]
+    /// Sets the rate at which the camera coasts to a stop once it stops moving, per second.
+    ///
+    /// It works like the acceleration, a low damping making the camera drift for longer.
+    /// The default, `f32::INFINITY`, stops it instantly. Negative values are clamped to 0.
+    pub const fn set_damping(&mut self, damping: f32) {
+        self.damping = damping.max(0.0);
+    }
+
+    #[inline]
+    /// Sets the radius of the lens of the camera.
+    ///
+    /// An aperture of 0, the default, gives a pinhole camera for which everything is sharp.
+    /// Negative values are clamped to 0.
+    pub const fn set_aperture(&mut self, aperture: f32) {
+        self.aperture = aperture.max(0.0);
+    }
+
+    #[inline]
+    /// Sets the distance from the camera at which the scene is sharp.
+    ///
+    /// It has no effect with an aperture of 0. Non-positive values are ignored.
+    pub fn set_focus_distance(&mut self, focus_distance: f32) {
+        if focus_distance > 0.0 {
+            self.focus_distance = focus_distance;
+        } else {
+            tracing::warn!("Ignoring non-positive focus distance {focus_distance}");
+        }
+    }
+
+    #[inline]
+    /// Sets the vertical field of view of the camera, in degrees.
+    ///
+    /// It defaults to 90 and is clamped between 10 and 120, the range the zoom input is limited to.
+    pub const fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(MIN_FOV, MAX_FOV);
+    }
+
+    #[inline]
+    /// Sets the distances of the near and far clip planes, along the direction of the camera.
+    ///
+    /// Clipping the surfaces closer than the camera, such as the inside of a model it moves through,
+    /// avoids artifacts, and a far plane bounds the traversal of huge scenes.
+    /// The near plane must be positive and closer than the far plane, which may be `f32::INFINITY`,
+    /// otherwise the planes are ignored. By default, nothing is clipped.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) {
+        if near > 0.0 && near < far {
+            self.clip_planes = (near, far);
+        } else {
+            tracing::warn!("Ignoring invalid clip planes: near {near}, far {far}");
+        }
+    }
 }
 
-#[inline]
-/// Normalizes in-place a 3D vector.
-fn normalize(v: &mut [f32; 3]) {
-    let inv_length = 1.0 / (v[2].mul_add(v[2], v[0].mul_add(v[0], v[1] * v[1]))).sqrt();
-    v[0] *= inv_length;
-    v[1] *= inv_length;
-    v[2] *= inv_length;
+#[must_use]
+/// Returns two orthonormal vectors spanning the plane orthogonal to the normalized `up` vector,
+/// along which the camera faces for a yaw of 0 and 90 degrees.
+///
+/// For a Y-up world, they are the X and Z axes.
+fn horizontal_axes(up: [f32; 3]) -> [[f32; 3]; 2] {
+    // The reference axis must not be close to the up vector
+    let reference = if up[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 0.0, 1.0]
+    };
+    let along_up = dot(reference, up);
+
+    let mut forward = std::array::from_fn(|i| (-along_up).mul_add(up[i], reference[i]));
+    normalize(&mut forward);
+    let side = cross(forward, up);
+
+    [forward, side]
 }
 
 impl Default for FirstPerson {
@@ -93,8 +259,17 @@ impl Default for FirstPerson {
             right: [-1.0, 0.0, 0.0],
             yaw: 270.0,
             pitch: 0.0,
+            max_pitch: 89.0,
             speed: 10.0,
+            velocity: [0.0; 3],
+            acceleration: f32::INFINITY,
+            damping: f32::INFINITY,
             sensitivity: 0.03,
+            world_up: [0.0, 1.0, 0.0],
+            aperture: 0.0,
+            focus_distance: 10.0,
+            fov: 90.0,
+            clip_planes: (0.0, f32::INFINITY),
         }
     }
 }
@@ -124,75 +299,183 @@ impl super::Camera for FirstPerson {
         self.right
     }
 
+    #[must_use]
+    #[inline]
+    fn aperture(&self) -> f32 {
+        self.aperture
+    }
+
+    #[must_use]
+    #[inline]
+    fn focus_distance(&self) -> f32 {
+        self.focus_distance
+    }
+
+    #[must_use]
+    #[inline]
+    fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    #[must_use]
+    #[inline]
+    fn clip_planes(&self) -> (f32, f32) {
+        self.clip_planes
+    }
+
+    fn look_at(&mut self, target: [f32; 3]) {
+        let direction = std::array::from_fn(|i| target[i] - self.position[i]);
+        if length(direction).is_normal() {
+            self.set_direction(direction);
+        }
+    }
+
+    #[inline]
+    fn set_position(&mut self, position: [f32; 3]) {
+        self.position = position;
+    }
+
     fn process_inputs(&mut self, inputs: Inputs, delta_seconds: f32) {
         let inputs = Into::<Box<[Input]>>::into(inputs);
-        if inputs.is_empty() {
+        if inputs.is_empty() && self.velocity.iter().all(|&component| component == 0.0) {
             return;
         }
 
-        let relative_speed = self.speed * delta_seconds;
+        // The velocity the inputs ask for
+        let mut target_velocity = [0.0; 3];
+        // Copied, as the closure must not borrow the camera the other inputs update
+        let speed = self.speed;
+        let mut add_target = |vector: [f32; 3], sign: f32| {
+            for (target, component) in target_velocity.iter_mut().zip(vector) {
+                *target += sign * component * speed;
+            }
+        };
 
         for input in inputs {
             match input {
-                Input::Forward => {
-                    self.position[0] += self.direction[0] * relative_speed;
-                    self.position[1] += self.direction[1] * relative_speed;
-                    self.position[2] += self.direction[2] * relative_speed;
-                }
-                Input::Backward => {
-                    self.position[0] -= self.direction[0] * relative_speed;
-                    self.position[1] -= self.direction[1] * relative_speed;
-                    self.position[2] -= self.direction[2] * relative_speed;
-                }
-                Input::Left => {
-                    self.position[0] -= self.right[0] * relative_speed;
-                    self.position[1] -= self.right[1] * relative_speed;
-                    self.position[2] -= self.right[2] * relative_speed;
-                }
-                Input::Right => {
-                    self.position[0] += self.right[0] * relative_speed;
-                    self.position[1] += self.right[1] * relative_speed;
-                    self.position[2] += self.right[2] * relative_speed;
-                }
-                Input::Up => {
-                    self.position[0] += self.up[0] * relative_speed;
-                    self.position[1] += self.up[1] * relative_speed;
-                    self.position[2] += self.up[2] * relative_speed;
-                }
-                Input::Down => {
-                    self.position[0] -= self.up[0] * relative_speed;
-                    self.position[1] -= self.up[1] * relative_speed;
-                    self.position[2] -= self.up[2] * relative_speed;
-                }
+                Input::Forward => add_target(self.direction, 1.0),
+                Input::Backward => add_target(self.direction, -1.0),
+                Input::Left => add_target(self.right, -1.0),
+                Input::Right => add_target(self.right, 1.0),
+                Input::Up => add_target(self.up, 1.0),
+                Input::Down => add_target(self.up, -1.0),
+                Input::Advance(value) => add_target(self.direction, value),
+                Input::Strafe(value) => add_target(self.right, value),
                 Input::Yaw(value) => {
                     self.yaw -= value * self.sensitivity;
                 }
+                // Looking past the vertical would turn the world upside down
                 Input::Pitch(value) => {
                     self.pitch -= value * self.sensitivity;
+                    self.clamp_pitch();
+                }
+                // The camera stays upright
+                Input::Roll(_) => {}
+                Input::Zoom(value) => {
+                    self.set_fov(value.mul_add(-ZOOM_STEP, self.fov));
                 }
             }
         }
 
-        self.direction = [
-            self.yaw.to_radians().cos() * self.pitch.to_radians().cos(),
-            self.pitch.to_radians().sin(),
-            self.yaw.to_radians().sin() * self.pitch.to_radians().cos(),
-        ];
-
-        self.right = [
-            -self.yaw.to_radians().sin(),
-            0.0,
-            self.yaw.to_radians().cos(),
-        ];
-
-        self.up = [
-            self.right[1].mul_add(self.direction[2], -(self.right[2] * self.direction[1])),
-            self.right[2].mul_add(self.direction[0], -(self.right[0] * self.direction[2])),
-            self.right[0].mul_add(self.direction[1], -(self.right[1] * self.direction[0])),
-        ];
-
-        // normalize(&mut self.direction); // This is not necessary, as the direction is normalized by the pitch and yaw.
-        // normalize(&mut self.right); // This is not necessary, as the right vector is normalized by the yaw.
-        normalize(&mut self.up);
+        let rate = if target_velocity.iter().all(|&component| component == 0.0) {
+            self.damping
+        } else {
+            self.acceleration
+        };
+        // An infinite rate reaches the target instantly, even for a null time step
+        let remaining = if rate.is_finite() {
+            (-rate * delta_seconds).exp()
+        } else {
+            0.0
+        };
+        for (velocity, target) in self.velocity.iter_mut().zip(target_velocity) {
+            *velocity = (*velocity - target).mul_add(remaining, target);
+        }
+
+        // Stop coasting once the motion is no longer visible
+        if length(self.velocity) < self.speed * 1e-3 {
+            self.velocity = [0.0; 3];
+        }
+
+        for (position, velocity) in self.position.iter_mut().zip(self.velocity) {
+            *position += velocity * delta_seconds;
+        }
+
+        self.update_vectors();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Camera;
+    use super::*;
+
+    /// Returns the angle between the direction of the camera and the horizontal plane, in degrees.
+    fn elevation(camera: &FirstPerson) -> f32 {
+        dot(camera.direction(), camera.world_up).asin().to_degrees()
+    }
+
+    #[test]
+    fn pitch_inputs_stay_within_max_pitch() {
+        let mut camera = FirstPerson::default();
+        camera.set_max_pitch(60.0);
+
+        // Large swings, alternating in bursts, so that both limits are hit repeatedly
+        let mut state = 0x2545_f491_u32;
+        for _ in 0..1000 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            #[allow(clippy::cast_precision_loss)]
+            let value = (state as f32 / u32::MAX as f32 - 0.5) * 20_000.0;
+
+            camera.process_inputs(Input::Pitch(value).into(), 0.016);
+            assert!(
+                camera.pitch.abs() <= camera.max_pitch,
+                "pitch {}",
+                camera.pitch
+            );
+            assert!(elevation(&camera).abs() <= camera.max_pitch + 1e-3);
+        }
+    }
+
+    #[test]
+    fn set_max_pitch_clamps_the_pitch() {
+        let mut camera = FirstPerson::from_position_yaw_pitch([0.0; 3], 0.0, 80.0);
+        assert!((camera.pitch - 80.0).abs() < f32::EPSILON);
+
+        camera.set_max_pitch(30.0);
+        assert!((camera.pitch - 30.0).abs() < f32::EPSILON);
+        assert!((elevation(&camera) - 30.0).abs() < 1e-3);
+
+        // The limit itself stays short of the vertical
+        camera.set_max_pitch(120.0);
+        assert!((camera.max_pitch - PITCH_LIMIT).abs() < f32::EPSILON);
+        camera.process_inputs(Input::Pitch(-1e6).into(), 0.016);
+        assert!((camera.pitch - PITCH_LIMIT).abs() < f32::EPSILON);
+
+        camera.set_max_pitch(-10.0);
+        assert!(camera.max_pitch.abs() < f32::EPSILON);
+        assert!(camera.pitch.abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn set_direction_clamps_the_pitch() {
+        let mut camera = FirstPerson::from_position_yaw_pitch([0.0; 3], 45.0, 0.0);
+        camera.set_max_pitch(50.0);
+
+        // Straight up, where the yaw is undefined and kept
+        camera.set_direction([0.0, 1.0, 0.0]);
+        assert!((camera.pitch - 50.0).abs() < f32::EPSILON);
+        assert!((camera.yaw - 45.0).abs() < 1e-3);
+
+        camera.set_direction([1.0, -3.0, 0.0]);
+        assert!((camera.pitch + 50.0).abs() < f32::EPSILON);
+        assert!(camera.yaw.abs() < 1e-3);
+
+        // Within the limits, the direction is kept
+        camera.set_direction([1.0, 1.0, 0.0]);
+        assert!((camera.pitch - 45.0).abs() < 1e-3);
+        assert!((elevation(&camera) - 45.0).abs() < 1e-3);
     }
 }
@@ -0,0 +1,224 @@
+use super::super::{Input, Inputs};
+use super::{cross, length, normalize};
+
+#[derive(Copy, Clone, Debug)]
+/// Represents a free camera, with six degrees of freedom.
+///
+/// Unlike the first person camera, it has no notion of a world up vector:
+/// the yaw, pitch and roll inputs rotate it around its own axes,
+/// so it can look in any direction and fly upside down.
+pub struct Free {
+    /// The position of the camera.
+    position: [f32; 3],
+    /// The direction the camera is facing.
+    direction: [f32; 3],
+    /// The up vector of the camera.
+    up: [f32; 3],
+    /// The right vector of the camera.
+    ///
+    /// The right vector is only stored for speed purposes,
+    /// it is only really computed when the camera rotates.
+    right: [f32; 3],
+    /// The speed of the camera.
+    speed: f32,
+    /// The sensitivity of the camera, in degrees per unit of rotation input.
+    sensitivity: f32,
+}
+
+impl Free {
+    #[must_use]
+    /// Creates a camera at the given position, facing `direction` with the given `up` vector.
+    ///
+    /// The up vector does not need to be orthogonal to the direction, only its component
+    /// orthogonal to the direction is kept.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the direction is null, or if the up vector is null or colinear to the direction.
+    pub fn from_position_direction_up(
+        position: [f32; 3],
+        mut direction: [f32; 3],
+        up: [f32; 3],
+    ) -> Self {
+        assert!(
+            length(direction).is_normal(),
+            "the direction of the camera must not be null"
+        );
+        normalize(&mut direction);
+        let right = cross(direction, up);
+        assert!(
+            length(right).is_normal(),
+            "the up vector of the camera must not be null or colinear to its direction"
+        );
+
+        let mut camera = Self {
+            position,
+            direction,
+            up,
+            right,
+            ..Default::default()
+        };
+        camera.orthonormalize();
+        camera
+    }
+
+    #[inline]
+    /// Sets the sensitivity of the camera.
+    pub const fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+
+    #[inline]
+    /// Sets the speed of the camera.
+    pub const fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Turns the camera to face the given direction, which does not need to be normalized.
+    ///
+    /// The up vector is kept as close as possible to the current one. When the direction is along it,
+    /// the right vector is kept instead. A null direction is ignored.
+    pub fn set_direction(&mut self, mut direction: [f32; 3]) {
+        if !length(direction).is_normal() {
+            tracing::warn!("Ignoring null camera direction");
+            return;
+        }
+        normalize(&mut direction);
+
+        if length(cross(direction, self.up)) < 1e-3 {
+            self.up = cross(self.right, direction);
+        }
+        self.direction = direction;
+        self.orthonormalize();
+    }
+
+    /// Recomputes the right and up vectors from the direction and the up vector,
+    /// so that rounding errors do not accumulate over the rotations.
+    fn orthonormalize(&mut self) {
+        normalize(&mut self.direction);
+        self.right = cross(self.direction, self.up);
+        normalize(&mut self.right);
+        self.up = cross(self.right, self.direction);
+    }
+}
+
+#[must_use]
+/// Rotates `v` by `angle` degrees around the normalized `axis`, counter-clockwise
+/// when looking from the tip of the axis.
+fn rotate(v: [f32; 3], axis: [f32; 3], angle: f32) -> [f32; 3] {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let axis_cross = cross(axis, v);
+    let axis_dot = axis[2].mul_add(v[2], axis[0].mul_add(v[0], axis[1] * v[1]));
+
+    // Rodrigues' rotation formula
+    std::array::from_fn(|i| {
+        (axis[i] * axis_dot).mul_add(1.0 - cos, v[i].mul_add(cos, axis_cross[i] * sin))
+    })
+}
+
+impl Default for Free {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0, 0.0],
+            direction: [0.0, 0.0, -1.0],
+            up: [0.0, 1.0, 0.0],
+            right: [1.0, 0.0, 0.0],
+            speed: 10.0,
+            sensitivity: 0.03,
+        }
+    }
+}
+
+impl super::Camera for Free {
+    #[must_use]
+    #[inline]
+    fn direction(&self) -> [f32; 3] {
+        self.direction
+    }
+
+    #[must_use]
+    #[inline]
+    fn position(&self) -> [f32; 3] {
+        self.position
+    }
+
+    #[must_use]
+    #[inline]
+    fn up(&self) -> [f32; 3] {
+        self.up
+    }
+
+    #[must_use]
+    #[inline]
+    fn right(&self) -> [f32; 3] {
+        self.right
+    }
+
+    fn look_at(&mut self, target: [f32; 3]) {
+        let direction = std::array::from_fn(|i| target[i] - self.position[i]);
+        if length(direction).is_normal() {
+            self.set_direction(direction);
+        }
+    }
+
+    #[inline]
+    fn set_position(&mut self, position: [f32; 3]) {
+        self.position = position;
+    }
+
+    fn process_inputs(&mut self, inputs: Inputs, delta_seconds: f32) {
+        let inputs = Into::<Box<[Input]>>::into(inputs);
+        if inputs.is_empty() {
+            return;
+        }
+
+        let relative_speed = self.speed * delta_seconds;
+        let mut translate = |vector: [f32; 3], sign: f32| {
+            for (position, component) in self.position.iter_mut().zip(vector) {
+                *position += sign * component * relative_speed;
+            }
+        };
+
+        let (direction, up, right) = (self.direction, self.up, self.right);
+        let mut rotated = false;
+        for input in &inputs {
+            match *input {
+                Input::Forward => translate(direction, 1.0),
+                Input::Backward => translate(direction, -1.0),
+                Input::Left => translate(right, -1.0),
+                Input::Right => translate(right, 1.0),
+                Input::Up => translate(up, 1.0),
+                Input::Down => translate(up, -1.0),
+                Input::Advance(value) => translate(direction, value),
+                Input::Strafe(value) => translate(right, value),
+                Input::Yaw(_) | Input::Pitch(_) | Input::Roll(_) => rotated = true,
+                Input::Zoom(_) => {}
+            }
+        }
+
+        if !rotated {
+            return;
+        }
+
+        // Rotations are applied around the axes of the camera, in the same conventions
+        // as the first person camera: positive yaws turn left and positive pitches look down
+        for input in &inputs {
+            match *input {
+                Input::Yaw(value) => {
+                    self.direction = rotate(self.direction, self.up, value * self.sensitivity);
+                }
+                Input::Pitch(value) => {
+                    let angle = -value * self.sensitivity;
+                    self.direction = rotate(self.direction, self.right, angle);
+                    self.up = rotate(self.up, self.right, angle);
+                }
+                Input::Roll(value) => {
+                    self.up = rotate(self.up, self.direction, value * self.sensitivity);
+                }
+                _ => continue,
+            }
+            // The next rotation uses the updated axes
+            self.orthonormalize();
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use super::super::{Input, Inputs};
+use super::{cross, length, normalize};
+
+/// The maximum pitch of the camera in degrees, which keeps it from flipping over the poles.
+const MAX_PITCH: f32 = 89.0;
+/// The minimum distance between the camera and its target.
+const MIN_DISTANCE: f32 = 1e-3;
+
+#[derive(Copy, Clone, Debug)]
+/// Represents a camera orbiting around a target point, like a turntable.
+///
+/// The yaw and pitch inputs rotate the camera around the target,
+/// while the forward and backward inputs zoom in and out.
+pub struct Orbit {
+    /// The point the camera looks at.
+    target: [f32; 3],
+    /// The distance between the camera and the target.
+    distance: f32,
+    /// The yaw of the camera around the target.
+    yaw: f32,
+    /// The pitch of the camera around the target.
+    pitch: f32,
+    /// The zoom speed of the camera.
+    speed: f32,
+    /// The sensitivity of the camera.
+    sensitivity: f32,
+    /// The position of the camera.
+    ///
+    /// The position is only stored for speed purposes,
+    /// it is only really computed when the camera moves.
+    position: [f32; 3],
+    /// The direction the camera is facing, towards the target.
+    direction: [f32; 3],
+    /// The up vector of the camera.
+    up: [f32; 3],
+    /// The right vector of the camera.
+    right: [f32; 3],
+}
+
+impl Orbit {
+    #[must_use]
+    /// Creates a camera looking at `target` from the given distance, along the negative Z axis.
+    pub fn from_target_distance(target: [f32; 3], distance: f32) -> Self {
+        let mut camera = Self {
+            target,
+            distance: distance.max(MIN_DISTANCE),
+            ..Default::default()
+        };
+        camera.update_vectors();
+        camera
+    }
+
+    #[inline]
+    /// Sets the sensitivity of the camera.
+    pub const fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+
+    #[inline]
+    /// Sets the zoom speed of the camera.
+    pub const fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Sets the point the camera looks at, keeping its distance and orientation.
+    pub fn set_target(&mut self, target: [f32; 3]) {
+        self.target = target;
+        self.update_vectors();
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the point the camera looks at.
+    pub const fn target(&self) -> [f32; 3] {
+        self.target
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the distance between the camera and its target.
+    pub const fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Recomputes the position, direction, right and up vectors
+    /// from the target, the distance, the yaw and the pitch.
+    fn update_vectors(&mut self) {
+        let (yaw_sin, yaw_cos) = self.yaw.to_radians().sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.to_radians().sin_cos();
+
+        self.direction = [yaw_cos * pitch_cos, pitch_sin, yaw_sin * pitch_cos];
+        self.right = [-yaw_sin, 0.0, yaw_cos];
+        self.up = cross(self.right, self.direction);
+        normalize(&mut self.up);
+
+        self.position =
+            std::array::from_fn(|i| (-self.distance).mul_add(self.direction[i], self.target[i]));
+    }
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            target: [0.0, 0.0, 0.0],
+            distance: 10.0,
+            yaw: 270.0,
+            pitch: 0.0,
+            speed: 10.0,
+            sensitivity: 0.03,
+            position: [0.0, 0.0, 10.0],
+            direction: [0.0, 0.0, -1.0],
+            up: [0.0, 1.0, 0.0],
+            right: [1.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl super::Camera for Orbit {
+    #[must_use]
+    #[inline]
+    fn direction(&self) -> [f32; 3] {
+        self.direction
+    }
+
+    #[must_use]
+    #[inline]
+    fn position(&self) -> [f32; 3] {
+        self.position
+    }
+
+    #[must_use]
+    #[inline]
+    fn up(&self) -> [f32; 3] {
+        self.up
+    }
+
+    #[must_use]
+    #[inline]
+    fn right(&self) -> [f32; 3] {
+        self.right
+    }
+
+    /// Orbits around the given point instead, see [`Orbit::set_target`].
+    ///
+    /// As the camera always faces its target, it moves to keep its distance and orientation.
+    fn look_at(&mut self, target: [f32; 3]) {
+        self.set_target(target);
+    }
+
+    /// Moves the camera around its target, the distance, yaw and pitch being recomputed
+    /// so that it still faces the target.
+    ///
+    /// The pitch and the distance are clamped as for the inputs,
+    /// and a position at the target is ignored.
+    fn set_position(&mut self, position: [f32; 3]) {
+        let mut direction = std::array::from_fn(|i| self.target[i] - position[i]);
+        let distance = length(direction);
+        if !distance.is_normal() {
+            return;
+        }
+        normalize(&mut direction);
+
+        self.distance = distance.max(MIN_DISTANCE);
+        self.pitch = direction[1]
+            .clamp(-1.0, 1.0)
+            .asin()
+            .to_degrees()
+            .clamp(-MAX_PITCH, MAX_PITCH);
+        self.yaw = direction[2].atan2(direction[0]).to_degrees();
+        self.update_vectors();
+    }
+
+    fn process_inputs(&mut self, inputs: Inputs, delta_seconds: f32) {
+        let inputs = Into::<Box<[Input]>>::into(inputs);
+        if inputs.is_empty() {
+            return;
+        }
+
+        let relative_speed = self.speed * delta_seconds;
+
+        for input in inputs {
+            match input {
+                Input::Forward => self.distance -= relative_speed,
+                Input::Backward => self.distance += relative_speed,
+                Input::Advance(value) => self.distance -= value * relative_speed,
+                Input::Yaw(value) => self.yaw -= value * self.sensitivity,
+                Input::Pitch(value) => self.pitch -= value * self.sensitivity,
+                Input::Left
+                | Input::Right
+                | Input::Up
+                | Input::Down
+                | Input::Roll(_)
+                | Input::Zoom(_)
+                | Input::Strafe(_) => {}
+            }
+        }
+
+        self.distance = self.distance.max(MIN_DISTANCE);
+        self.pitch = self.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+
+        self.update_vectors();
+    }
+}
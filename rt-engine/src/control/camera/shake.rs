@@ -0,0 +1,134 @@
+use std::f32::consts::TAU;
+
+#[derive(Debug, Clone, Copy)]
+/// Represents a procedural shake applied on top of the pose of the active camera,
+/// see [`crate::RayTracingApp::trigger_shake`].
+///
+/// The shake is driven by an intensity between 0 and 1, raised by the triggers and decaying over time.
+/// The camera is offset and turned by smooth noise, scaled by the square of the intensity,
+/// so that weak triggers barely move it. The cameras themselves are left untouched.
+pub struct CameraShake {
+    /// The largest offset of the camera along each of its axes, in world units.
+    translation: f32,
+    /// The largest rotation of the camera around each of its axes, in degrees.
+    rotation: f32,
+    /// The frequency of the noise, in hertz.
+    frequency: f32,
+    /// The intensity lost per second.
+    decay: f32,
+    /// The current intensity, between 0 and 1.
+    intensity: f32,
+    /// The time elapsed since the shake started, in seconds.
+    time: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self::new(0.05, 2.0, 15.0, 1.5)
+    }
+}
+
+impl CameraShake {
+    #[must_use]
+    /// Creates a shake offsetting the camera by up to `translation` world units
+    /// and turning it by up to `rotation` degrees along each axis, at the given frequency in hertz.
+    ///
+    /// A full intensity fades out in `1 / decay` seconds. Negative values are clamped to 0.
+    pub const fn new(translation: f32, rotation: f32, frequency: f32, decay: f32) -> Self {
+        Self {
+            translation: translation.max(0.0),
+            rotation: rotation.max(0.0),
+            frequency: frequency.max(0.0),
+            decay: decay.max(0.0),
+            intensity: 0.0,
+            time: 0.0,
+        }
+    }
+
+    /// Raises the intensity of the shake by the given amount, the intensity being capped at 1.
+    ///
+    /// Negative amounts calm the shake down.
+    pub fn trigger(&mut self, intensity: f32) {
+        if self.intensity <= 0.0 {
+            self.time = 0.0;
+        }
+        self.intensity = (self.intensity + intensity).clamp(0.0, 1.0);
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the current intensity of the shake, between 0 and 1.
+    pub const fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the shake currently moves the camera.
+    pub fn is_active(&self) -> bool {
+        self.intensity > 0.0
+    }
+
+    /// Advances the noise and decays the intensity by the given time.
+    pub(crate) fn advance(&mut self, delta_seconds: f32) {
+        if !self.is_active() {
+            return;
+        }
+        self.time += delta_seconds;
+        self.intensity = self.decay.mul_add(-delta_seconds, self.intensity).max(0.0);
+    }
+
+    /// Offsets and turns the given camera data by the current shake.
+    pub(crate) fn apply(&self, camera: &mut crate::shader::source::Camera) {
+        if !self.is_active() {
+            return;
+        }
+
+        let strength = self.intensity * self.intensity;
+        // Each axis reads the noise at its own phase, so that they move independently
+        let [dx, dy, dz, yaw, pitch, roll] =
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0].map(|axis: f32| strength * self.noise(axis * 17.3));
+
+        let mut view = *camera.view;
+        let mut up = *camera.up;
+        let mut right = camera.right;
+        let position: [f32; 3] = std::array::from_fn(|i| {
+            self.translation.mul_add(
+                right[i].mul_add(dx, up[i].mul_add(dy, view[i] * dz)),
+                camera.position[i],
+            )
+        });
+
+        let angle = |value: f32| (self.rotation * value).to_radians();
+        (view, right) = rotate(view, right, angle(yaw));
+        (view, up) = rotate(view, up, angle(pitch));
+        (right, up) = rotate(right, up, angle(roll));
+
+        camera.position = position.into();
+        camera.view = view.into();
+        camera.up = up.into();
+        camera.right = right;
+    }
+
+    #[must_use]
+    /// Returns smooth noise between -1 and 1 at the current time, shifted by the given phase.
+    fn noise(&self, phase: f32) -> f32 {
+        // Sines of incommensurate frequencies never line up, which hides their periodicity
+        let t = self.time * self.frequency * TAU;
+        let noise = 0.2_f32.mul_add(
+            t.mul_add(3.7, phase * 2.9).sin(),
+            0.5_f32.mul_add((t + phase).sin(), 0.3 * t.mul_add(2.3, phase * 1.3).sin()),
+        );
+        noise.clamp(-1.0, 1.0)
+    }
+}
+
+#[must_use]
+/// Rotates the orthonormal vectors `a` and `b` by the given angle in their plane, from `a` towards `b`.
+fn rotate(a: [f32; 3], b: [f32; 3], angle: f32) -> ([f32; 3], [f32; 3]) {
+    let (sin, cos) = angle.sin_cos();
+    (
+        std::array::from_fn(|i| a[i].mul_add(cos, b[i] * sin)),
+        std::array::from_fn(|i| b[i].mul_add(cos, -a[i] * sin)),
+    )
+}
@@ -6,9 +6,13 @@
 //! Add it to the list of controllers in the main app struct and it will be
 //! automatically handled by the event loop.
 
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
 pub mod keyboard;
 pub mod motion_device;
 pub mod mouse;
+pub mod replay;
+pub mod touch;
 
 /// Represents a controller.
 ///
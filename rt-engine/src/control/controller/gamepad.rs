@@ -0,0 +1,187 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use super::super::{Input, Inputs};
+
+#[derive(Debug)]
+/// Represents the state of the gamepads connected to the system.
+///
+/// The left stick moves the camera, at a speed proportional to its tilt past the deadzone,
+/// the right stick rotates it, and the right and left triggers move it up and down.
+///
+/// As gamepad events do not go through `winit`, they are polled once per frame,
+/// when `winit::event::Event::MainEventsCleared` is handled.
+pub struct Gamepad {
+    /// The context used to poll the gamepad events.
+    gilrs: Gilrs,
+    /// The position of the left stick, from -1.0 to 1.0 on each axis.
+    left_stick: [f32; 2],
+    /// The position of the right stick, from -1.0 to 1.0 on each axis.
+    right_stick: [f32; 2],
+    /// The values of the left and right triggers, from 0.0 to 1.0.
+    triggers: [f32; 2],
+    /// The stick and trigger values below which the gamepad is considered at rest.
+    deadzone: f32,
+    /// The rotation given by a fully tilted right stick, in mouse pixels per second.
+    sensitivity: f32,
+    /// The instant the inputs were last fetched, used to scale the rotation by the frame time.
+    last_fetch: std::time::Instant,
+}
+
+impl Gamepad {
+    /// Creates a new gamepad controller, handling every connected gamepad.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if gamepads are not supported on the platform.
+    pub fn new() -> Result<Self, gilrs::Error> {
+        Ok(Self {
+            gilrs: Gilrs::new()?,
+            left_stick: [0.0; 2],
+            right_stick: [0.0; 2],
+            triggers: [0.0; 2],
+            deadzone: 0.15,
+            sensitivity: 600.0,
+            last_fetch: std::time::Instant::now(),
+        })
+    }
+
+    #[inline]
+    /// Sets the stick and trigger values below which the gamepad is considered at rest.
+    pub fn set_deadzone(&mut self, deadzone: f32) {
+        self.deadzone = deadzone.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    /// Sets the rotation given by a fully tilted right stick, in mouse pixels per second.
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the given stick or trigger value, or 0.0 if it is inside the deadzone.
+    fn filter(&self, value: f32) -> f32 {
+        if value.abs() < self.deadzone {
+            0.0
+        } else {
+            value
+        }
+    }
+}
+
+#[must_use]
+/// Returns the inputs moving the camera for the given position of the left stick.
+///
+/// Each axis is rescaled so that the edge of the deadzone gives no movement and a full tilt the full speed.
+fn movement_inputs(left_stick: [f32; 2], deadzone: f32) -> Inputs {
+    let [x, y] = left_stick.map(|value| rescale(value, deadzone));
+
+    let mut inputs = Inputs::default();
+    if y != 0.0 {
+        inputs.accumulate(Input::Advance(y).into());
+    }
+    if x != 0.0 {
+        inputs.accumulate(Input::Strafe(x).into());
+    }
+    inputs
+}
+
+#[must_use]
+/// Returns the given stick value rescaled from `[deadzone, 1.0]` to `[0.0, 1.0]`, keeping its sign,
+/// or 0.0 if it is inside the deadzone.
+fn rescale(value: f32, deadzone: f32) -> f32 {
+    if value.abs() < deadzone || deadzone >= 1.0 {
+        return 0.0;
+    }
+    ((value.abs() - deadzone) / (1.0 - deadzone))
+        .min(1.0)
+        .copysign(value)
+}
+
+impl super::Controller for Gamepad {
+    fn handle_event(&mut self, event: &winit::event::Event<()>) {
+        if !matches!(event, winit::event::Event::MainEventsCleared) {
+            return;
+        }
+
+        while let Some(gilrs::Event { event, .. }) = self.gilrs.next_event() {
+            match event {
+                EventType::AxisChanged(axis, value, _) => match axis {
+                    Axis::LeftStickX => self.left_stick[0] = value,
+                    Axis::LeftStickY => self.left_stick[1] = value,
+                    Axis::RightStickX => self.right_stick[0] = value,
+                    Axis::RightStickY => self.right_stick[1] = value,
+                    _ => {}
+                },
+                EventType::ButtonChanged(button, value, _) => match button {
+                    Button::LeftTrigger2 => self.triggers[0] = value,
+                    Button::RightTrigger2 => self.triggers[1] = value,
+                    _ => {}
+                },
+                EventType::Disconnected => {
+                    tracing::debug!("Gamepad disconnected");
+                    self.left_stick = [0.0; 2];
+                    self.right_stick = [0.0; 2];
+                    self.triggers = [0.0; 2];
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[must_use]
+    fn fetch_input(&mut self) -> Inputs {
+        let elapsed = self.last_fetch.elapsed().as_secs_f32();
+        self.last_fetch = std::time::Instant::now();
+
+        let mut inputs = Inputs::default();
+
+        inputs.accumulate(movement_inputs(self.left_stick, self.deadzone));
+
+        let [down, up] = self.triggers.map(|value| self.filter(value));
+        if up > 0.0 {
+            inputs.accumulate(Input::Up.into());
+        }
+        if down > 0.0 {
+            inputs.accumulate(Input::Down.into());
+        }
+
+        // Same conventions as the mouse, whose vertical axis points down
+        let [x, y] = self.right_stick.map(|value| self.filter(value));
+        let yaw = -x * self.sensitivity * elapsed;
+        let pitch = -y * self.sensitivity * elapsed;
+        if yaw != 0.0 {
+            inputs.accumulate(Input::Yaw(yaw).into());
+        }
+        if pitch != 0.0 {
+            inputs.accumulate(Input::Pitch(pitch).into());
+        }
+
+        inputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::camera::{free::Free, Camera};
+
+    #[test]
+    /// A partially tilted stick moves the camera proportionally to its tilt past the deadzone.
+    fn partial_tilt_gives_proportional_movement() {
+        // The camera faces -Z, so moving forward decreases its Z coordinate
+        let distance = |stick: [f32; 2]| {
+            let mut camera = Free::default();
+            camera.process_inputs(movement_inputs(stick, 0.2), 1.0);
+            -camera.position()[2]
+        };
+
+        let full = distance([0.0, 1.0]);
+        assert!(full > 0.0);
+        assert!((distance([0.0, 0.6]) - full * 0.5).abs() < 1e-4);
+        assert!((distance([0.0, -0.4]) + full * 0.25).abs() < 1e-4);
+        // Inside the deadzone, and across its edge, the camera does not jump
+        assert!(distance([0.0, 0.1]).abs() < f32::EPSILON);
+        assert!(distance([0.0, 0.21]) < full * 0.02);
+    }
+}
@@ -1,8 +1,66 @@
+use std::collections::{HashMap, HashSet};
+
+use winit::event::VirtualKeyCode;
+
 use super::super::{Input, Inputs};
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 /// Represents the state of a keyboard.
-pub struct Keyboard(Inputs);
+pub struct Keyboard {
+    /// The bound keys currently held.
+    ///
+    /// The inputs are rebuilt from them at every fetch, so that releasing a key
+    /// neither releases an input still held by another key nor alters the analog values.
+    pressed: HashSet<VirtualKeyCode>,
+    /// The inputs fired by each key.
+    bindings: HashMap<VirtualKeyCode, Inputs>,
+}
+
+impl Keyboard {
+    #[must_use]
+    /// Creates a keyboard using the given key bindings.
+    ///
+    /// Several keys can be bound to the same input.
+    /// To bind a key to several inputs, use [`Keyboard::bind`].
+    pub fn with_bindings(bindings: HashMap<VirtualKeyCode, Input>) -> Self {
+        Self {
+            pressed: HashSet::new(),
+            bindings: bindings
+                .into_iter()
+                .map(|(key, input)| (key, input.into()))
+                .collect(),
+        }
+    }
+
+    /// Binds the given key to the given input, in addition to the inputs it is already bound to.
+    pub fn bind(&mut self, key: VirtualKeyCode, input: Input) {
+        self.bindings
+            .entry(key)
+            .or_default()
+            .accumulate(input.into());
+    }
+
+    /// Removes all the bindings of the given key.
+    pub fn unbind(&mut self, key: VirtualKeyCode) {
+        self.bindings.remove(&key);
+        self.pressed.remove(&key);
+    }
+}
+
+impl Default for Keyboard {
+    /// Creates a keyboard with the default AZERTY layout:
+    /// ZQSD to move, Space to go up and Left Shift to go down.
+    fn default() -> Self {
+        Self::with_bindings(HashMap::from([
+            (VirtualKeyCode::Z, Input::Forward),
+            (VirtualKeyCode::Q, Input::Left),
+            (VirtualKeyCode::S, Input::Backward),
+            (VirtualKeyCode::D, Input::Right),
+            (VirtualKeyCode::Space, Input::Up),
+            (VirtualKeyCode::LShift, Input::Down),
+        ]))
+    }
+}
 
 impl super::Controller for Keyboard {
     fn handle_event(&mut self, event: &winit::event::Event<()>) {
@@ -20,27 +78,24 @@ impl super::Controller for Keyboard {
             ..
         } = event
         {
-            // TODO: Personalize key bindings.
-            let mask = match key {
-                winit::event::VirtualKeyCode::Z => Input::Forward,
-                winit::event::VirtualKeyCode::Q => Input::Left,
-                winit::event::VirtualKeyCode::S => Input::Backward,
-                winit::event::VirtualKeyCode::D => Input::Right,
-                winit::event::VirtualKeyCode::Space => Input::Up,
-                winit::event::VirtualKeyCode::LShift => Input::Down,
-                _ => return,
-            };
+            if !self.bindings.contains_key(key) {
+                return;
+            }
 
+            // Key repeats insert the key again, which has no effect
             match state {
-                winit::event::ElementState::Pressed => self.0.accumulate(mask.into()),
-                winit::event::ElementState::Released => self.0.deccumulate(mask.into()),
-            }
+                winit::event::ElementState::Pressed => self.pressed.insert(*key),
+                winit::event::ElementState::Released => self.pressed.remove(key),
+            };
         }
     }
 
     #[must_use]
-    #[inline]
     fn fetch_input(&mut self) -> Inputs {
-        self.0
+        let mut inputs = Inputs::default();
+        for key in &self.pressed {
+            inputs.accumulate(self.bindings[key]);
+        }
+        inputs
     }
 }
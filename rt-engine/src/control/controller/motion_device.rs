@@ -1,9 +1,93 @@
 use super::super::{Input, Inputs};
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug)]
 /// Represents the state of a motion device.
 /// This includes the mouse, as well as the joystick of a gamepad.
-pub struct MotionDevice(f32, f32);
+pub struct MotionDevice {
+    /// The factor applied to the values of the axes.
+    sensitivity: f32,
+    /// Whether the horizontal motion is reversed.
+    invert_x: bool,
+    /// Whether the vertical motion is reversed.
+    invert_y: bool,
+    /// The yaw accumulated since the last fetch.
+    yaw: f32,
+    /// The pitch accumulated since the last fetch.
+    pitch: f32,
+}
+
+impl Default for MotionDevice {
+    fn default() -> Self {
+        Self::new(1.0, false, false)
+    }
+}
+
+impl MotionDevice {
+    #[must_use]
+    /// Creates a controller scaling its deltas by `sensitivity`,
+    /// and reversing the yaw and the pitch if `invert_x` and `invert_y` are set.
+    ///
+    /// The default has a sensitivity of 1 and no inversion.
+    pub const fn new(sensitivity: f32, invert_x: bool, invert_y: bool) -> Self {
+        Self {
+            sensitivity,
+            invert_x,
+            invert_y,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the factor applied to the values of the axes of the device.
+    pub const fn sensitivity(&self) -> f32 {
+        self.sensitivity
+    }
+
+    #[inline]
+    /// Sets the factor applied to the values of the axes of the device.
+    pub const fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the horizontal motion is reversed.
+    pub const fn invert_x(&self) -> bool {
+        self.invert_x
+    }
+
+    #[inline]
+    /// Sets whether the horizontal motion is reversed.
+    pub const fn set_invert_x(&mut self, invert_x: bool) {
+        self.invert_x = invert_x;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the vertical motion is reversed.
+    pub const fn invert_y(&self) -> bool {
+        self.invert_y
+    }
+
+    #[inline]
+    /// Sets whether the vertical motion is reversed.
+    pub const fn set_invert_y(&mut self, invert_y: bool) {
+        self.invert_y = invert_y;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the yaw and the pitch given by the motion of the device, with the sensitivity and inversion applied.
+    fn rotation(&self, delta_x: f32, delta_y: f32) -> (f32, f32) {
+        let sign = |inverted: bool| if inverted { -1.0 } else { 1.0 };
+        (
+            -delta_x * self.sensitivity * sign(self.invert_x),
+            delta_y * self.sensitivity * sign(self.invert_y),
+        )
+    }
+}
 
 impl super::Controller for MotionDevice {
     fn handle_event(&mut self, event: &winit::event::Event<()>) {
@@ -12,11 +96,11 @@ impl super::Controller for MotionDevice {
             ..
         } = event
         {
+            #[allow(clippy::cast_possible_truncation)]
+            let value = *value as f32;
             match axis {
-                #[allow(clippy::cast_possible_truncation)]
-                0 => self.0 -= *value as f32,
-                #[allow(clippy::cast_possible_truncation)]
-                1 => self.1 += *value as f32,
+                0 => self.yaw += self.rotation(value, 0.0).0,
+                1 => self.pitch += self.rotation(0.0, value).1,
                 _ => unreachable!("Unexpected axis"),
             }
         }
@@ -24,8 +108,8 @@ impl super::Controller for MotionDevice {
 
     #[must_use]
     fn fetch_input(&mut self) -> Inputs {
-        let yaw = core::mem::take(&mut self.0);
-        let pitch = core::mem::take(&mut self.1);
+        let yaw = core::mem::take(&mut self.yaw);
+        let pitch = core::mem::take(&mut self.pitch);
 
         let mut inputs = Inputs::default();
         if yaw != 0.0 {
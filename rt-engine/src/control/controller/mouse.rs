@@ -1,32 +1,139 @@
 use super::super::{Input, Inputs};
 
-#[derive(Copy, Clone, Debug, Default)]
+/// The number of pixels of a precise scroll, such as the one of a touchpad, equivalent to a scroll line.
+const PIXELS_PER_LINE: f64 = 20.0;
+
+#[derive(Copy, Clone, Debug)]
 /// Represents the state of a motion device.
 /// This includes the mouse, as well as the joystick of a gamepad.
-pub struct Mouse(f32, f32);
+///
+/// The scroll wheel zooms, its deltas being converted to lines.
+/// The sensitivity and the inversion only apply to the motion, not to the scroll wheel.
+pub struct Mouse {
+    /// The factor applied to the deltas of the mouse.
+    sensitivity: f32,
+    /// Whether the horizontal motion is reversed.
+    invert_x: bool,
+    /// Whether the vertical motion is reversed.
+    invert_y: bool,
+    /// The yaw accumulated since the last fetch.
+    yaw: f32,
+    /// The pitch accumulated since the last fetch.
+    pitch: f32,
+    /// The zoom accumulated since the last fetch, in lines.
+    zoom: f32,
+}
+
+impl Default for Mouse {
+    fn default() -> Self {
+        Self::new(1.0, false, false)
+    }
+}
+
+impl Mouse {
+    #[must_use]
+    /// Creates a controller scaling its deltas by `sensitivity`,
+    /// and reversing the yaw and the pitch if `invert_x` and `invert_y` are set.
+    ///
+    /// The default has a sensitivity of 1 and no inversion.
+    pub const fn new(sensitivity: f32, invert_x: bool, invert_y: bool) -> Self {
+        Self {
+            sensitivity,
+            invert_x,
+            invert_y,
+            yaw: 0.0,
+            pitch: 0.0,
+            zoom: 0.0,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the factor applied to the deltas of the device.
+    pub const fn sensitivity(&self) -> f32 {
+        self.sensitivity
+    }
+
+    #[inline]
+    /// Sets the factor applied to the deltas of the device.
+    pub const fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the horizontal motion is reversed.
+    pub const fn invert_x(&self) -> bool {
+        self.invert_x
+    }
+
+    #[inline]
+    /// Sets whether the horizontal motion is reversed.
+    pub const fn set_invert_x(&mut self, invert_x: bool) {
+        self.invert_x = invert_x;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the vertical motion is reversed.
+    pub const fn invert_y(&self) -> bool {
+        self.invert_y
+    }
+
+    #[inline]
+    /// Sets whether the vertical motion is reversed.
+    pub const fn set_invert_y(&mut self, invert_y: bool) {
+        self.invert_y = invert_y;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the yaw and the pitch given by the motion of the device, with the sensitivity and inversion applied.
+    fn rotation(&self, delta_x: f32, delta_y: f32) -> (f32, f32) {
+        let sign = |inverted: bool| if inverted { -1.0 } else { 1.0 };
+        (
+            -delta_x * self.sensitivity * sign(self.invert_x),
+            delta_y * self.sensitivity * sign(self.invert_y),
+        )
+    }
+}
 
 impl super::Controller for Mouse {
+    #[allow(clippy::cast_possible_truncation)]
     fn handle_event(&mut self, event: &winit::event::Event<()>) {
-        if let winit::event::Event::DeviceEvent {
-            event:
-                winit::event::DeviceEvent::MouseMotion {
-                    delta: (delta_x, delta_y),
-                },
-            ..
-        } = event
-        {
-            #[allow(clippy::cast_possible_truncation)]
-            {
-                self.0 -= *delta_x as f32;
-                self.1 += *delta_y as f32;
+        match event {
+            winit::event::Event::DeviceEvent {
+                event:
+                    winit::event::DeviceEvent::MouseMotion {
+                        delta: (delta_x, delta_y),
+                    },
+                ..
+            } => {
+                let (yaw, pitch) = self.rotation(*delta_x as f32, *delta_y as f32);
+                self.yaw += yaw;
+                self.pitch += pitch;
+            }
+            winit::event::Event::WindowEvent {
+                event: winit::event::WindowEvent::MouseWheel { delta, .. },
+                ..
+            } => {
+                // Wheels scroll by lines, while touchpads scroll by pixels on some platforms
+                self.zoom += match delta {
+                    winit::event::MouseScrollDelta::LineDelta(_, lines) => *lines,
+                    winit::event::MouseScrollDelta::PixelDelta(position) => {
+                        (position.y / PIXELS_PER_LINE) as f32
+                    }
+                };
             }
+            _ => {}
         }
     }
 
     #[must_use]
     fn fetch_input(&mut self) -> Inputs {
-        let yaw = core::mem::take(&mut self.0);
-        let pitch = core::mem::take(&mut self.1);
+        let yaw = core::mem::take(&mut self.yaw);
+        let pitch = core::mem::take(&mut self.pitch);
+        let zoom = core::mem::take(&mut self.zoom);
 
         let mut inputs = Inputs::default();
         if yaw != 0.0 {
@@ -35,6 +142,9 @@ impl super::Controller for Mouse {
         if pitch != 0.0 {
             inputs.accumulate(Input::Pitch(pitch).into());
         }
+        if zoom != 0.0 {
+            inputs.accumulate(Input::Zoom(zoom).into());
+        }
 
         inputs
     }
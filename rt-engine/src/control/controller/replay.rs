@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use super::super::{Input, Inputs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Represents when a scripted input of a [`Replay`] is emitted.
+pub enum ReplayTime {
+    /// The input is emitted by the fetch of the given frame, the first fetch being frame 0.
+    ///
+    /// As the inputs are fetched once per frame, this is deterministic.
+    Frame(u64),
+    /// The input is emitted by the first fetch at least the given time after the first fetch.
+    ///
+    /// The frame the input lands on depends on the frame rate.
+    Time(Duration),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Represents what a [`Replay`] does once its last input is emitted.
+pub enum ReplayEnd {
+    #[default]
+    /// No input is emitted anymore.
+    Stop,
+    /// The script starts over, right after the frame or time of its last input.
+    Loop,
+}
+
+#[derive(Debug, Clone)]
+/// Represents a controller emitting scripted inputs, regardless of the events of the window.
+///
+/// Combined with a fixed seed, see [`crate::shader::ShaderDescriptor::seed`], frame-based scripts
+/// make whole sessions reproducible, for demos, benchmarks or smoke tests.
+///
+/// Each input is only emitted for the frame it is scheduled on: digital inputs, such as
+/// [`Input::Forward`], must be scheduled on every frame they are held for.
+pub struct Replay {
+    /// The scripted inputs, sorted by time.
+    script: Vec<(ReplayTime, Input)>,
+    /// What happens once the last input is emitted.
+    end: ReplayEnd,
+    /// The index of the next input of the script.
+    next: usize,
+    /// The index of the next fetch since the start of the current run of the script.
+    frame: u64,
+    /// The time of the first fetch of the current run of the script.
+    start: Option<Instant>,
+}
+
+impl Replay {
+    #[must_use]
+    /// Creates a controller emitting the given inputs at their frame or time.
+    ///
+    /// The inputs may be given in any order, those scheduled at the same frame or time being accumulated.
+    /// Scripts should schedule all their inputs either by frame or by time:
+    /// the inputs scheduled by time are only emitted once those scheduled by frame have been.
+    pub fn new(mut script: Vec<(ReplayTime, Input)>, end: ReplayEnd) -> Self {
+        // The sort is stable, which keeps the order of the inputs scheduled together
+        script.sort_by_key(|(time, _)| *time);
+
+        Self {
+            script,
+            end,
+            next: 0,
+            frame: 0,
+            start: None,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether every input has been emitted, which never happens when looping.
+    pub fn is_finished(&self) -> bool {
+        self.end == ReplayEnd::Stop && self.next >= self.script.len()
+    }
+
+    /// Starts the script over from its first input.
+    pub const fn restart(&mut self) {
+        self.next = 0;
+        self.frame = 0;
+        self.start = None;
+    }
+
+    #[must_use]
+    /// Returns whether the given time of the script has been reached by the current fetch.
+    fn is_due(&self, time: ReplayTime, now: Instant) -> bool {
+        match time {
+            ReplayTime::Frame(frame) => frame <= self.frame,
+            ReplayTime::Time(time) => self
+                .start
+                .is_some_and(|start| now.duration_since(start) >= time),
+        }
+    }
+}
+
+impl super::Controller for Replay {
+    #[inline]
+    fn handle_event(&mut self, _event: &winit::event::Event<()>) {}
+
+    #[must_use]
+    fn fetch_input(&mut self) -> Inputs {
+        let now = Instant::now();
+        if self.end == ReplayEnd::Loop && self.next >= self.script.len() && !self.script.is_empty()
+        {
+            // The previous run ended with its last input, which was due at the previous fetch
+            self.restart();
+            tracing::trace!("Replay started over");
+        }
+        self.start.get_or_insert(now);
+
+        let mut inputs = Inputs::default();
+        while let Some(&(time, input)) = self.script.get(self.next) {
+            if !self.is_due(time, now) {
+                break;
+            }
+            inputs.accumulate(input.into());
+            self.next += 1;
+        }
+
+        self.frame += 1;
+        inputs
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use winit::event::TouchPhase;
+
+use super::super::{Input, Inputs};
+
+#[derive(Clone, Debug, Default)]
+/// Represents the state of a touch screen or trackpad.
+///
+/// Dragging a single finger rotates the camera, like the mouse,
+/// and pinching two fingers moves it forward or backward.
+pub struct Touch {
+    /// The last position of each finger on the surface, by touch identifier.
+    fingers: HashMap<u64, [f32; 2]>,
+    /// The yaw accumulated since the last fetch.
+    yaw: f32,
+    /// The pitch accumulated since the last fetch.
+    pitch: f32,
+    /// The change of the distance between two fingers since the last fetch.
+    pinch: f32,
+}
+
+impl Touch {
+    #[must_use]
+    /// Returns the distance between the two fingers on the surface, if there are exactly two.
+    fn finger_distance(&self) -> Option<f32> {
+        let mut positions = self.fingers.values();
+        match (positions.next(), positions.next(), positions.next()) {
+            (Some(a), Some(b), None) => Some((a[0] - b[0]).hypot(a[1] - b[1])),
+            _ => None,
+        }
+    }
+}
+
+impl super::Controller for Touch {
+    fn handle_event(&mut self, event: &winit::event::Event<()>) {
+        let winit::event::Event::WindowEvent {
+            event: winit::event::WindowEvent::Touch(touch),
+            ..
+        } = event
+        else {
+            return;
+        };
+
+        #[allow(clippy::cast_possible_truncation)]
+        let position = [touch.location.x as f32, touch.location.y as f32];
+
+        match touch.phase {
+            TouchPhase::Started => {
+                self.fingers.insert(touch.id, position);
+            }
+            TouchPhase::Moved => {
+                let distance_before = self.finger_distance();
+                let Some(previous) = self.fingers.insert(touch.id, position) else {
+                    return;
+                };
+
+                if self.fingers.len() == 1 {
+                    // Same conventions as the mouse
+                    self.yaw -= position[0] - previous[0];
+                    self.pitch += position[1] - previous[1];
+                } else if let (Some(before), Some(after)) =
+                    (distance_before, self.finger_distance())
+                {
+                    self.pinch += after - before;
+                }
+            }
+            // A lifted finger no longer contributes to the motion
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.fingers.remove(&touch.id);
+            }
+        }
+    }
+
+    #[must_use]
+    fn fetch_input(&mut self) -> Inputs {
+        let yaw = core::mem::take(&mut self.yaw);
+        let pitch = core::mem::take(&mut self.pitch);
+        let spread = core::mem::take(&mut self.pinch);
+
+        let mut inputs = Inputs::default();
+        if yaw != 0.0 {
+            inputs.accumulate(Input::Yaw(yaw).into());
+        }
+        if pitch != 0.0 {
+            inputs.accumulate(Input::Pitch(pitch).into());
+        }
+        // Spreading the fingers zooms in
+        if spread > 0.0 {
+            inputs.accumulate(Input::Forward.into());
+        } else if spread < 0.0 {
+            inputs.accumulate(Input::Backward.into());
+        }
+
+        inputs
+    }
+}
@@ -12,6 +12,7 @@
 //! - Model Loading
 //! - Window Rendering
 //! - Image Rendering
+//! - Video Rendering
 
 #![deny(unsafe_code)]
 #![warn(clippy::pedantic, clippy::nursery)]
@@ -54,12 +55,13 @@
     clippy::wildcard_imports
 )]
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 #[cfg(feature = "image")]
 use render::image::{Image, ImageDescriptor};
 
-use render::{window::WindowDescriptor, Buffers, RenderSurface, Renderer};
+use render::video::{VideoDescriptor, VideoSurface};
+use render::{window::WindowDescriptor, Buffers, RenderError, RenderSurface, Renderer};
 use vulkano::{
     command_buffer::allocator::{
         StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo,
@@ -71,10 +73,10 @@ use vulkano::{
         physical::{PhysicalDevice, PhysicalDeviceType},
         Device, DeviceExtensions, Features, Queue, QueueCreateInfo,
     },
-    instance::{Instance, InstanceCreateInfo},
+    instance::{Instance, InstanceCreateInfo, InstanceExtensions},
     memory::allocator::StandardMemoryAllocator,
     swapchain::Surface,
-    VulkanLibrary,
+    Validated, VulkanLibrary,
 };
 
 /// Handles everything related to the camera.
@@ -88,8 +90,240 @@ pub mod shader;
 /// Utils to handle staging buffers.
 mod buffer;
 
+/// Re-export of `vulkano`, so that users can name its types without depending on it.
+pub use vulkano;
+
+#[cfg(feature = "logging")]
+/// Installs a global subscriber printing the events of the engine
+/// up to the given level to the standard output.
+///
+/// The engine never installs a subscriber by itself,
+/// so this is only a convenience for applications without their own logging setup.
+///
+/// ## Panics
+///
+/// This function panics if a global subscriber has already been installed.
+pub fn init_tracing(level: tracing::Level) {
+    tracing_subscriber::fmt().with_max_level(level).init();
+}
+
+#[derive(Debug)]
+/// Represents an error that occured while initializing the application.
+pub enum InitError {
+    /// The Vulkan library could not be loaded, typically because no Vulkan driver is installed.
+    VulkanLibraryLoad(vulkano::LoadingError),
+    /// No physical device can render to the render surface,
+    /// the rejected devices being given along with the reason of their rejection.
+    NoSuitableDevice(Vec<RejectedDevice>),
+    /// No physical device matches the index or the name of the given preference.
+    DeviceNotFound(DevicePreference),
+    /// The Vulkan library does not support the instance extensions required by the render surface.
+    UnsupportedExtensions,
+    /// The render surface could not be created, such as a window, its Vulkan surface or the encoder of a video.
+    SurfaceCreation,
+    /// The render surface type is [`RenderSurfaceType::Custom`], whose surface must be given
+    /// through [`RayTracingApp::try_with_surface`] instead.
+    CustomSurface,
+    /// The shader has no entry point with the given name, see [`shader::ShaderDescriptor::entry_point`].
+    EntryPointNotFound(String),
+    /// The model at the given path could not be loaded, for the given reason.
+    ModelLoad(PathBuf, shader::model::ModelLoadError),
+    /// The model at the given path references a material index that is out of range.
+    MaterialOutOfRange(PathBuf, u32),
+    /// The instance at the given index references a model index that is out of range.
+    InstanceOutOfRange(usize, usize),
+    /// The index of the active camera is out of range, given along with the number of cameras.
+    CameraOutOfRange(usize, usize),
+    /// The environment map at the given path could not be loaded.
+    EnvironmentLoad(PathBuf),
+    /// The texture at the given path could not be loaded.
+    TextureLoad(PathBuf),
+    /// The material at the given index references a texture index that is out of range.
+    TextureOutOfRange(usize, i32),
+    /// A frame rendered during the initialization failed for a reason other than a Vulkan error.
+    Render(RenderError),
+    /// Any other Vulkan error.
+    Vulkan(vulkano::VulkanError),
+}
+
+impl From<vulkano::VulkanError> for InitError {
+    fn from(error: vulkano::VulkanError) -> Self {
+        Self::Vulkan(error)
+    }
+}
+
+impl From<RenderError> for InitError {
+    fn from(error: RenderError) -> Self {
+        match error {
+            RenderError::OutOfDate => Self::Vulkan(vulkano::VulkanError::OutOfDate),
+            RenderError::DeviceLost => Self::Vulkan(vulkano::VulkanError::DeviceLost),
+            RenderError::Vulkan(error) => Self::Vulkan(error),
+            error => Self::Render(error),
+        }
+    }
+}
+
+impl std::fmt::Display for InitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::VulkanLibraryLoad(error) => write!(f, "failed to load Vulkan library: {error}"),
+            Self::NoSuitableDevice(rejected) => {
+                f.write_str("failed to find a suitable physical device")?;
+                for (i, device) in rejected.iter().enumerate() {
+                    let separator = if i == 0 { ": " } else { ", " };
+                    write!(f, "{separator}{} ({})", device.name, device.reason)?;
+                }
+                Ok(())
+            }
+            Self::DeviceNotFound(DevicePreference::Index(index)) => {
+                write!(f, "requested physical device {index} does not exist")
+            }
+            Self::DeviceNotFound(DevicePreference::Name(name)) => {
+                write!(f, "requested physical device \"{name}\" does not exist")
+            }
+            Self::DeviceNotFound(_) => f.write_str("requested physical device does not exist"),
+            Self::UnsupportedExtensions => {
+                f.write_str("Vulkan library does not support required extensions")
+            }
+            Self::SurfaceCreation => f.write_str("failed to create the render surface"),
+            Self::CustomSurface => f.write_str(
+                "custom render surfaces must be given through `RayTracingApp::try_with_surface`",
+            ),
+            Self::EntryPointNotFound(name) => {
+                write!(f, "shader has no entry point named `{name}`")
+            }
+            Self::ModelLoad(path, error) => {
+                write!(f, "failed to load model {}: {error}", path.display())
+            }
+            Self::MaterialOutOfRange(path, index) => write!(
+                f,
+                "material {index} of model {} is out of range",
+                path.display()
+            ),
+            Self::InstanceOutOfRange(instance, model) => {
+                write!(f, "model {model} of instance {instance} is out of range")
+            }
+            Self::CameraOutOfRange(index, count) => write!(
+                f,
+                "active camera index {index} out of range, {count} cameras given"
+            ),
+            Self::EnvironmentLoad(path) => {
+                write!(f, "failed to load environment map {}", path.display())
+            }
+            Self::TextureLoad(path) => write!(f, "failed to load texture {}", path.display()),
+            Self::TextureOutOfRange(material, index) => {
+                write!(f, "texture {index} of material {material} is out of range")
+            }
+            Self::Render(error) => write!(f, "failed to render a frame: {error}"),
+            Self::Vulkan(error) => write!(f, "a Vulkan error occured: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for InitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::VulkanLibraryLoad(error) => Some(error),
+            Self::ModelLoad(_, error) => Some(error),
+            Self::Render(error) => Some(error),
+            Self::Vulkan(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Represents a physical device rejected when creating the context.
+pub struct RejectedDevice {
+    /// The name of the device.
+    pub name: String,
+    /// The reason why the device was rejected.
+    pub reason: DeviceRejection,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Represents the reason why a physical device cannot be used.
+pub enum DeviceRejection {
+    /// The device does not support the extensions of the given names, required by the render surface.
+    MissingExtensions(Vec<&'static str>),
+    /// The device has no queue family supporting compute operations.
+    NoComputeQueue,
+}
+
+impl std::fmt::Display for DeviceRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingExtensions(extensions) => {
+                write!(f, "missing extensions {}", extensions.join(", "))
+            }
+            Self::NoComputeQueue => f.write_str("no compute queue"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Represents the physical device the application should render on.
+pub enum DevicePreference {
+    /// The device at the given index, among the devices listed by [`RayTracingApp::enumerate_devices`].
+    Index(usize),
+    /// The first device whose name contains the given string, ignoring case.
+    Name(String),
+    #[default]
+    /// A discrete GPU if one is suitable, then an integrated one.
+    PreferDiscrete,
+    /// An integrated GPU if one is suitable, then a discrete one.
+    ///
+    /// It typically saves battery on laptops, at the cost of performance.
+    PreferIntegrated,
+}
+
+impl DevicePreference {
+    #[must_use]
+    /// Returns the rank of the given type of device, the suitable device of lowest rank being used.
+    ///
+    /// Software devices are always the last resort.
+    const fn rank(&self, device_type: PhysicalDeviceType) -> u8 {
+        let (discrete, integrated) = match self {
+            Self::PreferIntegrated => (2, 1),
+            _ => (1, 2),
+        };
+        match device_type {
+            PhysicalDeviceType::DiscreteGpu => discrete,
+            PhysicalDeviceType::IntegratedGpu => integrated,
+            PhysicalDeviceType::VirtualGpu => 3,
+            PhysicalDeviceType::Cpu => 4,
+            PhysicalDeviceType::Other => 5,
+            _ => 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Describes a physical device, as listed by [`RayTracingApp::enumerate_devices`].
+pub struct DeviceInfo {
+    /// The index of the device, to be given to [`DevicePreference::Index`].
+    pub index: usize,
+    /// The name of the device.
+    pub name: String,
+    /// The type of the device.
+    pub device_type: PhysicalDeviceType,
+    /// The version of Vulkan supported by the device.
+    pub api_version: vulkano::Version,
+    /// The reason why the device cannot render to a window, if any.
+    ///
+    /// Devices missing extensions may still render to images and videos.
+    pub rejection: Option<DeviceRejection>,
+}
+
+/// A logical device, along with its compute queue and its transfer queue.
+type DeviceQueues = (Arc<Device>, Arc<Queue>, Arc<Queue>);
+
+#[derive(Clone)]
 /// Represents the context of the ray tracing application.
-struct Context {
+///
+/// It is given to the closure creating a custom render surface,
+/// see [`RayTracingApp::try_with_surface`].
+pub struct Context {
     /// The Vulkan device.
     device: Arc<Device>,
     /// The compute queue.
@@ -102,16 +336,25 @@ struct Context {
     descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
     /// The command buffer allocator.
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    /// The anisotropy level of texture samplers, if anisotropic filtering is enabled.
+    sampler_anisotropy: Option<f32>,
 }
 
 impl Context {
-    #[must_use]
     /// Creates a new context for the ray tracing application.
-    pub fn new(
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if Vulkan is unavailable or if no device is suitable.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if a window is rendered without an event loop.
+    pub(crate) fn new(
         config: &RayTracingAppConfig,
         event_loop: Option<&winit::event_loop::EventLoop<()>>,
-    ) -> Self {
-        let library = VulkanLibrary::new().expect("failed to load Vulkan library");
+    ) -> Result<Self, InitError> {
+        let library = VulkanLibrary::new().map_err(InitError::VulkanLibraryLoad)?;
 
         tracing::debug!("Vulkan library loaded");
 
@@ -119,32 +362,126 @@ impl Context {
             RenderSurfaceType::Window(_) => Surface::required_extensions(event_loop.unwrap()),
             #[cfg(feature = "image")]
             RenderSurfaceType::Image(_) => vulkano::instance::InstanceExtensions::empty(),
+            RenderSurfaceType::Video(_) => vulkano::instance::InstanceExtensions::empty(),
+            RenderSurfaceType::Custom => vulkano::instance::InstanceExtensions::empty(),
         };
-        assert!(
-            library
-                .supported_extensions()
-                .contains(&instance_extensions),
-            "Vulkan library does not support required extensions"
+        if !library
+            .supported_extensions()
+            .contains(&instance_extensions)
+        {
+            return Err(InitError::UnsupportedExtensions);
+        }
+
+        let windowed = matches!(config.render_surface_type, RenderSurfaceType::Window(_));
+
+        let instance = Self::create_instance(library, instance_extensions)?;
+
+        tracing::debug!("Vulkan instance created");
+
+        let physical_device = Self::select_device(&instance, &config.device_preference, windowed)?;
+
+        tracing::info!(
+            "Using device {} ({:?})",
+            physical_device.properties().device_name,
+            physical_device.properties().device_type
         );
+        if physical_device.properties().device_type == PhysicalDeviceType::Cpu {
+            tracing::warn!("No GPU is suitable, rendering on the CPU will be slow");
+        }
+        let device_extensions = Self::required_extensions(&physical_device, windowed);
 
-        let device_extensions = match config.render_surface_type {
-            RenderSurfaceType::Window(_) => DeviceExtensions {
-                khr_storage_buffer_storage_class: true,
-                khr_swapchain: true,
-                ..DeviceExtensions::empty()
-            },
-            #[cfg(feature = "image")]
-            RenderSurfaceType::Image(_) => DeviceExtensions::empty(),
+        let anisotropy = config.shader_descriptor.anisotropy.clamp(1, 16);
+        let (device_features, sampler_anisotropy) = if anisotropy == 1 {
+            (Features::empty(), None)
+        } else if physical_device.supported_features().sampler_anisotropy {
+            let max_anisotropy = physical_device.properties().max_sampler_anisotropy;
+            (
+                Features {
+                    sampler_anisotropy: true,
+                    ..Features::empty()
+                },
+                Some(f32::from(anisotropy).min(max_anisotropy)),
+            )
+        } else {
+            tracing::warn!("Anisotropic filtering is not supported by the device, disabling it");
+            (Features::empty(), None)
         };
 
-        let instance = Instance::new(
+        let (device, compute_queue, transfer_queue) =
+            Self::create_device(physical_device, &device_extensions, &device_features)?;
+
+        tracing::debug!("Vulkan device created");
+
+        Ok(Self {
+            device: device.clone(),
+            compute_queue,
+            transfer_queue,
+            sampler_anisotropy,
+            memory_allocator: Arc::new(StandardMemoryAllocator::new_default(device.clone())),
+            descriptor_set_allocator: Arc::new(StandardDescriptorSetAllocator::new(
+                device.clone(),
+                StandardDescriptorSetAllocatorCreateInfo::default(),
+            )),
+            command_buffer_allocator: Arc::new(StandardCommandBufferAllocator::new(
+                device,
+                StandardCommandBufferAllocatorCreateInfo::default(),
+            )),
+        })
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the Vulkan device.
+    pub const fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the queue used for rendering.
+    pub const fn compute_queue(&self) -> &Arc<Queue> {
+        &self.compute_queue
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the memory allocator.
+    pub const fn memory_allocator(&self) -> &Arc<StandardMemoryAllocator> {
+        &self.memory_allocator
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the command buffer allocator.
+    pub const fn command_buffer_allocator(&self) -> &Arc<StandardCommandBufferAllocator> {
+        &self.command_buffer_allocator
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the anisotropy level to use for texture samplers,
+    /// or `None` if anisotropic filtering is disabled or unsupported.
+    pub const fn sampler_anisotropy(&self) -> Option<f32> {
+        self.sampler_anisotropy
+    }
+
+    /// Creates a new Vulkan instance with the given extensions.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the instance could not be created.
+    fn create_instance(
+        library: Arc<VulkanLibrary>,
+        instance_extensions: InstanceExtensions,
+    ) -> Result<Arc<Instance>, InitError> {
+        Instance::new(
             library,
             InstanceCreateInfo {
                 #[cfg(target_os = "macos")]
                 flags: vulkano::instance::InstanceCreateFlags::ENUMERATE_PORTABILITY,
                 application_version: vulkano::Version::major_minor(1, 0),
                 #[cfg(target_os = "macos")]
-                enabled_extensions: vulkano::instance::InstanceExtensions {
+                enabled_extensions: InstanceExtensions {
                     khr_portability_enumeration: true,
                     ..Default::default()
                 }
@@ -154,54 +491,150 @@ impl Context {
                 ..Default::default()
             },
         )
-        .expect("failed to create instance");
+        .map_err(Validated::unwrap)
+        .map_err(InitError::from)
+    }
 
-        tracing::debug!("Vulkan instance created");
+    /// Lists the physical devices available, see [`RayTracingApp::enumerate_devices`].
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if Vulkan is unavailable.
+    fn enumerate_devices() -> Result<Vec<DeviceInfo>, InitError> {
+        let library = VulkanLibrary::new().map_err(InitError::VulkanLibraryLoad)?;
+        let instance = Self::create_instance(library, InstanceExtensions::empty())?;
 
-        let physical_device = instance
-            .enumerate_physical_devices()
-            .expect("failed to enumerate physical devices")
-            .filter(|p| p.supported_extensions().contains(&device_extensions))
-            .min_by_key(|p| match p.properties().device_type {
-                PhysicalDeviceType::DiscreteGpu => 1,
-                PhysicalDeviceType::IntegratedGpu => 2,
-                PhysicalDeviceType::VirtualGpu => 3,
-                PhysicalDeviceType::Cpu => 4,
-                PhysicalDeviceType::Other => 5,
-                _ => 6,
+        Ok(instance
+            .enumerate_physical_devices()?
+            .enumerate()
+            .map(|(index, p)| DeviceInfo {
+                index,
+                name: p.properties().device_name.clone(),
+                device_type: p.properties().device_type,
+                api_version: p.api_version(),
+                rejection: Self::check_device(&p, true).err(),
             })
-            .expect("failed to find a suitable physical device");
+            .collect())
+    }
 
-        tracing::info!("Using device {}", physical_device.properties().device_name,);
+    /// Selects the physical device to render on according to the given preference,
+    /// the device having to be able to render to a window if `windowed` is set.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the device requested by index or name does not exist
+    /// or cannot be used, or if no device is suitable.
+    fn select_device(
+        instance: &Arc<Instance>,
+        preference: &DevicePreference,
+        windowed: bool,
+    ) -> Result<Arc<PhysicalDevice>, InitError> {
+        let physical_devices = instance.enumerate_physical_devices()?.collect::<Vec<_>>();
 
-        let (device, compute_queue, transfer_queue) =
-            Self::create_device(physical_device, &device_extensions, &Features::empty());
+        let requested = match preference {
+            DevicePreference::Index(index) => Some(physical_devices.get(*index)),
+            DevicePreference::Name(name) => {
+                let name = name.to_lowercase();
+                Some(
+                    physical_devices
+                        .iter()
+                        .find(|p| p.properties().device_name.to_lowercase().contains(&name)),
+                )
+            }
+            DevicePreference::PreferDiscrete | DevicePreference::PreferIntegrated => None,
+        };
+        if let Some(requested) = requested {
+            let Some(physical_device) = requested else {
+                return Err(InitError::DeviceNotFound(preference.clone()));
+            };
+            return match Self::check_device(physical_device, windowed) {
+                Ok(()) => Ok(physical_device.clone()),
+                Err(reason) => Err(InitError::NoSuitableDevice(vec![RejectedDevice {
+                    name: physical_device.properties().device_name.clone(),
+                    reason,
+                }])),
+            };
+        }
 
-        tracing::debug!("Vulkan device created");
+        // Every device able to render is a candidate, software ones being the last resort
+        let mut rejected = Vec::new();
+        let physical_device = physical_devices
+            .into_iter()
+            .filter(|p| {
+                let Err(reason) = Self::check_device(p, windowed) else {
+                    return true;
+                };
+                let name = p.properties().device_name.clone();
+                tracing::warn!("Rejecting device {name}: {reason}");
+                rejected.push(RejectedDevice { name, reason });
+                false
+            })
+            .min_by_key(|p| preference.rank(p.properties().device_type));
+        let Some(physical_device) = physical_device else {
+            return Err(InitError::NoSuitableDevice(rejected));
+        };
 
-        Self {
-            device: device.clone(),
-            compute_queue,
-            transfer_queue,
-            memory_allocator: Arc::new(StandardMemoryAllocator::new_default(device.clone())),
-            descriptor_set_allocator: Arc::new(StandardDescriptorSetAllocator::new(
-                device.clone(),
-                StandardDescriptorSetAllocatorCreateInfo::default(),
-            )),
-            command_buffer_allocator: Arc::new(StandardCommandBufferAllocator::new(
-                device,
-                StandardCommandBufferAllocatorCreateInfo::default(),
-            )),
-        }
+        Ok(physical_device)
     }
 
     #[must_use]
+    /// Returns the extensions the given device must enable to render to a window, if `windowed` is set,
+    /// or to any other render surface.
+    fn required_extensions(physical_device: &PhysicalDevice, windowed: bool) -> DeviceExtensions {
+        DeviceExtensions {
+            // The extension is part of the core of Vulkan since 1.1, and no longer advertised by some drivers
+            khr_storage_buffer_storage_class: windowed
+                && physical_device.api_version() < vulkano::Version::V1_1,
+            khr_swapchain: windowed,
+            ..DeviceExtensions::empty()
+        }
+    }
+
+    /// Checks whether the given device can render to a window, if `windowed` is set,
+    /// or to any other render surface.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the reason why the device cannot be used.
+    fn check_device(
+        physical_device: &PhysicalDevice,
+        windowed: bool,
+    ) -> Result<(), DeviceRejection> {
+        let required_extensions = Self::required_extensions(physical_device, windowed);
+        if !physical_device
+            .supported_extensions()
+            .contains(&required_extensions)
+        {
+            let missing_extensions = required_extensions
+                .difference(physical_device.supported_extensions())
+                .into_iter()
+                .filter_map(|(name, missing)| missing.then_some(name))
+                .collect();
+            return Err(DeviceRejection::MissingExtensions(missing_extensions));
+        }
+
+        let has_compute_queue = physical_device.queue_family_properties().iter().any(|q| {
+            q.queue_flags
+                .intersects(vulkano::device::QueueFlags::COMPUTE)
+        });
+        if !has_compute_queue {
+            return Err(DeviceRejection::NoComputeQueue);
+        }
+
+        Ok(())
+    }
+
     /// Creates a new Vulkan device.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the device does not have a compute queue
+    /// or if it could not be created.
     fn create_device(
         physical_device: Arc<PhysicalDevice>,
         device_extensions: &DeviceExtensions,
         device_features: &Features,
-    ) -> (Arc<Device>, Arc<Queue>, Arc<Queue>) {
+    ) -> Result<DeviceQueues, InitError> {
         let queue_family_compute = physical_device
             .queue_family_properties()
             .iter()
@@ -212,7 +645,12 @@ impl Context {
                     .intersects(vulkano::device::QueueFlags::COMPUTE)
             })
             .map(|(i, _)| i)
-            .expect("could not find a queue that supports graphics");
+            .ok_or_else(|| {
+                InitError::NoSuitableDevice(vec![RejectedDevice {
+                    name: physical_device.properties().device_name.clone(),
+                    reason: DeviceRejection::NoComputeQueue,
+                }])
+            })?;
 
         // Try finding a separate queue for transfer
         let queue_family_transfer = physical_device
@@ -227,18 +665,15 @@ impl Context {
             })
             .map(|(i, _)| i);
 
-        let queue_create_infos = vec![
-            QueueCreateInfo {
-                queue_family_index: queue_family_compute,
+        // Devices with a single queue family, such as software ones, share the compute queue for transfers,
+        // as a queue family cannot be requested twice
+        let queue_create_infos = std::iter::once(queue_family_compute)
+            .chain(queue_family_transfer)
+            .map(|queue_family_index| QueueCreateInfo {
+                queue_family_index,
                 ..Default::default()
-            },
-            queue_family_transfer.map_or_else(QueueCreateInfo::default, |transfer_queue| {
-                QueueCreateInfo {
-                    queue_family_index: transfer_queue,
-                    ..Default::default()
-                }
-            }),
-        ];
+            })
+            .collect();
 
         let (device, mut queues) = Device::new(
             physical_device,
@@ -249,7 +684,7 @@ impl Context {
                 ..Default::default()
             },
         )
-        .expect("failed to create device");
+        .map_err(Validated::unwrap)?;
 
         let compute_queue = queues.next().unwrap();
         let transfer_queue = queue_family_transfer.map_or_else(
@@ -257,7 +692,7 @@ impl Context {
             |_| queues.next().expect("Failed to get transfer queue"),
         );
 
-        (device, compute_queue, transfer_queue)
+        Ok((device, compute_queue, transfer_queue))
     }
 }
 
@@ -265,35 +700,97 @@ impl Context {
 pub struct RayTracingApp {
     /// The configuration of the ray tracing application.
     config: RayTracingAppConfig,
+    /// The context, kept to update the scenes after initialization.
+    context: Context,
     /// The renderer.
     renderer: Renderer,
     /// The GPU buffers.
     buffers: Buffers,
+    /// The loaded scenes, the first one being the scene of the configuration.
+    scenes: Vec<shader::model::LoadedModels>,
+    /// The index of the scene being rendered.
+    active_scene: usize,
     /// The optional event loop.
     event_loop: Option<winit::event_loop::EventLoop<()>>,
+    /// The instant the last frame was rendered at.
+    last_frame: std::time::Instant,
+    /// Whether the application has been asked to exit.
+    exit_requested: bool,
+    /// Whether the render surface is currently minimized.
+    minimized: bool,
+    /// Whether rendering is frozen on the last rendered frame.
+    frozen: bool,
+    /// Whether the GPU resources have been released by [`RayTracingApp::shutdown`].
+    shut_down: bool,
+    /// The callback the rendered frames are handed to, if any.
+    on_frame: Option<render::FrameCallback>,
+    /// The number of frames rendered to the video, for a video render surface.
+    video_frames: u32,
+    /// The animation of the active camera, if it is being animated.
+    camera_animation: Option<control::camera::animation::CameraAnimation>,
+    /// What happens to the inputs while the camera is animated.
+    animation_input: control::camera::animation::AnimationInput,
+    /// The shake applied on top of the pose of the active camera.
+    camera_shake: control::camera::shake::CameraShake,
 }
 
 impl RayTracingApp {
+    #[must_use]
+    /// Returns the physical devices available, in the order of [`DevicePreference::Index`].
+    ///
+    /// It can be called before creating the application, to choose the device to render on.
+    /// The list is empty if Vulkan is unavailable, the cause being logged.
+    pub fn enumerate_devices() -> Vec<DeviceInfo> {
+        Context::enumerate_devices().unwrap_or_else(|error| {
+            tracing::error!("Failed to enumerate devices: {error}");
+            Vec::new()
+        })
+    }
+
     #[must_use]
     /// Creates a new ray tracing application from the given configuration.
     ///
     /// ## Panics
     ///
     /// This function will panic if the application encounters any errors during initialization.
+    /// Use [`RayTracingApp::try_new`] to handle them instead.
     pub fn new(config: RayTracingAppConfig) -> Self {
+        match Self::try_new(config) {
+            Ok(app) => app,
+            Err(error) => panic!("failed to initialize the application: {error}"),
+        }
+    }
+
+    /// Creates a new ray tracing application from the given configuration.
+    ///
+    /// This allows falling back gracefully when no GPU is available or when a model is missing.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if Vulkan, the render surface or the scene could not be initialized,
+    /// or if the render surface type is [`RenderSurfaceType::Custom`].
+    pub fn try_new(config: RayTracingAppConfig) -> Result<Self, InitError> {
         let event_loop = match config.render_surface_type {
             RenderSurfaceType::Window(_) => Some(winit::event_loop::EventLoop::new()),
             #[cfg(feature = "image")]
             RenderSurfaceType::Image(_) => None,
+            RenderSurfaceType::Video(_) => None,
+            RenderSurfaceType::Custom => return Err(InitError::CustomSurface),
         };
-        let context = Context::new(&config, event_loop.as_ref());
+        let context = Context::new(&config, event_loop.as_ref())?;
 
         let render_surface: Box<dyn RenderSurface> = match &config.render_surface_type {
-            RenderSurfaceType::Window(descriptor) => Box::new(crate::render::window::Window::new(
-                event_loop.as_ref().unwrap(),
-                &context.device,
-                descriptor,
-            )),
+            RenderSurfaceType::Window(descriptor) => {
+                // Created along with the window surface type
+                let Some(event_loop) = event_loop.as_ref() else {
+                    unreachable!()
+                };
+                Box::new(crate::render::window::Window::new(
+                    event_loop,
+                    &context.device,
+                    descriptor,
+                )?)
+            }
             #[cfg(feature = "image")]
             RenderSurfaceType::Image(descriptor) => Box::new(Image::new(
                 descriptor,
@@ -301,158 +798,1173 @@ impl RayTracingApp {
                 &context.command_buffer_allocator,
                 context.compute_queue.clone(),
             )),
+            RenderSurfaceType::Video(descriptor) => Box::new(VideoSurface::new(
+                descriptor,
+                &context.memory_allocator,
+                &context.command_buffer_allocator,
+                context.compute_queue.clone(),
+            )?),
+            // Rejected along with the event loop
+            RenderSurfaceType::Custom => unreachable!(),
         };
 
-        let buffers = Self::init_gpu_buffers(&config, &context);
+        Self::from_surface(config, &context, render_surface, event_loop)
+    }
+
+    #[must_use]
+    /// Creates a new ray tracing application rendering on a user-provided surface.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the application encounters any errors during initialization.
+    /// Use [`RayTracingApp::try_with_surface`] to handle them instead.
+    pub fn with_surface(
+        config: RayTracingAppConfig,
+        create_surface: impl FnOnce(&Context) -> Box<dyn RenderSurface>,
+    ) -> Self {
+        match Self::try_with_surface(config, create_surface) {
+            Ok(app) => app,
+            Err(error) => panic!("failed to initialize the application: {error}"),
+        }
+    }
+
+    /// Creates a new ray tracing application rendering on a user-provided surface.
+    ///
+    /// `create_surface` is given the context of the application,
+    /// so that the surface can create its images on the right device.
+    ///
+    /// `config.render_surface_type` is ignored and no event loop is created:
+    /// the controllers will not receive any `winit` event and
+    /// the application is driven by [`RayTracingApp::run`], which renders frames until
+    /// [`RenderSurface::is_closed`] returns `true`, or by the caller through [`RayTracingApp::pump`].
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if Vulkan or the scene could not be initialized.
+    pub fn try_with_surface(
+        mut config: RayTracingAppConfig,
+        create_surface: impl FnOnce(&Context) -> Box<dyn RenderSurface>,
+    ) -> Result<Self, InitError> {
+        config.render_surface_type = RenderSurfaceType::Custom;
+
+        let context = Context::new(&config, None)?;
+        let render_surface = create_surface(&context);
+
+        Self::from_surface(config, &context, render_surface, None)
+    }
+
+    /// Finishes the initialization of the application once the render surface is created.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if `active_camera` is not the index of one of the cameras,
+    /// if the scene could not be loaded,
+    /// or if the shader has no entry point with the name given in the shader descriptor.
+    fn from_surface(
+        config: RayTracingAppConfig,
+        context: &Context,
+        render_surface: Box<dyn RenderSurface>,
+        event_loop: Option<winit::event_loop::EventLoop<()>>,
+    ) -> Result<Self, InitError> {
+        if config.active_camera >= config.cameras.len() {
+            return Err(InitError::CameraOutOfRange(
+                config.active_camera,
+                config.cameras.len(),
+            ));
+        }
+
+        let (buffers, scenes) = Self::init_gpu_buffers(&config, context)?;
+        let shader_descriptor = config
+            .shader_descriptor
+            .clone()
+            .scaled_to_scene(scenes[0].scene_bounds());
 
         let renderer = Renderer::new(
             &context.device,
             &context.compute_queue,
+            &context.memory_allocator,
             &context.descriptor_set_allocator,
-            &context.command_buffer_allocator,
+            context.sampler_anisotropy(),
             render_surface,
             &buffers,
-            config.shader_descriptor,
-        );
+            shader_descriptor,
+        )?;
 
         tracing::debug!("Successfully initialized");
 
-        Self {
+        Ok(Self {
             config,
+            context: context.clone(),
             renderer,
             buffers,
+            scenes,
+            active_scene: 0,
             event_loop,
-        }
+            last_frame: std::time::Instant::now(),
+            exit_requested: false,
+            minimized: false,
+            frozen: false,
+            shut_down: false,
+            on_frame: None,
+            video_frames: 0,
+            camera_animation: None,
+            animation_input: control::camera::animation::AnimationInput::default(),
+            camera_shake: control::camera::shake::CameraShake::default(),
+        })
     }
 
-    #[must_use]
     /// Initializes the GPU buffers.
-    fn init_gpu_buffers(config: &RayTracingAppConfig, context: &Context) -> Buffers {
+    ///
+    /// Also returns the loaded scenes, the first one being the scene the buffers point to.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if a model of one of the scenes could not be loaded.
+    fn init_gpu_buffers(
+        config: &RayTracingAppConfig,
+        context: &Context,
+    ) -> Result<(Buffers, Vec<shader::model::LoadedModels>), InitError> {
         let camera_uniform = {
-            use crate::shader::source::{Camera, CameraBuffer};
-            let data = Camera {
-                position: config.camera.position().into(),
-                view: config.camera.direction().into(),
-                up: config.camera.up().into(),
-                right: config.camera.right(),
-            };
             let buffer =
-                crate::buffer::new_uniform::<CameraBuffer>(&context.memory_allocator).unwrap();
-            buffer.write().unwrap().camera = data;
+                crate::buffer::new_uniform::<shader::CameraBuffer>(&context.memory_allocator)
+                    .unwrap();
+            buffer.write().unwrap().camera =
+                Self::camera_data(&*config.cameras[config.active_camera]);
             buffer
         };
         tracing::trace!("Camera buffer initialized");
 
-        let shader::model::LoadedModels {
-            triangles_buffer,
-            materials_buffer,
-            models_buffer,
-            bvhs_buffer,
-        } = shader::model::LoadedModels::load(
-            &context.memory_allocator,
-            &context.command_buffer_allocator,
-            &context.transfer_queue,
-            &config.scene_descriptor,
-        );
+        // The scenes are uploaded in the background, rendering waits for the active one
+        let load_scene = |scene_descriptor: &shader::SceneDescriptor, memory_budget: u64| {
+            shader::model::LoadedModels::load_async_within(
+                &context.memory_allocator,
+                &context.command_buffer_allocator,
+                &context.transfer_queue,
+                scene_descriptor,
+                memory_budget,
+            )
+        };
+
+        let mut scenes = vec![load_scene(&config.scene_descriptor, u64::MAX)?
+            .expect("every scene fits in an unlimited memory budget")];
+
+        // Preloaded scenes stay resident, so they must fit in device memory
+        let memory_budget = context
+            .device
+            .physical_device()
+            .memory_properties()
+            .memory_heaps
+            .iter()
+            .filter(|heap| {
+                heap.flags
+                    .intersects(vulkano::memory::MemoryHeapFlags::DEVICE_LOCAL)
+            })
+            .map(|heap| heap.size)
+            .max()
+            .unwrap_or(u64::MAX);
+        let mut memory_used = scenes[0].size();
+
+        // Each scene is only uploaded if its estimated size fits in what is left of the budget
+        for (i, scene_descriptor) in config.preloaded_scenes.iter().enumerate() {
+            let Some(scene) =
+                load_scene(scene_descriptor, memory_budget.saturating_sub(memory_used))?
+            else {
+                tracing::warn!(
+                    "Preloaded scenes do not fit in device memory, only {i} of them were loaded"
+                );
+                break;
+            };
+
+            memory_used += scene.size();
+            scenes.push(scene);
+        }
+        if scenes.len() > 1 {
+            tracing::debug!("{} scenes loaded, using {memory_used} bytes", scenes.len());
+        }
+
+        Ok((Buffers::new(camera_uniform, &scenes[0]), scenes))
+    }
+
+    #[must_use]
+    /// Returns the content of the camera uniform for the given camera.
+    ///
+    /// Invalid clip planes are ignored, with a warning.
+    fn camera_data(camera: &dyn control::camera::Camera) -> shader::source::Camera {
+        let (near, far) = match camera.clip_planes() {
+            (near, far) if near >= 0.0 && near < far => (near, far),
+            (near, far) => {
+                tracing::warn!(
+                    "Invalid clip planes of the camera (near {near}, far {far}), ignoring them"
+                );
+                (0.0, f32::INFINITY)
+            }
+        };
 
-        Buffers {
-            camera_uniform,
-            triangles_buffer,
-            materials_buffer,
-            models_buffer,
-            bvhs_buffer,
+        shader::source::Camera {
+            position: camera.position().into(),
+            view: camera.direction().into(),
+            up: camera.up().into(),
+            right: camera.right(),
+            aperture: camera.aperture(),
+            focus_distance: camera.focus_distance(),
+            fov: camera.fov(),
+            near,
+            far,
         }
     }
 
+    #[cfg(feature = "cpu-reference")]
+    #[must_use]
+    /// Renders the active scene from the active camera on the CPU, returning the RGBA8 pixels
+    /// of the image row by row from the top, at the size of the render surface.
+    ///
+    /// It runs a single-threaded path tracer independent of the shader, which reads the same triangles,
+    /// BVHs and materials, as a ground truth to compare the frames of the shader with, within a tolerance.
+    /// It is slow, and traces as many samples per pixel as the shader does per frame.
+    /// Its random numbers differ from the ones of the shader, so only converged images can be compared.
+    /// Textures, environment maps and debug modes are ignored, and emissive surfaces are only found by the bounces.
+    pub fn render_cpu_reference(&self) -> Vec<u8> {
+        let scene = &self.scenes[self.active_scene];
+        let shader_descriptor = self
+            .config
+            .shader_descriptor
+            .clone()
+            .scaled_to_scene(scene.scene_bounds());
+        let camera = Self::camera_data(&*self.config.cameras[self.config.active_camera]);
+
+        shader::reference::render(
+            &scene.reference_scene(),
+            &camera,
+            &shader_descriptor,
+            self.renderer.surface_size(),
+        )
+    }
+
     #[must_use]
     /// Returns the buffers used in the shader.
     pub fn buffers(&self) -> Buffers {
         self.buffers.clone()
     }
 
-    /// Run the application.
+    #[must_use]
+    #[inline]
+    /// Returns the axis-aligned bounding box of the active scene, as `(min, max)`.
+    pub fn scene_bounds(&self) -> ([f32; 3], [f32; 3]) {
+        self.scenes[self.active_scene].scene_bounds()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns statistics about the loading of each model of the active scene,
+    /// in the order they are given in the scene descriptor.
+    pub fn model_load_stats(&self) -> &[shader::model::ModelLoadStats] {
+        &self.scenes[self.active_scene].load_stats
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the number of loaded scenes.
     ///
-    /// ## Note
+    /// It may be less than the number of scenes of the configuration
+    /// if they do not all fit in device memory.
+    pub const fn scene_count(&self) -> usize {
+        self.scenes.len()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the index of the scene being rendered.
+    pub const fn active_scene(&self) -> usize {
+        self.active_scene
+    }
+
+    /// Renders only the given region of the render surface, keeping the rest of the previous frame.
     ///
-    /// Use the argument `on_waiting_for_render` to update anything unrelated to rendering while waiting for the render to complete.
+    /// This is useful for mostly static scenes, where only a small part of the image changes.
+    /// Determining which regions changed is up to the application,
+    /// and the camera is not updated, as moving it changes the whole image.
+    /// After the surface is resized, a whole frame must be rendered before rendering regions again.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the region could not be rendered,
+    /// or [`RenderError::ShutDown`] if the application has been shut down.
+    /// Use [`RenderError::is_recoverable`] to know whether the next frame can be rendered.
     ///
     /// ## Panics
     ///
     /// This function will panic if the application encounters any errors during runtime.
-    /// Typically, this can happen if there is a concurrency issue or if the application is unable to render.
-    pub fn run(self, mut on_waiting_for_render: Box<dyn FnMut(u32)>) {
-        match self.config.render_surface_type {
-            RenderSurfaceType::Window(_) => {
-                let Self {
-                    event_loop,
-                    config:
-                        RayTracingAppConfig {
-                            mut controllers,
-                            mut camera,
-                            ..
-                        },
-                    mut renderer,
-                    buffers,
-                    ..
-                } = self;
-
-                let mut start = std::time::Instant::now();
+    pub fn render_region(&mut self, region: render::Region) -> Result<(), RenderError> {
+        self.check_running()?;
+        self.scenes[self.active_scene].wait_for_upload();
+        self.renderer.render_region(region, &mut |_| {})?;
+        self.hand_frame()
+    }
 
-                // ## Panics
-                // This line cannot panic because the event loop is always `Some` for window rendering.
-                event_loop.unwrap().run(move |event, _, control_flow| {
-                    for controller in &mut controllers {
-                        controller.handle_event(&event);
-                    }
-                    match event {
-                        winit::event::Event::WindowEvent {
-                            event: winit::event::WindowEvent::CloseRequested,
-                            ..
-                        } => {
-                            *control_flow = winit::event_loop::ControlFlow::Exit;
-                        }
-                        // winit::event::Event::WindowEvent {
-                        //     event: winit::event::WindowEvent::Resized(_size),
-                        //     ..
-                        // } => {
-                        //     // TODO: Handle window resizing
-                        //     todo!("Handle window resizing");
-                        // }
-                        winit::event::Event::MainEventsCleared => {
-                            let elapsed = start.elapsed().as_secs_f32();
-                            start = std::time::Instant::now();
-
-                            let inputs = controllers
-                                .iter_mut()
-                                .map(|controller| controller.fetch_input())
-                                .fold(crate::control::Inputs::default(), |mut acc, i| {
-                                    acc.accumulate(i);
-                                    acc
-                                });
-                            camera.process_inputs(inputs, elapsed);
-
-                            let mut camera_handle = buffers.camera_uniform.write().unwrap();
-                            camera_handle.camera.position = camera.position().into();
-                            camera_handle.camera.view = camera.direction().into();
-                            camera_handle.camera.up = camera.up().into();
-                            camera_handle.camera.right = camera.right();
-                            drop(camera_handle);
-
-                            // Innacurate at high FPS
-                            // tracing::trace!("FPS: {:.01}", 1.0 / elapsed);
-
-                            renderer.render(&mut on_waiting_for_render);
-                        }
-                        _ => {}
-                    }
-                });
+    /// Restricts every following frame to the given region of the render surface,
+    /// or renders whole frames again with `None`.
+    ///
+    /// Unlike [`RayTracingApp::render_region`], the camera is still updated and the frames accumulated,
+    /// which suits tiled offline rendering: each process renders a tile of the same image,
+    /// the rest of its output being left untouched, and the tiles are composited afterwards.
+    /// The region is clamped to the render surface and needs not be aligned to the work groups of the shader.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    pub fn set_render_region(&mut self, region: Option<render::Region>) -> Result<(), RenderError> {
+        self.check_running()?;
+        self.renderer.set_render_region(region)
+    }
+
+    /// Switches between path tracing and the debug views, see [`shader::DebugMode`].
+    ///
+    /// The mode is kept when switching scenes.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    pub fn set_debug_mode(&mut self, mode: shader::DebugMode) -> Result<(), RenderError> {
+        self.check_running()?;
+        self.config.shader_descriptor.debug_mode = mode;
+        self.renderer.set_debug_mode(mode)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the exposure of the output, in stops.
+    pub const fn exposure(&self) -> f32 {
+        self.config.shader_descriptor.exposure
+    }
+
+    /// Sets the exposure of the output in stops, see [`shader::ShaderDescriptor::exposure`].
+    ///
+    /// It applies from the next frame, without discarding the accumulated frames,
+    /// so it can be dialed while the image converges.
+    pub const fn set_exposure(&mut self, exposure: f32) {
+        self.config.shader_descriptor.exposure = exposure;
+        self.renderer.set_exposure(exposure);
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the color rendered as white.
+    pub const fn white_point(&self) -> [f32; 3] {
+        self.config.shader_descriptor.white_point
+    }
+
+    /// Sets the color rendered as white, see [`shader::ShaderDescriptor::white_point`].
+    ///
+    /// Like the exposure, it applies from the next frame without discarding the accumulated frames.
+    pub const fn set_white_point(&mut self, white_point: [f32; 3]) {
+        self.config.shader_descriptor.white_point = white_point;
+        self.renderer.set_white_point(white_point);
+    }
+
+    /// Sets the callback every rendered frame is handed to, or removes it.
+    ///
+    /// While a callback is set, each frame is copied to host memory once rendered,
+    /// and the callback is called with it after the frame has been presented.
+    /// As the copy costs a transfer and a wait for the GPU every frame, it is only done on demand.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffers cannot be recreated.
+    pub fn set_frame_callback(
+        &mut self,
+        on_frame: Option<render::FrameCallback>,
+    ) -> Result<(), RenderError> {
+        self.check_running()?;
+        self.renderer.set_frame_readback(on_frame.is_some())?;
+        self.on_frame = on_frame;
+        Ok(())
+    }
+
+    /// Sets whether the time the GPU spends rendering each frame is measured,
+    /// to be read with [`RayTracingApp::last_gpu_time`].
+    ///
+    /// Timestamps are written around the dispatch of the shader, so the copies to the render surface
+    /// are not included. Profiling is only enabled if the device supports timestamps on the render queue.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the profiling resources cannot be created.
+    pub fn set_gpu_profiling(&mut self, enabled: bool) -> Result<(), RenderError> {
+        self.check_running()?;
+        self.renderer.set_gpu_profiling(enabled)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the time the GPU spent rendering the last completed frame,
+    /// or `None` if GPU profiling is disabled or no frame has completed since it was enabled.
+    pub const fn last_gpu_time(&self) -> Option<std::time::Duration> {
+        self.renderer.last_gpu_time()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the size of the work groups of the shader, in pixels.
+    ///
+    /// It is the one of the shader descriptor, unless the device does not support it,
+    /// see [`shader::ShaderDescriptor::work_group_size`].
+    pub const fn work_group_size(&self) -> [u32; 2] {
+        self.renderer.work_group_size()
+    }
+
+    /// Returns [`RenderError::ShutDown`] if the application has been shut down,
+    /// as its renderer no longer has command buffers to render or re-record.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if [`RayTracingApp::shutdown`] has been called.
+    const fn check_running(&self) -> Result<(), RenderError> {
+        if self.shut_down {
+            Err(RenderError::ShutDown)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Hands the last rendered frame to the frame callback, if any.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame failed to render.
+    fn hand_frame(&mut self) -> Result<(), RenderError> {
+        if let Some(on_frame) = &mut self.on_frame {
+            if let Some(frame) = self.renderer.read_frame()? {
+                on_frame(frame);
+            }
+        }
+        Ok(())
+    }
+
+    /// Switches to the scene at the given index.
+    ///
+    /// Index 0 is `scene_descriptor` of the configuration,
+    /// and the following indices are the scenes of `preloaded_scenes`.
+    /// As all the scenes are resident, switching only re-records the command buffers.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`RenderError::SceneOutOfRange`] if there is no loaded scene at the given index,
+    /// an error if one of the frames in flight failed to render,
+    /// or [`RenderError::ShutDown`] if the application has been shut down.
+    pub fn set_active_scene(&mut self, index: usize) -> Result<(), RenderError> {
+        self.check_running()?;
+        if index >= self.scenes.len() {
+            return Err(RenderError::SceneOutOfRange(index, self.scenes.len()));
+        }
+        if index == self.active_scene {
+            return Ok(());
+        }
+
+        let scene = &self.scenes[index];
+        let buffers = Buffers::new(self.buffers.camera_uniform.clone(), scene);
+        let shader_descriptor = self
+            .config
+            .shader_descriptor
+            .clone()
+            .scaled_to_scene(scene.scene_bounds());
+
+        self.renderer.set_buffers(&buffers, shader_descriptor)?;
+        self.buffers = buffers;
+        self.active_scene = index;
+        tracing::debug!("Switched to scene {index}");
+
+        Ok(())
+    }
+
+    /// Switches to the camera at the given index of `cameras` in the configuration.
+    ///
+    /// Only the active camera processes the inputs of the controllers, so the other cameras
+    /// stay where they were until they are switched back to. The camera uniform is written
+    /// from the new camera on the next frame, and the accumulated frames are discarded.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`RenderError::CameraOutOfRange`] if there is no camera at the given index,
+    /// like [`InitError::CameraOutOfRange`] for the initial one.
+    pub fn set_active_camera(&mut self, index: usize) -> Result<(), RenderError> {
+        if index >= self.config.cameras.len() {
+            return Err(RenderError::CameraOutOfRange(
+                index,
+                self.config.cameras.len(),
+            ));
+        }
+        if index == self.config.active_camera {
+            return Ok(());
+        }
+
+        self.camera_animation = None;
+        self.config.active_camera = index;
+        self.renderer.reset_accumulation();
+        tracing::debug!("Switched to camera {index}");
+
+        Ok(())
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the index of the active camera, see [`RayTracingApp::set_active_camera`].
+    pub const fn active_camera(&self) -> usize {
+        self.config.active_camera
+    }
+
+    /// Smoothly moves the active camera from its current pose to `position`, looking at `target`,
+    /// over `duration` seconds.
+    ///
+    /// The transition eases in and out, and advances with the time elapsed between the frames.
+    /// Until it ends, the camera does not process the inputs of the controllers itself,
+    /// which are handled as set by [`RayTracingApp::set_animation_input`].
+    /// A new animation replaces the current one from the current pose, and switching
+    /// the active camera cancels it. A non-positive duration moves the camera on the next frame.
+    pub fn animate_camera_to(&mut self, position: [f32; 3], target: [f32; 3], duration: f32) {
+        self.camera_animation = Some(control::camera::animation::CameraAnimation::new(
+            &*self.config.cameras[self.config.active_camera],
+            position,
+            target,
+            duration,
+            self.animation_input,
+        ));
+    }
+
+    #[inline]
+    /// Stops the animation of the active camera where it is, if any,
+    /// the camera processing the inputs again.
+    pub const fn cancel_camera_animation(&mut self) {
+        self.camera_animation = None;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the active camera is being animated, see [`RayTracingApp::animate_camera_to`].
+    pub const fn is_camera_animating(&self) -> bool {
+        self.camera_animation.is_some()
+    }
+
+    #[inline]
+    /// Sets what happens to the inputs of the controllers while the camera is animated,
+    /// which defaults to ignoring them.
+    ///
+    /// It applies to the animations started afterwards.
+    pub const fn set_animation_input(&mut self, input: control::camera::animation::AnimationInput) {
+        self.animation_input = input;
+    }
+
+    #[inline]
+    /// Shakes the active camera, raising the intensity of the shake by the given amount.
+    ///
+    /// The intensity is capped at 1 and decays over time, see [`control::camera::shake::CameraShake`].
+    /// The shake is applied to the pose of whichever camera is active, without changing the camera itself,
+    /// and the frames are not accumulated while it lasts.
+    pub fn trigger_shake(&mut self, intensity: f32) {
+        self.camera_shake.trigger(intensity);
+    }
+
+    #[inline]
+    /// Replaces the amplitude, frequency and decay of the camera shake, see [`RayTracingApp::trigger_shake`].
+    ///
+    /// The current intensity is replaced by the one of the given shake.
+    pub const fn set_camera_shake(&mut self, shake: control::camera::shake::CameraShake) {
+        self.camera_shake = shake;
+    }
+
+    /// Moves the model at the given index of the active scene to the given position,
+    /// replacing the one given in its scene descriptor.
+    ///
+    /// Only the entry of the model in the models buffer is rewritten,
+    /// as its triangles and BVH are relative to its position.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`RenderError::ModelOutOfRange`] if there is no model at the given index
+    /// in the active scene, or an error if one of the frames in flight failed to render.
+    pub fn update_model_transform(
+        &mut self,
+        model_index: usize,
+        translation: [f32; 3],
+    ) -> Result<(), RenderError> {
+        self.check_running()?;
+        let model_count = self.scenes[self.active_scene].model_count();
+        if model_index >= model_count {
+            return Err(RenderError::ModelOutOfRange(model_index, model_count));
+        }
+        // The frames in flight may still be reading the models buffer
+        self.renderer.wait_for_frames_in_flight()?;
+
+        self.scenes[self.active_scene].set_model_translation(
+            &self.context.memory_allocator,
+            &self.context.command_buffer_allocator,
+            &self.context.transfer_queue,
+            model_index,
+            translation,
+        );
+        self.renderer.reset_accumulation();
+
+        Ok(())
+    }
+
+    /// Loads a model and adds it to the active scene with the given transform,
+    /// using the material at index `material_id` of the scene.
+    ///
+    /// A translation can be given as the transform of a model that is only positioned.
+    ///
+    /// Returns the index of the model, to be given to [`RayTracingApp::update_model_transform`].
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the model cannot be loaded,
+    /// if it references a material that does not exist, if one of the frames in flight failed to render,
+    /// or if the application has been shut down.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the buffers of the scene cannot be extended.
+    pub fn add_model(
+        &mut self,
+        path: &str,
+        transform: impl Into<shader::Transform>,
+        material_id: u32,
+    ) -> Result<usize, InitError> {
+        self.check_running()?;
+        let scene = &mut self.scenes[self.active_scene];
+        let model_index = scene.add_model(
+            &self.context.memory_allocator,
+            &self.context.command_buffer_allocator,
+            &self.context.transfer_queue,
+            path,
+            &transform.into(),
+            material_id,
+        )?;
+
+        // The scene buffers have been replaced by larger ones
+        let buffers = Buffers::new(self.buffers.camera_uniform.clone(), scene);
+        let shader_descriptor = self
+            .config
+            .shader_descriptor
+            .clone()
+            .scaled_to_scene(scene.scene_bounds());
+        self.renderer.set_buffers(&buffers, shader_descriptor)?;
+        self.buffers = buffers;
+        tracing::debug!("Added model {path} to scene {}", self.active_scene);
+
+        Ok(model_index)
+    }
+
+    #[inline]
+    /// Switches the window to the given mode, such as exclusive fullscreen.
+    ///
+    /// Going back to windowed mode restores the size and position the window had before.
+    /// Nothing happens if the render surface is not a window.
+    pub fn set_window_mode(&mut self, mode: render::window::Mode) {
+        self.renderer.set_window_mode(mode);
+    }
+
+    #[inline]
+    /// Sets the present mode of the window, for instance [`render::window::PresentMode::Immediate`]
+    /// to disable vertical synchronization and [`render::window::PresentMode::Fifo`] to enable it.
+    ///
+    /// The swapchain is recreated before the next frame, falling back to `Fifo` if the mode is not supported.
+    /// Nothing happens if the render surface is not a window.
+    pub fn set_present_mode(&mut self, present_mode: render::window::PresentMode) {
+        self.renderer.set_present_mode(present_mode);
+    }
+
+    #[inline]
+    /// Grabs or releases the cursor, for instance to let the user click on a menu.
+    ///
+    /// A grabbed cursor is released while the window does not have the focus, then grabbed again.
+    /// Nothing happens if the render surface is not a window.
+    pub fn set_cursor_grab(&mut self, grab: bool) {
+        self.renderer.set_cursor_grab(grab);
+    }
+
+    #[inline]
+    /// Discards the frames accumulated so far, so that the next frame starts a new average.
+    ///
+    /// Accumulation is already reset when the camera moves or when the scene changes.
+    /// This is only needed when the application changes the rendered image by other means.
+    pub const fn reset_accumulation(&mut self) {
+        self.renderer.reset_accumulation();
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the number of frames averaged together in the displayed image.
+    pub const fn accumulated_frames(&self) -> u32 {
+        self.renderer.accumulated_frames()
+    }
+
+    #[inline]
+    /// Freezes or resumes rendering.
+    ///
+    /// While frozen, no frame is rendered, so the last rendered frame stays displayed
+    /// without spending GPU time and no frame is accumulated. The inputs of the controllers are discarded,
+    /// so that the camera stays where the frozen frame was rendered from.
+    /// The event loop of a window waits for events instead of polling, so that no CPU time is spent either.
+    pub fn freeze_accumulation(&mut self, frozen: bool) {
+        if self.frozen && !frozen {
+            // Avoid a huge time step for the first frame after resuming
+            self.last_frame = std::time::Instant::now();
+        }
+        self.frozen = frozen;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether rendering is frozen, see [`RayTracingApp::freeze_accumulation`].
+    pub const fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Waits for the GPU to finish its work, then releases the resources used for rendering.
+    ///
+    /// It is called when the window is closed, and when the application is dropped otherwise,
+    /// so that no resource is destroyed while the GPU is still using it.
+    /// Once shut down, the application no longer renders and [`RayTracingApp::pump`] returns `false`,
+    /// while the methods rendering or changing the scene return [`RenderError::ShutDown`].
+    /// Calling it again has no effect.
+    pub fn shutdown(&mut self) {
+        if self.shut_down {
+            return;
+        }
+        self.shut_down = true;
+        self.exit_requested = true;
+
+        // Uploads may still be running on the transfer queue
+        if let Err(error) = self
+            .context
+            .transfer_queue
+            .with(|mut queue| queue.wait_idle())
+        {
+            tracing::error!("Failed to wait for the transfer queue to be idle: {error}");
+        }
+        self.renderer.shutdown();
+
+        tracing::debug!("Successfully shut down");
+    }
+
+    /// Runs the application, handing every rendered frame to `on_frame`.
+    ///
+    /// This is typically used to stream or encode the frames,
+    /// see [`RayTracingApp::set_frame_callback`] for the cost of reading them back.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the application encounters any errors during runtime.
+    pub fn run_with_frame(mut self, on_frame: render::FrameCallback) {
+        if let Err(error) = self.set_frame_callback(Some(on_frame)) {
+            if !Self::recover_from(error) {
+                return;
+            }
+        }
+        self.run(Box::new(|_| {}));
+    }
+
+    /// Run the application.
+    ///
+    /// ## Note
+    ///
+    /// Use the argument `on_waiting_for_render` to update anything unrelated to rendering while waiting for the render to complete.
+    ///
+    /// Recoverable render errors skip the frame, while fatal ones stop the application.
+    /// If you need to keep control of the thread or to handle render errors yourself,
+    /// use [`RayTracingApp::try_pump`] instead.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the application encounters any errors during runtime.
+    /// Typically, this can happen if there is a concurrency issue.
+    pub fn run(mut self, mut on_waiting_for_render: Box<dyn FnMut(u32)>) {
+        match self.config.render_surface_type {
+            RenderSurfaceType::Window(_) => {
+                // ## Panics
+                // This line cannot panic because the event loop is always `Some` for window rendering.
+                let event_loop = self.event_loop.take().unwrap();
+
+                event_loop.run(move |event, _, control_flow| {
+                    if let Err(error) =
+                        self.handle_event(&event, control_flow, &mut on_waiting_for_render)
+                    {
+                        if !Self::recover_from(error) {
+                            control_flow.set_exit();
+                        }
+                    }
+                });
             }
             #[cfg(feature = "image")]
             RenderSurfaceType::Image(_) => {
-                let Self { mut renderer, .. } = self;
-                renderer.render(&mut on_waiting_for_render);
+                if let Err(error) = self.render_image(&mut on_waiting_for_render) {
+                    tracing::error!("Failed to render image: {error}");
+                }
             }
+            RenderSurfaceType::Video(VideoDescriptor { frame_count, .. }) => {
+                while !self.exit_requested {
+                    if let Err(error) =
+                        self.render_video_frame(frame_count, &mut on_waiting_for_render)
+                    {
+                        if !Self::recover_from(error) {
+                            return;
+                        }
+                    }
+                }
+            }
+            RenderSurfaceType::Custom => {
+                while !self.renderer.is_surface_closed() {
+                    // Nothing is rendered while frozen, the surface is only checked from time to time
+                    if self.frozen {
+                        std::thread::sleep(std::time::Duration::from_millis(10));
+                    }
+                    if let Err(error) = self.render_frame(&mut on_waiting_for_render) {
+                        if !Self::recover_from(error) {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os = "ios", target_arch = "wasm32")))]
+    /// Processes the pending events and renders a single frame.
+    ///
+    /// Returns whether the application should keep running,
+    /// which allows the caller to own the loop cadence and interleave other work between frames.
+    ///
+    /// Recoverable render errors skip the frame, while fatal ones stop the application.
+    /// Use [`RayTracingApp::try_pump`] to handle them yourself.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the application encounters any errors during runtime.
+    pub fn pump(&mut self) -> bool {
+        match self.try_pump() {
+            Ok(keep_running) => keep_running,
+            Err(error) => {
+                let keep_running = Self::recover_from(error);
+                self.exit_requested |= !keep_running;
+                keep_running
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os = "ios", target_arch = "wasm32")))]
+    /// Processes the pending events and renders a single frame.
+    ///
+    /// Returns whether the application should keep running.
+    ///
+    /// For an image render surface, the image is rendered on the first call and `false` is returned.
+    /// For a video render surface, a frame of the video is rendered on every call,
+    /// `false` being returned once the last one is rendered.
+    /// For a custom render surface, a frame is rendered on every call,
+    /// `false` being returned once [`RenderSurface::is_closed`] returns `true`.
+    ///
+    /// ## Platform caveats
+    ///
+    /// This function relies on `winit`'s `run_return`, which is not available on iOS and on the web.
+    /// On Windows and macOS, it may block while the window is being moved or resized.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be rendered.
+    /// Use [`RenderError::is_recoverable`] to know whether the next frame can be rendered.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the application encounters any errors during runtime.
+    pub fn try_pump(&mut self) -> Result<bool, RenderError> {
+        use winit::platform::run_return::EventLoopExtRunReturn;
+
+        if self.exit_requested {
+            return Ok(false);
+        }
+
+        match self.config.render_surface_type {
+            RenderSurfaceType::Window(_) => {
+                let Some(mut event_loop) = self.event_loop.take() else {
+                    return Ok(false);
+                };
+
+                let mut result = Ok(());
+                event_loop.run_return(|event, _, control_flow| {
+                    let end_of_frame = matches!(event, winit::event::Event::MainEventsCleared);
+
+                    if let Err(error) = self.handle_event(&event, control_flow, &mut |_| {}) {
+                        result = Err(error);
+                    }
+
+                    if end_of_frame {
+                        *control_flow = winit::event_loop::ControlFlow::Exit;
+                    }
+                });
+
+                self.event_loop = Some(event_loop);
+                result?;
+            }
+            #[cfg(feature = "image")]
+            RenderSurfaceType::Image(_) => {
+                self.exit_requested = true;
+                self.render_image(&mut |_| {})?;
+            }
+            RenderSurfaceType::Video(VideoDescriptor { frame_count, .. }) => {
+                self.render_video_frame(frame_count, &mut |_| {})?;
+            }
+            RenderSurfaceType::Custom => {
+                if self.renderer.is_surface_closed() {
+                    self.exit_requested = true;
+                } else {
+                    self.render_frame(&mut |_| {})?;
+                }
+            }
+        }
+
+        Ok(!self.exit_requested)
+    }
+
+    #[cfg(feature = "image")]
+    /// Accumulates the frames requested by the image descriptor, then renders and saves the image.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames could not be rendered.
+    fn render_image(
+        &mut self,
+        on_waiting_for_render: &mut dyn FnMut(u32),
+    ) -> Result<(), RenderError> {
+        self.scenes[self.active_scene].wait_for_upload();
+        if let RenderSurfaceType::Image(descriptor) = &self.config.render_surface_type {
+            self.renderer
+                .accumulate(descriptor.accumulated_frames.saturating_sub(1))?;
+        }
+        self.renderer.render(on_waiting_for_render)?;
+        self.hand_frame()
+    }
+
+    /// Renders the next frame of the video, requesting the exit once the last of the `frame_count` frames is rendered.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be rendered, in which case it is not counted.
+    fn render_video_frame(
+        &mut self,
+        frame_count: u32,
+        on_waiting_for_render: &mut dyn FnMut(u32),
+    ) -> Result<(), RenderError> {
+        self.render_frame(on_waiting_for_render)?;
+        self.video_frames += 1;
+        if self.video_frames >= frame_count {
+            self.exit_requested = true;
+        }
+        Ok(())
+    }
+
+    /// Logs the given render error, returning whether the application can keep running.
+    fn recover_from(error: RenderError) -> bool {
+        if error.is_recoverable() {
+            tracing::debug!("Frame skipped: {error}");
+            true
+        } else {
+            tracing::error!("Failed to render frame: {error}");
+            false
+        }
+    }
+
+    /// Handles an event of the event loop.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if a frame had to be rendered and could not be.
+    fn handle_event(
+        &mut self,
+        event: &winit::event::Event<()>,
+        control_flow: &mut winit::event_loop::ControlFlow,
+        on_waiting_for_render: &mut dyn FnMut(u32),
+    ) -> Result<(), RenderError> {
+        for controller in &mut self.config.controllers {
+            controller.handle_event(event);
+        }
+        match event {
+            winit::event::Event::WindowEvent {
+                event: winit::event::WindowEvent::CloseRequested,
+                ..
+            } => {
+                *control_flow = winit::event_loop::ControlFlow::Exit;
+                self.shutdown();
+            }
+            winit::event::Event::WindowEvent {
+                event: winit::event::WindowEvent::Resized(size),
+                ..
+            } => {
+                // The swapchain is recreated before the next frame, minimized windows excepted
+                self.renderer.resize((size.width, size.height));
+            }
+            winit::event::Event::WindowEvent {
+                event: winit::event::WindowEvent::Focused(focused),
+                ..
+            } => {
+                // A grabbed cursor would be trapped in the window while using another one
+                self.renderer.set_focused(*focused);
+            }
+            winit::event::Event::MainEventsCleared => {
+                let (width, height) = self.renderer.surface_size();
+
+                // The window is minimized, there is nothing to render on
+                if width == 0 || height == 0 {
+                    if !self.minimized {
+                        tracing::debug!("Window minimized, rendering paused");
+                        self.minimized = true;
+                    }
+                    if !self.exit_requested {
+                        control_flow.set_wait();
+                    }
+                    return Ok(());
+                }
+
+                if self.minimized {
+                    tracing::debug!("Window restored, rendering resumed");
+                    self.minimized = false;
+                    // Avoid a huge time step for the first frame after restoring
+                    self.last_frame = std::time::Instant::now();
+                }
+
+                // A frozen window has nothing to render, so the loop waits for events instead of spinning
+                if !self.exit_requested {
+                    if self.frozen {
+                        control_flow.set_wait();
+                    } else {
+                        control_flow.set_poll();
+                    }
+                }
+
+                return self.render_frame(on_waiting_for_render);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Updates the camera from the inputs of the controllers and renders a frame.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame could not be rendered.
+    fn render_frame(
+        &mut self,
+        on_waiting_for_render: &mut dyn FnMut(u32),
+    ) -> Result<(), RenderError> {
+        if self.shut_down {
+            return Ok(());
+        }
+        if self.frozen {
+            for controller in &mut self.config.controllers {
+                controller.fetch_input();
+            }
+            return Ok(());
+        }
+
+        // Windows stay responsive while the active scene is uploaded, keeping their previous content,
+        // whereas other surfaces would only skip frames
+        let scene = &mut self.scenes[self.active_scene];
+        if matches!(
+            self.config.render_surface_type,
+            RenderSurfaceType::Window(_)
+        ) {
+            if !scene.is_uploaded() {
+                for controller in &mut self.config.controllers {
+                    controller.fetch_input();
+                }
+                self.last_frame = std::time::Instant::now();
+                return Ok(());
+            }
+        } else {
+            scene.wait_for_upload();
+        }
+
+        // Videos advance by a fixed step, whatever the time spent rendering their frames
+        #[allow(clippy::cast_precision_loss)]
+        let elapsed = match self.config.render_surface_type {
+            RenderSurfaceType::Video(VideoDescriptor { fps, .. }) => 1.0 / fps.max(1) as f32,
+            _ => self.last_frame.elapsed().as_secs_f32(),
+        };
+        self.last_frame = std::time::Instant::now();
+
+        let mut inputs = self
+            .config
+            .controllers
+            .iter_mut()
+            .map(|controller| controller.fetch_input())
+            .fold(crate::control::Inputs::default(), |mut acc, i| {
+                acc.accumulate(i);
+                acc
+            });
+        if let Some(input_filter) = &mut self.config.input_filter {
+            input_filter(&mut inputs, elapsed);
+        }
+        let camera = &mut self.config.cameras[self.config.active_camera];
+        let previous_pose = (
+            camera.position(),
+            camera.direction(),
+            camera.up(),
+            camera.right(),
+            camera.aperture(),
+            camera.focus_distance(),
+            camera.fov(),
+        );
+        match &mut self.camera_animation {
+            Some(animation) => {
+                if animation.advance(&mut **camera, inputs, elapsed) {
+                    self.camera_animation = None;
+                    tracing::debug!("Camera animation finished");
+                }
+            }
+            None => camera.process_inputs(inputs, elapsed),
         }
+
+        // Frames rendered from another point of view cannot be averaged with the next ones
+        #[allow(clippy::float_cmp)]
+        let camera_moved = (
+            camera.position(),
+            camera.direction(),
+            camera.up(),
+            camera.right(),
+            camera.aperture(),
+            camera.focus_distance(),
+            camera.fov(),
+        ) != previous_pose;
+        // The frame right after the shake ends is also rendered from another pose
+        let shaking = self.camera_shake.is_active();
+        self.camera_shake.advance(elapsed);
+        if camera_moved || shaking {
+            self.renderer.reset_accumulation();
+        }
+
+        // The frames in flight keep their own copy of the camera
+        let mut camera_data = Self::camera_data(&**camera);
+        self.camera_shake.apply(&mut camera_data);
+        self.renderer.set_camera(camera_data);
+
+        // Innacurate at high FPS
+        // tracing::trace!("FPS: {:.01}", 1.0 / elapsed);
+
+        self.renderer.render(on_waiting_for_render)?;
+        self.hand_frame()
+    }
+}
+
+impl Drop for RayTracingApp {
+    fn drop(&mut self) {
+        self.shutdown();
     }
 }
 
@@ -460,14 +1972,209 @@ impl RayTracingApp {
 pub struct RayTracingAppConfig {
     /// The type of render surface to use.
     pub render_surface_type: RenderSurfaceType,
-    /// The camera to use.
-    pub camera: Box<dyn control::camera::Camera>,
+    /// The cameras of the application, which can be switched between with
+    /// [`RayTracingApp::set_active_camera`].
+    pub cameras: Vec<Box<dyn control::camera::Camera>>,
+    /// The index of the camera used at startup.
+    ///
+    /// It must be the index of one of `cameras`,
+    /// otherwise creating the application fails with [`InitError::CameraOutOfRange`].
+    pub active_camera: usize,
     /// The controllers to use.
     pub controllers: Vec<Box<dyn control::controller::Controller>>,
+    /// An optional filter applied every frame to the inputs of all the controllers,
+    /// before they are given to the camera.
+    ///
+    /// It is given the merged inputs and the time elapsed since the last frame in seconds,
+    /// which allows to implement smoothing, dead-zones or sensitivity curves
+    /// regardless of the controllers.
+    pub input_filter: Option<control::InputFilter>,
     /// Scene data to render.
     pub scene_descriptor: shader::SceneDescriptor,
+    /// Additional scenes loaded at initialization,
+    /// which can be switched to with [`RayTracingApp::set_active_scene`].
+    ///
+    /// They all stay in device memory, which trades memory for instant switching.
+    /// A scene is only loaded if the estimated size of its geometry fits in what is left of device memory,
+    /// otherwise it and the following ones are skipped.
+    pub preloaded_scenes: Vec<shader::SceneDescriptor>,
     /// Shader parameters.
     pub shader_descriptor: shader::ShaderDescriptor,
+    /// The physical device to render on.
+    ///
+    /// The devices available can be listed beforehand with [`RayTracingApp::enumerate_devices`].
+    pub device_preference: DevicePreference,
+}
+
+impl RayTracingAppConfig {
+    #[must_use]
+    #[inline]
+    /// Returns a builder for the configuration, whose missing parts are filled with defaults.
+    pub fn builder() -> RayTracingAppConfigBuilder {
+        RayTracingAppConfigBuilder::default()
+    }
+}
+
+#[derive(Default)]
+#[allow(clippy::module_name_repetitions)]
+/// A builder for [`RayTracingAppConfig`].
+///
+/// Without explicit values, the configuration renders an empty scene to a default window,
+/// through a first person camera and without controllers.
+pub struct RayTracingAppConfigBuilder {
+    /// The type of render surface to use, a default window if `None`.
+    render_surface_type: Option<RenderSurfaceType>,
+    /// The cameras of the application, a default first person camera being used if empty.
+    cameras: Vec<Box<dyn control::camera::Camera>>,
+    /// The index of the camera used at startup.
+    active_camera: usize,
+    /// The controllers to use.
+    controllers: Vec<Box<dyn control::controller::Controller>>,
+    /// The filter applied to the inputs of the controllers.
+    input_filter: Option<control::InputFilter>,
+    /// Scene data to render.
+    scene_descriptor: shader::SceneDescriptor,
+    /// Additional scenes loaded at initialization.
+    preloaded_scenes: Vec<shader::SceneDescriptor>,
+    /// Shader parameters.
+    shader_descriptor: shader::ShaderDescriptor,
+    /// The physical device to render on.
+    device_preference: DevicePreference,
+}
+
+impl RayTracingAppConfigBuilder {
+    #[must_use]
+    #[inline]
+    /// Renders to a window described by the given descriptor.
+    pub fn window(self, window_descriptor: WindowDescriptor) -> Self {
+        self.render_surface(RenderSurfaceType::Window(window_descriptor))
+    }
+
+    #[cfg(feature = "image")]
+    #[must_use]
+    #[inline]
+    /// Renders to an image described by the given descriptor.
+    pub fn image(self, image_descriptor: ImageDescriptor) -> Self {
+        self.render_surface(RenderSurfaceType::Image(image_descriptor))
+    }
+
+    #[must_use]
+    #[inline]
+    /// Renders to a video described by the given descriptor, which requires `ffmpeg` at runtime.
+    pub fn video(self, video_descriptor: VideoDescriptor) -> Self {
+        self.render_surface(RenderSurfaceType::Video(video_descriptor))
+    }
+
+    #[must_use]
+    #[inline]
+    /// Sets the type of render surface to use.
+    pub fn render_surface(mut self, render_surface_type: RenderSurfaceType) -> Self {
+        self.render_surface_type = Some(render_surface_type);
+        self
+    }
+
+    #[must_use]
+    /// Adds a camera, the first one added being used at startup.
+    pub fn camera(mut self, camera: impl control::camera::Camera + 'static) -> Self {
+        self.cameras.push(Box::new(camera));
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Sets the index of the camera used at startup, among the cameras added in order.
+    pub const fn active_camera(mut self, index: usize) -> Self {
+        self.active_camera = index;
+        self
+    }
+
+    #[must_use]
+    /// Adds a controller.
+    pub fn add_controller(
+        mut self,
+        controller: impl control::controller::Controller + 'static,
+    ) -> Self {
+        self.controllers.push(Box::new(controller));
+        self
+    }
+
+    #[must_use]
+    /// Sets the filter applied every frame to the inputs of all the controllers,
+    /// see [`RayTracingAppConfig::input_filter`].
+    pub fn input_filter(
+        mut self,
+        input_filter: impl FnMut(&mut control::Inputs, f32) + 'static,
+    ) -> Self {
+        self.input_filter = Some(Box::new(input_filter));
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Sets the scene to render.
+    pub fn scene(mut self, scene_descriptor: shader::SceneDescriptor) -> Self {
+        self.scene_descriptor = scene_descriptor;
+        self
+    }
+
+    #[must_use]
+    /// Adds a scene loaded at initialization, see [`RayTracingAppConfig::preloaded_scenes`].
+    pub fn preload_scene(mut self, scene_descriptor: shader::SceneDescriptor) -> Self {
+        self.preloaded_scenes.push(scene_descriptor);
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Sets the shader parameters.
+    pub fn shader(mut self, shader_descriptor: shader::ShaderDescriptor) -> Self {
+        self.shader_descriptor = shader_descriptor;
+        self
+    }
+
+    #[must_use]
+    #[inline]
+    /// Sets the physical device to render on, a discrete GPU being preferred by default.
+    pub fn device_preference(mut self, device_preference: DevicePreference) -> Self {
+        self.device_preference = device_preference;
+        self
+    }
+
+    #[must_use]
+    /// Builds the configuration, filling the missing parts with defaults.
+    ///
+    /// An active camera index out of range falls back to the first camera.
+    pub fn build(self) -> RayTracingAppConfig {
+        let mut cameras = self.cameras;
+        if cameras.is_empty() {
+            cameras.push(Box::<control::camera::first_person::FirstPerson>::default());
+        }
+
+        let active_camera = if self.active_camera < cameras.len() {
+            self.active_camera
+        } else {
+            tracing::warn!(
+                "Active camera index {} out of range, {} cameras available: using the first one",
+                self.active_camera,
+                cameras.len()
+            );
+            0
+        };
+
+        RayTracingAppConfig {
+            render_surface_type: self
+                .render_surface_type
+                .unwrap_or_else(|| RenderSurfaceType::Window(WindowDescriptor::default())),
+            cameras,
+            active_camera,
+            controllers: self.controllers,
+            input_filter: self.input_filter,
+            scene_descriptor: self.scene_descriptor,
+            preloaded_scenes: self.preloaded_scenes,
+            shader_descriptor: self.shader_descriptor,
+            device_preference: self.device_preference,
+        }
+    }
 }
 
 #[non_exhaustive]
@@ -480,4 +2187,11 @@ pub enum RenderSurfaceType {
     #[cfg(feature = "image")]
     /// An image.
     Image(ImageDescriptor),
+    /// A video, encoded by an `ffmpeg` subprocess.
+    ///
+    /// The frames are rendered one after the other, the cameras advancing by a fixed step between them,
+    /// and the application exits once the last one is rendered.
+    Video(VideoDescriptor),
+    /// A surface provided by the user, see [`RayTracingApp::with_surface`].
+    Custom,
 }
@@ -4,30 +4,143 @@
 
 use std::sync::Arc;
 use vulkano::{
-    buffer::Subbuffer,
-    command_buffer::{self, allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder},
+    buffer::{subbuffer::BufferReadGuard, Subbuffer},
+    command_buffer::{
+        allocator::{StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo},
+        AutoCommandBufferBuilder, CommandBufferExecFuture, CommandBufferUsage, CopyImageInfo,
+        CopyImageToBufferInfo,
+    },
     descriptor_set::{
         allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
     },
     device::{Device, Queue},
-    image::view::ImageView,
+    image::{
+        sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo},
+        view::ImageView,
+        ImageCreateInfo, ImageUsage,
+    },
+    memory::allocator::{AllocationCreateInfo, StandardMemoryAllocator},
     pipeline::{
         compute::ComputePipelineCreateInfo, layout::PipelineDescriptorSetLayoutCreateInfo,
         ComputePipeline, Pipeline, PipelineLayout, PipelineShaderStageCreateInfo,
     },
-    sync::GpuFuture,
+    query::{QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType},
+    shader::SpecializationConstant,
+    sync::{future::FenceSignalFuture, GpuFuture, PipelineStage},
 };
 
+pub mod external;
 #[cfg(feature = "image")]
 pub mod image;
+pub mod video;
 pub mod window;
 
-#[derive(Copy, Clone, Debug)]
-/// Represents an error that occurs when acquiring an image view.
-pub struct AcquireError;
-#[derive(Copy, Clone, Debug)]
-/// Represents an error that occurs when presenting an image.
-pub struct PresentError;
+#[allow(clippy::module_name_repetitions)]
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Represents an error that occurs while rendering a frame.
+pub enum RenderError {
+    /// The render surface is out of date, typically because it has been resized.
+    ///
+    /// The surface is recreated and the next frame can be rendered.
+    OutOfDate,
+    /// The device has been lost, typically because of a driver crash or a GPU reset.
+    DeviceLost,
+    /// A command buffer could not be submitted, because one of its resources was in use.
+    ///
+    /// The cause is logged when the error occurs.
+    Execution,
+    /// A buffer could not be accessed from the host, because it was in use by the device.
+    ///
+    /// The cause is logged when the error occurs.
+    HostAccess,
+    /// The rendered image or one of its auxiliary outputs could not be saved.
+    ///
+    /// The cause is logged when the error occurs.
+    Save,
+    /// The index of the scene to switch to is out of range, given along with the number of loaded scenes.
+    SceneOutOfRange(usize, usize),
+    /// The index of the camera to switch to is out of range, given along with the number of cameras.
+    CameraOutOfRange(usize, usize),
+    /// The index of the model to move is out of range, given along with the number of models of the scene.
+    ModelOutOfRange(usize, usize),
+    /// The application has been shut down, see [`crate::RayTracingApp::shutdown`],
+    /// so nothing can be rendered anymore.
+    ShutDown,
+    /// Any other Vulkan error.
+    Vulkan(vulkano::VulkanError),
+}
+
+impl RenderError {
+    #[must_use]
+    #[inline]
+    /// Returns whether the next frame can be rendered after this error.
+    pub const fn is_recoverable(&self) -> bool {
+        matches!(self, Self::OutOfDate)
+    }
+}
+
+impl From<vulkano::VulkanError> for RenderError {
+    fn from(error: vulkano::VulkanError) -> Self {
+        match error {
+            vulkano::VulkanError::OutOfDate => Self::OutOfDate,
+            vulkano::VulkanError::DeviceLost => Self::DeviceLost,
+            error => Self::Vulkan(error),
+        }
+    }
+}
+
+impl From<vulkano::command_buffer::CommandBufferExecError> for RenderError {
+    fn from(error: vulkano::command_buffer::CommandBufferExecError) -> Self {
+        tracing::error!("Failed to execute command buffer: {error}");
+        Self::Execution
+    }
+}
+
+impl From<vulkano::sync::HostAccessError> for RenderError {
+    fn from(error: vulkano::sync::HostAccessError) -> Self {
+        tracing::error!("Failed to access buffer from the host: {error}");
+        Self::HostAccess
+    }
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfDate => f.write_str("the render surface is out of date"),
+            Self::DeviceLost => f.write_str("the device has been lost"),
+            Self::Execution => f.write_str("a command buffer could not be executed"),
+            Self::HostAccess => f.write_str("a buffer could not be accessed from the host"),
+            Self::Save => f.write_str("the rendered image could not be saved"),
+            Self::SceneOutOfRange(index, count) => {
+                write!(f, "scene index {index} out of range, {count} scenes loaded")
+            }
+            Self::CameraOutOfRange(index, count) => {
+                write!(
+                    f,
+                    "camera index {index} out of range, {count} cameras given"
+                )
+            }
+            Self::ModelOutOfRange(index, count) => {
+                write!(
+                    f,
+                    "model index {index} out of range, the scene has {count} models"
+                )
+            }
+            Self::ShutDown => f.write_str("the application has been shut down"),
+            Self::Vulkan(error) => write!(f, "a Vulkan error occured: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Vulkan(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 #[allow(clippy::module_name_repetitions)]
 /// The type of a render command buffer.
@@ -46,6 +159,11 @@ pub trait RenderSurface {
     ///
     /// Views must be in the same order as the one used for indexing when returning index from `acquire()`.
     /// This function is used to generate command buffers.
+    ///
+    /// The renderer copies its output to the images of the views, so they must have
+    /// the size of the surface and the `TRANSFER_DST` usage. Their format must be either
+    /// `R8G8B8A8_UNORM`, for gamma-corrected colors, or `R32G32B32A32_SFLOAT`,
+    /// for linear colors whose alpha channel is unspecified.
     fn views(&self) -> &[Arc<ImageView>];
     /// Acquires the next image view.
     ///
@@ -54,7 +172,7 @@ pub trait RenderSurface {
     /// ## Errors
     ///
     /// This function returns an error if the image view cannot be acquired.
-    fn acquire(&mut self) -> Result<(u32, Box<dyn vulkano::sync::GpuFuture>), AcquireError>;
+    fn acquire(&mut self) -> Result<(u32, Box<dyn vulkano::sync::GpuFuture>), RenderError>;
     /// Presents the rendered image.
     ///
     /// ## Errors
@@ -64,7 +182,86 @@ pub trait RenderSurface {
         &mut self,
         render_future: Box<dyn vulkano::sync::GpuFuture>,
         queue: &Arc<Queue>,
-    ) -> Result<(), PresentError>;
+    ) -> Result<(), RenderError>;
+
+    /// Notifies the surface that it has been resized,
+    /// so that it can recreate its views before the next frame.
+    ///
+    /// The default implementation does nothing, for surfaces that cannot be resized.
+    fn resized(&mut self) {}
+
+    /// Returns whether the views have been recreated since the last call,
+    /// in which case the renderer records its command buffers again.
+    ///
+    /// It is called right after `acquire()`.
+    /// The default implementation returns `false`, for surfaces whose views never change.
+    fn take_recreated_views(&mut self) -> bool {
+        false
+    }
+
+    /// Switches the surface to the given window mode.
+    ///
+    /// The default implementation does nothing, for surfaces that are not windows.
+    fn set_window_mode(&mut self, _mode: window::Mode) {}
+
+    /// Sets the present mode of the surface.
+    ///
+    /// The default implementation does nothing, for surfaces that are not windows.
+    fn set_present_mode(&mut self, _present_mode: window::PresentMode) {}
+
+    /// Grabs or releases the cursor.
+    ///
+    /// The default implementation does nothing, for surfaces that are not windows.
+    fn set_cursor_grab(&mut self, _grab: bool) {}
+
+    /// Notifies the surface that it gained or lost the focus.
+    ///
+    /// The default implementation does nothing, for surfaces that are not windows.
+    fn set_focused(&mut self, _focused: bool) {}
+
+    /// Returns the number of frames that may be rendered at the same time,
+    /// for which the renderer keeps separate uniforms and command buffers.
+    ///
+    /// It is read once, when the renderer is created.
+    /// The default implementation returns 1, for surfaces that wait for every frame.
+    fn frames_in_flight(&self) -> usize {
+        1
+    }
+
+    /// Returns the views the auxiliary outputs of the renderer are copied to,
+    /// in the same order as `views()`.
+    ///
+    /// The renderer only writes its auxiliary outputs if this is not empty.
+    /// The default implementation returns no views, for surfaces that only need colors.
+    fn auxiliary_views(&self) -> &[AuxiliaryViews] {
+        &[]
+    }
+
+    /// Returns whether the surface is closed, in which case [`crate::RayTracingApp::run`] returns.
+    ///
+    /// It is checked before every frame of custom render surfaces.
+    /// The default implementation returns `false`, for surfaces rendered to until the application is dropped.
+    fn is_closed(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone)]
+/// Represents the auxiliary outputs of the renderer, typically fed to denoisers.
+///
+/// They are averaged over the accumulated frames, like the colors.
+/// The images must have the size of the surface and the `TRANSFER_DST` usage.
+pub struct AuxiliaryViews {
+    /// The distance from the camera to the first surface hit, in a `R32_SFLOAT` image.
+    ///
+    /// Pixels where no surface is hit hold the maximum distance of the shader.
+    pub depth: Arc<ImageView>,
+    /// The world-space normal of the first surface hit, in a `R32G32B32A32_SFLOAT` image.
+    pub normal: Arc<ImageView>,
+    /// The linear albedo of the first surface hit, in a `R32G32B32A32_SFLOAT` image.
+    ///
+    /// Pixels where no surface is hit hold the color of the background.
+    pub albedo: Arc<ImageView>,
 }
 
 #[derive(Clone)]
@@ -80,6 +277,193 @@ pub struct Buffers {
     pub models_buffer: Subbuffer<crate::shader::ModelsBuffer>,
     /// The BVHs buffer.
     pub bvhs_buffer: Subbuffer<crate::shader::BvhBuffer>,
+    /// The top-level BVH buffer.
+    pub tlas_buffer: Subbuffer<crate::shader::TlasBuffer>,
+    /// The lights buffer.
+    pub lights_buffer: Subbuffer<crate::shader::LightsBuffer>,
+    /// The emissive triangles buffer.
+    pub emissive_triangles_buffer: Subbuffer<crate::shader::EmissiveTrianglesBuffer>,
+    /// The environment map, if the scene has one.
+    pub environment_map: Option<Arc<ImageView>>,
+    /// The distribution used to importance sample the environment map.
+    pub environment_cdf_buffer: Subbuffer<crate::shader::EnvironmentCdfBuffer>,
+    /// The array of the diffuse textures, if the scene has any.
+    pub textures: Option<Arc<ImageView>>,
+    /// The filter used when sampling the textures.
+    pub texture_filter: crate::shader::TextureFilter,
+}
+
+impl Buffers {
+    #[must_use]
+    /// Creates the buffers used to render the given scene.
+    pub(crate) fn new(
+        camera_uniform: Subbuffer<crate::shader::CameraBuffer>,
+        scene: &crate::shader::model::LoadedModels,
+    ) -> Self {
+        Self {
+            camera_uniform,
+            triangles_buffer: scene.triangles_buffer.clone(),
+            materials_buffer: scene.materials_buffer.clone(),
+            models_buffer: scene.models_buffer.clone(),
+            bvhs_buffer: scene.bvhs_buffer.clone(),
+            tlas_buffer: scene.tlas_buffer.clone(),
+            lights_buffer: scene.lights_buffer.clone(),
+            emissive_triangles_buffer: scene.emissive_triangles_buffer.clone(),
+            environment_map: scene.environment_map.clone(),
+            environment_cdf_buffer: scene.environment_cdf_buffer.clone(),
+            textures: scene.textures.clone(),
+            texture_filter: scene.texture_filter,
+        }
+    }
+}
+
+/// A callback given every frame read back from the device, see [`crate::RayTracingApp::set_frame_callback`].
+pub type FrameCallback = Box<dyn FnMut(FrameView<'_>)>;
+
+/// Represents a frame read back from the device, once it has finished rendering.
+///
+/// The frame holds a read lock on the host copy of the output image,
+/// so it must be dropped before the next frame is rendered.
+pub struct FrameView<'a> {
+    /// The width of the frame, in pixels.
+    width: u32,
+    /// The height of the frame, in pixels.
+    height: u32,
+    /// The host copy of the output image.
+    pixels: BufferReadGuard<'a, [u8]>,
+}
+
+impl FrameView<'_> {
+    #[must_use]
+    #[inline]
+    /// Returns the width of the frame, in pixels.
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the height of the frame, in pixels.
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the gamma-corrected RGBA8 pixels of the frame, row by row, starting from the top.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Represents a rectangular region of the render surface, in pixels.
+pub struct Region {
+    /// The offset of the region from the left of the surface.
+    pub x: u32,
+    /// The offset of the region from the top of the surface.
+    pub y: u32,
+    /// The width of the region.
+    pub width: u32,
+    /// The height of the region.
+    pub height: u32,
+}
+
+impl Region {
+    #[must_use]
+    #[inline]
+    /// Returns the region covering a whole surface of the given size.
+    pub const fn full(width: u32, height: u32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+
+    #[must_use]
+    /// Returns the part of the region that is inside a surface of the given size.
+    fn clamped(self, width: u32, height: u32) -> Self {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Self {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the region contains no pixel.
+    pub const fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+/// The future of a submitted frame, signaled once the GPU has finished rendering it.
+type FrameFuture = Arc<FenceSignalFuture<CommandBufferExecFuture<Box<dyn GpuFuture>>>>;
+
+/// The resources of one of the frames that may be in flight at the same time.
+struct FrameSlot {
+    /// The camera uniform read by the frame, written when the frame is submitted.
+    camera_uniform: Subbuffer<crate::shader::CameraBuffer>,
+    /// The uniform holding the number of frames accumulated before the frame,
+    /// and the factors of its colors from the exposure and the white point.
+    frame_count_uniform: Subbuffer<crate::shader::source::AccumulationBuffer>,
+    /// The render command buffers of the frame, one per view.
+    command_buffers: Box<[RenderCommandBuffer]>,
+    /// The last frame submitted with these resources, which may still be executing on the GPU.
+    in_flight: Option<FrameFuture>,
+}
+
+/// The timestamp queries written around the dispatch of every frame, to measure its duration on the GPU.
+struct TimestampQueries {
+    /// The pool of the two queries, written before and after the dispatch.
+    pool: Arc<QueryPool>,
+    /// The number of nanoseconds per timestamp tick.
+    period: f64,
+    /// The mask of the valid bits of the timestamps.
+    mask: u64,
+}
+
+impl TimestampQueries {
+    #[must_use]
+    /// Creates the timestamp queries of the given queue, or returns `None` if it does not support timestamps.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the query pool cannot be created.
+    fn new(queue: &Queue) -> Option<Self> {
+        let device = queue.device();
+        let physical_device = device.physical_device();
+        let Some(valid_bits) = physical_device.queue_family_properties()
+            [queue.queue_family_index() as usize]
+            .timestamp_valid_bits
+        else {
+            tracing::warn!(
+                "The render queue does not support timestamps, GPU profiling is disabled"
+            );
+            return None;
+        };
+
+        let pool = QueryPool::new(
+            device.clone(),
+            QueryPoolCreateInfo {
+                query_count: 2,
+                ..QueryPoolCreateInfo::query_type(QueryType::Timestamp)
+            },
+        )
+        .unwrap();
+
+        Some(Self {
+            pool,
+            period: f64::from(physical_device.properties().timestamp_period),
+            mask: u64::MAX >> (64 - valid_bits.min(64)),
+        })
+    }
 }
 
 /// Represents a renderer.
@@ -87,39 +471,122 @@ pub(crate) struct Renderer {
     /// The queue used by the renderer.
     queue: Arc<Queue>,
     /// The compute pipeline used by the renderer.
-    _pipeline: Arc<ComputePipeline>,
+    pipeline: Arc<ComputePipeline>,
     /// The render surface used by the renderer.
     render_surface: Box<dyn RenderSurface>,
-    /// The render command buffers used by the renderer.
-    render_command_buffers: Box<[RenderCommandBuffer]>,
+    /// The image the shader renders to, which is then copied to the view being presented.
+    ///
+    /// As it persists across frames, parts of it can be re-rendered
+    /// while the rest of the previous frame is kept.
+    output_image: Arc<ImageView>,
+    /// The image the frames are averaged into while the camera does not move.
+    ///
+    /// It holds linear colors, so it is the one copied to views with a float format.
+    accumulation_image: Arc<ImageView>,
+    /// The direction numbers of the Sobol sequence, bound even when white noise is selected,
+    /// as the shader always expects them.
+    sobol_buffer: Subbuffer<crate::shader::source::SobolBuffer>,
+    /// The number of frames accumulated since the last reset.
+    frame_count: u32,
+    /// Whether the shader writes its auxiliary outputs,
+    /// which is the case if the render surface has auxiliary views.
+    auxiliary_outputs: bool,
+    /// The images the auxiliary outputs are written to,
+    /// which are only 1x1 placeholders if `auxiliary_outputs` is not set.
+    auxiliary_images: AuxiliaryViews,
+    /// The sampler used to sample the environment map.
+    environment_sampler: Arc<Sampler>,
+    /// The image bound in place of the environment map when the scene has none,
+    /// as the shader always expects one.
+    fallback_environment_map: Arc<ImageView>,
+    /// The sampler used to sample the diffuse textures, with the filter of the buffers.
+    texture_sampler: Arc<Sampler>,
+    /// The anisotropy level of the texture samplers, if anisotropic filtering is enabled.
+    sampler_anisotropy: Option<f32>,
+    /// The array bound in place of the textures when the scene has none,
+    /// as the shader always expects one.
+    fallback_textures: Arc<ImageView>,
+    /// The host buffer the output image is copied to after every presented frame,
+    /// if the frames are read back.
+    frame_readback: Option<Subbuffer<[u8]>>,
+    /// The memory allocator, used to reallocate the output, accumulation and auxiliary images
+    /// when the surface is resized.
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    /// The allocator dedicated to the render command buffers.
+    ///
+    /// As it is only used for them, the command buffers it allocates
+    /// are recycled when the render command buffers are recreated.
+    command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    /// The descriptor set allocator.
+    descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
     /// The buffers used by the renderer.
-    _buffers: Buffers,
+    buffers: Buffers,
     /// Shader parameters descriptor.
-    _shader_descriptor: crate::shader::ShaderDescriptor,
+    shader_descriptor: crate::shader::ShaderDescriptor,
+    /// The resources of the frames that may be in flight, used in turn.
+    frame_slots: Box<[FrameSlot]>,
+    /// The index of the frame slot of the next frame.
+    current_slot: usize,
+    /// The camera of the next frame, written to the camera uniform of its slot when it is submitted.
+    camera: crate::shader::source::Camera,
+    /// The region the frames are restricted to, if any, the rest of the output being left untouched.
+    render_region: Option<Region>,
+    /// The timestamp queries written around the dispatch, if GPU profiling is enabled.
+    timestamp_queries: Option<TimestampQueries>,
+    /// The size of the work groups the shader is specialized with, within the limits of the device.
+    work_group_size: [u32; 2],
+    /// The time the GPU spent on the dispatch of the last completed frame, if it was profiled.
+    last_gpu_time: Option<std::time::Duration>,
 }
 
 impl Renderer {
-    #[must_use]
+    #[allow(clippy::too_many_arguments)]
     /// Creates a new renderer.
     ///
+    /// The environment map and the textures are sampled with the given anisotropy level, if any.
+    ///
+    /// The shader is specialized with the work group size of the shader descriptor,
+    /// or the default one if the device does not support it.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`crate::InitError::EntryPointNotFound`]
+    /// if the shader has no entry point with the name given in the shader descriptor.
+    ///
     /// ## Panics
     ///
     /// This function panics if the renderer cannot be created, typically due to pipeline creation failure.
     pub fn new(
         device: &Arc<Device>,
         queue: &Arc<Queue>,
+        memory_allocator: &Arc<StandardMemoryAllocator>,
         descriptor_set_allocator: &Arc<StandardDescriptorSetAllocator>,
-        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+        sampler_anisotropy: Option<f32>,
         render_surface: Box<dyn RenderSurface>,
         buffers: &Buffers,
         shader_descriptor: crate::shader::ShaderDescriptor,
-    ) -> Self {
-        let (width, height) = render_surface.size();
-
+    ) -> Result<Self, crate::InitError> {
+        let work_group_size =
+            Self::supported_work_group_size(device, shader_descriptor.work_group_size);
         let pipeline = {
             let stage = {
-                let shader = crate::shader::source::load_compute(device.clone()).unwrap();
-                PipelineShaderStageCreateInfo::new(shader.entry_point("main").unwrap())
+                let shader = crate::shader::source::load_compute(device.clone())
+                    .unwrap()
+                    .specialize(
+                        [
+                            (0, SpecializationConstant::U32(work_group_size[0])),
+                            (1, SpecializationConstant::U32(work_group_size[1])),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    )
+                    .unwrap();
+                let entry_point = shader
+                    .entry_point(&shader_descriptor.entry_point)
+                    .ok_or_else(|| {
+                        crate::InitError::EntryPointNotFound(shader_descriptor.entry_point.clone())
+                    })?;
+                PipelineShaderStageCreateInfo::new(entry_point)
             };
             tracing::trace!("Shader loaded");
 
@@ -140,163 +607,1113 @@ impl Renderer {
         };
         tracing::debug!("Pipeline created");
 
-        let work_group_count = [(width + 15) / 16, (height + 15) / 16, 1];
-        let descriptor_set_layout = pipeline.layout().set_layouts().first().unwrap();
-        let render_command_buffers = render_surface
-            .views()
-            .iter()
-            .map(|view| {
-                let descriptor_set = PersistentDescriptorSet::new(
-                    descriptor_set_allocator,
-                    descriptor_set_layout.clone(),
-                    [
-                        WriteDescriptorSet::image_view(0, view.clone()),
-                        WriteDescriptorSet::buffer(1, buffers.camera_uniform.clone()),
-                        WriteDescriptorSet::buffer(2, buffers.triangles_buffer.clone()),
-                        WriteDescriptorSet::buffer(3, buffers.materials_buffer.clone()),
-                        WriteDescriptorSet::buffer(4, buffers.models_buffer.clone()),
-                        WriteDescriptorSet::buffer(5, buffers.bvhs_buffer.clone()),
-                    ],
-                    [],
-                )
-                .unwrap();
+        // The first slot uses the camera uniform of the buffers, so that a single frame in flight
+        // behaves as if the renderer read it directly
+        let frame_slots = (0..render_surface.frames_in_flight().max(1))
+            .map(|slot| FrameSlot {
+                camera_uniform: if slot == 0 {
+                    buffers.camera_uniform.clone()
+                } else {
+                    crate::buffer::new_uniform(memory_allocator).unwrap()
+                },
+                frame_count_uniform: crate::buffer::new_uniform(memory_allocator).unwrap(),
+                command_buffers: Box::new([]),
+                in_flight: None,
+            })
+            .collect::<Box<[_]>>();
+        if frame_slots.len() > 1 {
+            tracing::debug!("Rendering with {} frames in flight", frame_slots.len());
+        }
 
-                let mut builder = AutoCommandBufferBuilder::primary(
-                    command_buffer_allocator,
-                    queue.queue_family_index(),
-                    command_buffer::CommandBufferUsage::MultipleSubmit,
-                )
-                .unwrap();
+        // One command buffer per view and frame slot, allocated in a single batch
+        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
+            device.clone(),
+            StandardCommandBufferAllocatorCreateInfo {
+                primary_buffer_count: render_surface.views().len() * frame_slots.len(),
+                secondary_buffer_count: 0,
+                ..Default::default()
+            },
+        ));
 
-                builder
-                    .bind_pipeline_compute(pipeline.clone())
-                    .unwrap()
-                    .push_constants(
-                        pipeline.layout().clone(),
-                        0,
-                        crate::shader::source::ShaderConstants::from(shader_descriptor),
-                    )
-                    .unwrap()
-                    .bind_descriptor_sets(
-                        vulkano::pipeline::PipelineBindPoint::Compute,
-                        pipeline.layout().clone(),
-                        0,
-                        vec![descriptor_set],
-                    )
-                    .unwrap()
-                    .dispatch(work_group_count)
-                    .unwrap();
-                builder.build().unwrap()
-            })
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
-        tracing::debug!("Command buffers created");
+        let size = Self::views_size(&*render_surface);
+        let output_image = Self::create_output_image(memory_allocator, size);
+        let accumulation_image = Self::create_accumulation_image(memory_allocator, size);
+        let sobol_buffer = crate::shader::sobol::new_buffer(memory_allocator);
+        let auxiliary_outputs = !render_surface.auxiliary_views().is_empty();
+        let auxiliary_images = Self::create_auxiliary_images(
+            memory_allocator,
+            if auxiliary_outputs { size } else { (1, 1) },
+        );
 
-        Self {
+        let environment_sampler = Self::create_environment_sampler(device, sampler_anisotropy);
+        let fallback_environment_map = {
+            let image = vulkano::image::Image::new(
+                memory_allocator.clone(),
+                ImageCreateInfo {
+                    format: vulkano::format::Format::R32G32B32A32_SFLOAT,
+                    extent: [1, 1, 1],
+                    usage: ImageUsage::SAMPLED,
+                    ..Default::default()
+                },
+                AllocationCreateInfo::default(),
+            )
+            .unwrap();
+            ImageView::new_default(image).unwrap()
+        };
+
+        let texture_sampler =
+            Self::create_texture_sampler(device, buffers.texture_filter, sampler_anisotropy);
+        let fallback_textures = {
+            let image = vulkano::image::Image::new(
+                memory_allocator.clone(),
+                ImageCreateInfo {
+                    format: vulkano::format::Format::R8G8B8A8_SRGB,
+                    extent: [1, 1, 1],
+                    usage: ImageUsage::SAMPLED,
+                    ..Default::default()
+                },
+                AllocationCreateInfo::default(),
+            )
+            .unwrap();
+            ImageView::new(
+                image.clone(),
+                vulkano::image::view::ImageViewCreateInfo {
+                    view_type: vulkano::image::view::ImageViewType::Dim2dArray,
+                    ..vulkano::image::view::ImageViewCreateInfo::from_image(&image)
+                },
+            )
+            .unwrap()
+        };
+
+        let mut renderer = Self {
             queue: queue.clone(),
-            _pipeline: pipeline,
+            pipeline,
             render_surface,
-            render_command_buffers,
-            _buffers: buffers.clone(),
-            _shader_descriptor: shader_descriptor,
+            output_image,
+            accumulation_image,
+            sobol_buffer,
+            frame_count: 0,
+            auxiliary_outputs,
+            auxiliary_images,
+            environment_sampler,
+            fallback_environment_map,
+            texture_sampler,
+            sampler_anisotropy,
+            fallback_textures,
+            frame_readback: None,
+            memory_allocator: memory_allocator.clone(),
+            command_buffer_allocator,
+            descriptor_set_allocator: descriptor_set_allocator.clone(),
+            buffers: buffers.clone(),
+            shader_descriptor,
+            frame_slots,
+            current_slot: 0,
+            camera: buffers.camera_uniform.read().unwrap().camera,
+            render_region: None,
+            timestamp_queries: None,
+            work_group_size,
+            last_gpu_time: None,
+        };
+        renderer.record_frame_slots();
+        tracing::debug!("Command buffers created");
+
+        Ok(renderer)
+    }
+
+    #[must_use]
+    /// Creates the sampler of the diffuse textures, with the given filter.
+    ///
+    /// The textures wrap around, and linearly filtered ones use the given anisotropy level if any.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the sampler cannot be created.
+    fn create_texture_sampler(
+        device: &Arc<Device>,
+        filter: crate::shader::TextureFilter,
+        sampler_anisotropy: Option<f32>,
+    ) -> Arc<Sampler> {
+        let (filter, anisotropy) = match filter {
+            crate::shader::TextureFilter::Linear => (Filter::Linear, sampler_anisotropy),
+            crate::shader::TextureFilter::Nearest => (Filter::Nearest, None),
+        };
+
+        Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                mag_filter: filter,
+                min_filter: filter,
+                anisotropy,
+                ..SamplerCreateInfo::simple_repeat_linear_no_mipmap()
+            },
+        )
+        .unwrap()
+    }
+
+    #[must_use]
+    /// Creates the sampler of the environment map.
+    ///
+    /// The map wraps around horizontally, but not over the poles.
+    /// It is filtered linearly if the device supports it for float images.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the sampler cannot be created.
+    fn create_environment_sampler(
+        device: &Arc<Device>,
+        sampler_anisotropy: Option<f32>,
+    ) -> Arc<Sampler> {
+        let filter = if device
+            .physical_device()
+            .format_properties(vulkano::format::Format::R32G32B32A32_SFLOAT)
+            .is_ok_and(|properties| {
+                properties
+                    .optimal_tiling_features
+                    .intersects(vulkano::format::FormatFeatures::SAMPLED_IMAGE_FILTER_LINEAR)
+            }) {
+            Filter::Linear
+        } else {
+            Filter::Nearest
+        };
+
+        Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                mag_filter: filter,
+                min_filter: filter,
+                address_mode: [
+                    SamplerAddressMode::Repeat,
+                    SamplerAddressMode::ClampToEdge,
+                    SamplerAddressMode::ClampToEdge,
+                ],
+                anisotropy: sampler_anisotropy,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[must_use]
+    /// Returns the given work group size if the device supports it, or the default one otherwise.
+    ///
+    /// Each dimension must be within the limits of the device, as must be the number of invocations of a work group.
+    fn supported_work_group_size(device: &Device, requested: [u32; 2]) -> [u32; 2] {
+        let properties = device.physical_device().properties();
+        let [max_width, max_height, _] = properties.max_compute_work_group_size;
+        let [width, height] = requested;
+
+        let supported = width > 0
+            && height > 0
+            && width <= max_width
+            && height <= max_height
+            && width.saturating_mul(height) <= properties.max_compute_work_group_invocations;
+        if supported {
+            tracing::debug!("Work groups of {width}x{height} pixels");
+            requested
+        } else {
+            tracing::warn!(
+                "Work groups of {width}x{height} pixels exceed the limits of the device \
+                ({max_width}x{max_height}, {} invocations), falling back to the default size",
+                properties.max_compute_work_group_invocations
+            );
+            crate::shader::DEFAULT_WORK_GROUP_SIZE
         }
     }
 
-    /// Recreates the command buffers, typically when the render surface is resized.
+    #[must_use]
+    /// Returns the size of the images of the views of the given surface.
+    ///
+    /// It may differ from the size of the surface while it is being resized,
+    /// until the views are recreated.
+    fn views_size(render_surface: &dyn RenderSurface) -> (u32, u32) {
+        render_surface.views().first().map_or_else(
+            || render_surface.size(),
+            |view| {
+                let [width, height, _] = view.image().extent();
+                (width, height)
+            },
+        )
+    }
+
+    #[must_use]
+    /// Creates the image the shader renders to, for a surface of the given size.
     ///
     /// ## Panics
     ///
-    /// This function panics if the command buffers cannot be recreated, typically if the pipeline is out of date
+    /// This function panics if the image cannot be allocated.
+    fn create_output_image(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        size: (u32, u32),
+    ) -> Arc<ImageView> {
+        Self::create_storage_image(
+            memory_allocator,
+            size,
+            vulkano::format::Format::R8G8B8A8_UNORM,
+            ImageUsage::TRANSFER_SRC,
+        )
+    }
+
+    #[must_use]
+    /// Creates the host buffer the output image is copied to when the frames are read back.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the buffer cannot be allocated.
+    fn create_frame_readback(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        output_image: &ImageView,
+    ) -> Subbuffer<[u8]> {
+        let [width, height, _] = output_image.image().extent();
+        vulkano::buffer::Buffer::new_unsized(
+            memory_allocator.clone(),
+            vulkano::buffer::BufferCreateInfo {
+                usage: vulkano::buffer::BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: vulkano::memory::allocator::MemoryTypeFilter::PREFER_HOST
+                    | vulkano::memory::allocator::MemoryTypeFilter::HOST_RANDOM_ACCESS,
+                ..Default::default()
+            },
+            u64::from(width) * u64::from(height) * output_image.format().block_size(),
+        )
+        .unwrap()
+    }
+
+    #[must_use]
+    /// Creates the image the frames are accumulated into, for a surface of the given size.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the image cannot be allocated.
+    fn create_accumulation_image(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        size: (u32, u32),
+    ) -> Arc<ImageView> {
+        Self::create_storage_image(
+            memory_allocator,
+            size,
+            vulkano::format::Format::R32G32B32A32_SFLOAT,
+            ImageUsage::TRANSFER_SRC,
+        )
+    }
+
+    #[must_use]
+    /// Creates the images the auxiliary outputs are written to, for a surface of the given size.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the images cannot be allocated.
+    fn create_auxiliary_images(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        size: (u32, u32),
+    ) -> AuxiliaryViews {
+        let create_image = |format| {
+            Self::create_storage_image(memory_allocator, size, format, ImageUsage::TRANSFER_SRC)
+        };
+
+        AuxiliaryViews {
+            depth: create_image(vulkano::format::Format::R32_SFLOAT),
+            normal: create_image(vulkano::format::Format::R32G32B32A32_SFLOAT),
+            albedo: create_image(vulkano::format::Format::R32G32B32A32_SFLOAT),
+        }
+    }
+
+    #[must_use]
+    /// Creates a storage image of the given size, format and additional usage.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the image cannot be allocated.
+    fn create_storage_image(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        (width, height): (u32, u32),
+        format: vulkano::format::Format,
+        usage: ImageUsage,
+    ) -> Arc<ImageView> {
+        let image = vulkano::image::Image::new(
+            memory_allocator.clone(),
+            ImageCreateInfo {
+                format,
+                // A minimized surface has a null size
+                extent: [width.max(1), height.max(1), 1],
+                usage: ImageUsage::STORAGE | usage,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )
+        .unwrap();
+
+        ImageView::new_default(image).unwrap()
+    }
+
+    /// Records a render command buffer for each view of the render surface and each frame slot.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffers cannot be recorded, typically if the pipeline is out of date
     /// or if the render surface is invalid.
-    pub fn _recreate_command_buffers(
-        &mut self,
-        descriptor_set_allocator: &Arc<StandardDescriptorSetAllocator>,
-        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
-        render_surface: &Arc<dyn RenderSurface>,
-    ) {
-        let (width, height) = render_surface.size();
-
-        let work_group_count = [(width + 15) / 16, (height + 15) / 16, 1];
-        let descriptor_set_layout = self._pipeline.layout().set_layouts().first().unwrap();
-
-        self.render_command_buffers = render_surface
-            .views()
-            .iter()
-            .map(|view| {
-                let descriptor_set = PersistentDescriptorSet::new(
-                    descriptor_set_allocator,
-                    descriptor_set_layout.clone(),
-                    [
-                        WriteDescriptorSet::image_view(0, view.clone()),
-                        WriteDescriptorSet::buffer(1, self._buffers.camera_uniform.clone()),
-                        WriteDescriptorSet::buffer(2, self._buffers.triangles_buffer.clone()),
-                        WriteDescriptorSet::buffer(3, self._buffers.materials_buffer.clone()),
-                        WriteDescriptorSet::buffer(4, self._buffers.models_buffer.clone()),
-                        WriteDescriptorSet::buffer(5, self._buffers.bvhs_buffer.clone()),
-                    ],
-                    [],
-                )
-                .unwrap();
+    fn record_frame_slots(&mut self) {
+        let region = self.frame_region();
 
-                let mut builder = AutoCommandBufferBuilder::primary(
-                    command_buffer_allocator,
-                    self.queue.queue_family_index(),
-                    command_buffer::CommandBufferUsage::MultipleSubmit,
-                )
+        // The previous command buffers are released first, so that the allocator can recycle them
+        for slot in &mut *self.frame_slots {
+            slot.command_buffers = Box::new([]);
+        }
+        for slot in 0..self.frame_slots.len() {
+            let command_buffers = (0..self.render_surface.views().len())
+                .map(|view_index| {
+                    self.record_command_buffer(
+                        slot,
+                        Some(view_index),
+                        region,
+                        CommandBufferUsage::MultipleSubmit,
+                    )
+                })
+                .collect();
+            self.frame_slots[slot].command_buffers = command_buffers;
+        }
+    }
+
+    #[must_use]
+    /// Records a command buffer rendering the given region to the output image with the uniforms
+    /// of the given frame slot, and copying the output image to the view at the given index if any.
+    ///
+    /// Views with a float format receive the accumulation image instead.
+    /// The auxiliary outputs are copied to the auxiliary views at the same index, if any,
+    /// and the output image to the frame readback if there is a view and the frames are read back.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffer cannot be recorded.
+    fn record_command_buffer(
+        &self,
+        slot: usize,
+        view_index: Option<usize>,
+        region: Region,
+        usage: CommandBufferUsage,
+    ) -> RenderCommandBuffer {
+        let [group_width, group_height] = self.work_group_size;
+        let work_group_count = [
+            region.width.div_ceil(group_width),
+            region.height.div_ceil(group_height),
+            1,
+        ];
+        let descriptor_set_layout = self.pipeline.layout().set_layouts().first().unwrap();
+
+        let descriptor_set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            descriptor_set_layout.clone(),
+            [
+                WriteDescriptorSet::image_view(0, self.output_image.clone()),
+                WriteDescriptorSet::buffer(1, self.frame_slots[slot].camera_uniform.clone()),
+                WriteDescriptorSet::buffer(2, self.buffers.triangles_buffer.clone()),
+                WriteDescriptorSet::buffer(3, self.buffers.materials_buffer.clone()),
+                WriteDescriptorSet::buffer(4, self.buffers.models_buffer.clone()),
+                WriteDescriptorSet::buffer(5, self.buffers.bvhs_buffer.clone()),
+                WriteDescriptorSet::image_view(6, self.accumulation_image.clone()),
+                WriteDescriptorSet::buffer(7, self.frame_slots[slot].frame_count_uniform.clone()),
+                WriteDescriptorSet::buffer(8, self.buffers.lights_buffer.clone()),
+                WriteDescriptorSet::image_view_sampler(
+                    9,
+                    self.buffers
+                        .environment_map
+                        .as_ref()
+                        .unwrap_or(&self.fallback_environment_map)
+                        .clone(),
+                    self.environment_sampler.clone(),
+                ),
+                WriteDescriptorSet::image_view(10, self.auxiliary_images.depth.clone()),
+                WriteDescriptorSet::image_view(11, self.auxiliary_images.normal.clone()),
+                WriteDescriptorSet::image_view(12, self.auxiliary_images.albedo.clone()),
+                WriteDescriptorSet::image_view_sampler(
+                    13,
+                    self.buffers
+                        .textures
+                        .as_ref()
+                        .unwrap_or(&self.fallback_textures)
+                        .clone(),
+                    self.texture_sampler.clone(),
+                ),
+                WriteDescriptorSet::buffer(14, self.buffers.tlas_buffer.clone()),
+                WriteDescriptorSet::buffer(15, self.sobol_buffer.clone()),
+                WriteDescriptorSet::buffer(16, self.buffers.emissive_triangles_buffer.clone()),
+                WriteDescriptorSet::buffer(17, self.buffers.environment_cdf_buffer.clone()),
+            ],
+            [],
+        )
+        .unwrap();
+
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &self.command_buffer_allocator,
+            self.queue.queue_family_index(),
+            usage,
+        )
+        .unwrap();
+
+        if let Some(timestamp_queries) = &self.timestamp_queries {
+            Self::write_timestamp(&mut builder, &timestamp_queries.pool, 0);
+        }
+        builder
+            .bind_pipeline_compute(self.pipeline.clone())
+            .unwrap()
+            .push_constants(
+                self.pipeline.layout().clone(),
+                0,
+                crate::shader::source::ShaderConstants {
+                    region_x: region.x,
+                    region_y: region.y,
+                    region_width: region.width,
+                    region_height: region.height,
+                    use_environment_map: u32::from(self.buffers.environment_map.is_some()),
+                    write_auxiliary_outputs: u32::from(self.auxiliary_outputs),
+                    ..crate::shader::source::ShaderConstants::from(&self.shader_descriptor)
+                },
+            )
+            .unwrap()
+            .bind_descriptor_sets(
+                vulkano::pipeline::PipelineBindPoint::Compute,
+                self.pipeline.layout().clone(),
+                0,
+                vec![descriptor_set],
+            )
+            .unwrap()
+            .dispatch(work_group_count)
+            .unwrap();
+        if let Some(timestamp_queries) = &self.timestamp_queries {
+            Self::write_timestamp(&mut builder, &timestamp_queries.pool, 1);
+        }
+        if let Some(view) = view_index.map(|index| &self.render_surface.views()[index]) {
+            let source = if view.format() == vulkano::format::Format::R32G32B32A32_SFLOAT {
+                &self.accumulation_image
+            } else {
+                &self.output_image
+            };
+            builder
+                .copy_image(CopyImageInfo::images(
+                    source.image().clone(),
+                    view.image().clone(),
+                ))
                 .unwrap();
+        }
+        if let (Some(_), Some(frame_readback)) = (view_index, &self.frame_readback) {
+            builder
+                .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
+                    self.output_image.image().clone(),
+                    frame_readback.clone(),
+                ))
+                .unwrap();
+        }
+        if let Some(auxiliary_views) =
+            view_index.and_then(|index| self.render_surface.auxiliary_views().get(index))
+        {
+            for (source, destination) in [
+                (&self.auxiliary_images.depth, &auxiliary_views.depth),
+                (&self.auxiliary_images.normal, &auxiliary_views.normal),
+                (&self.auxiliary_images.albedo, &auxiliary_views.albedo),
+            ] {
+                builder
+                    .copy_image(CopyImageInfo::images(
+                        source.image().clone(),
+                        destination.image().clone(),
+                    ))
+                    .unwrap();
+            }
+        }
+        builder.build().unwrap()
+    }
 
+    #[allow(unsafe_code)]
+    /// Records the write of the timestamp query at the given index, 0 before the dispatch and 1 after it.
+    ///
+    /// The queries are reset before the first one is written.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the commands cannot be recorded.
+    fn write_timestamp(
+        builder: &mut AutoCommandBufferBuilder<
+            vulkano::command_buffer::PrimaryAutoCommandBuffer<Arc<StandardCommandBufferAllocator>>,
+            Arc<StandardCommandBufferAllocator>,
+        >,
+        query_pool: &Arc<QueryPool>,
+        query: u32,
+    ) {
+        // SAFETY: the queries are only used by the render command buffers, which never execute
+        // concurrently as each frame waits for the previous one, and they are reset in the same
+        // command buffer before being written, so they are unavailable when written
+        unsafe {
+            if query == 0 {
+                builder.reset_query_pool(query_pool.clone(), 0..2).unwrap();
                 builder
-                    .bind_pipeline_compute(self._pipeline.clone())
-                    .unwrap()
-                    .push_constants(
-                        self._pipeline.layout().clone(),
-                        0,
-                        crate::shader::source::ShaderConstants::from(self._shader_descriptor),
-                    )
-                    .unwrap()
-                    .bind_descriptor_sets(
-                        vulkano::pipeline::PipelineBindPoint::Compute,
-                        self._pipeline.layout().clone(),
-                        0,
-                        vec![descriptor_set],
-                    )
-                    .unwrap()
-                    .dispatch(work_group_count)
+                    .write_timestamp(query_pool.clone(), 0, PipelineStage::TopOfPipe)
                     .unwrap();
-                builder.build().unwrap()
-            })
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+            } else {
+                builder
+                    .write_timestamp(query_pool.clone(), query, PipelineStage::BottomOfPipe)
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Recreates the command buffers, typically when the render surface is resized.
+    ///
+    /// The previous command buffers are released first, so that the allocator
+    /// can recycle them once they are no longer in use by the GPU.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffers cannot be recreated, typically if the pipeline is out of date
+    /// or if the render surface is invalid.
+    pub fn recreate_command_buffers(&mut self) {
+        let size = Self::views_size(&*self.render_surface);
+        let [width, height, _] = self.output_image.image().extent();
+        if size != (width, height) {
+            self.output_image = Self::create_output_image(&self.memory_allocator, size);
+            if self.frame_readback.is_some() {
+                self.frame_readback = Some(Self::create_frame_readback(
+                    &self.memory_allocator,
+                    &self.output_image,
+                ));
+            }
+            self.accumulation_image = Self::create_accumulation_image(&self.memory_allocator, size);
+            if self.auxiliary_outputs {
+                self.auxiliary_images = Self::create_auxiliary_images(&self.memory_allocator, size);
+            }
+            self.reset_accumulation();
+        }
+
+        self.record_frame_slots();
 
         tracing::trace!("Command buffers recreated");
     }
 
+    #[must_use]
+    /// Returns the region rendered by every frame: the render region clamped to the surface if any,
+    /// or the whole surface.
+    fn frame_region(&self) -> Region {
+        let (width, height) = Self::views_size(&*self.render_surface);
+        self.render_region.map_or_else(
+            || Region::full(width, height),
+            |region| region.clamped(width, height),
+        )
+    }
+
+    /// Restricts every frame to the given region of the render surface, or renders whole frames again.
+    ///
+    /// The rest of the output keeps the pixels of the previous frames, so that tiles of a large image
+    /// can be rendered by several processes and composited afterwards.
+    /// The region is clamped to the surface, and the accumulated frames are discarded.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffers cannot be recreated.
+    pub fn set_render_region(&mut self, region: Option<Region>) -> Result<(), RenderError> {
+        if region == self.render_region {
+            return Ok(());
+        }
+
+        // The command buffers of the frames in flight must not be released
+        self.wait_for_frames_in_flight()?;
+        self.render_region = region;
+        self.recreate_command_buffers();
+        self.reset_accumulation();
+
+        Ok(())
+    }
+
+    /// Switches the shader between path tracing and its debug views.
+    ///
+    /// The accumulated frames are discarded.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffers cannot be recreated.
+    pub fn set_debug_mode(&mut self, mode: crate::shader::DebugMode) -> Result<(), RenderError> {
+        if mode == self.shader_descriptor.debug_mode {
+            return Ok(());
+        }
+
+        // The command buffers of the frames in flight must not be released
+        self.wait_for_frames_in_flight()?;
+        self.shader_descriptor.debug_mode = mode;
+        self.recreate_command_buffers();
+        self.reset_accumulation();
+
+        Ok(())
+    }
+
+    /// Makes the renderer use the given buffers and shader parameters,
+    /// typically when switching to another scene.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffers cannot be recreated.
+    pub fn set_buffers(
+        &mut self,
+        buffers: &Buffers,
+        shader_descriptor: crate::shader::ShaderDescriptor,
+    ) -> Result<(), RenderError> {
+        // The command buffers of the frames in flight must not be released
+        self.wait_for_frames_in_flight()?;
+
+        if buffers.texture_filter != self.buffers.texture_filter {
+            self.texture_sampler = Self::create_texture_sampler(
+                self.queue.device(),
+                buffers.texture_filter,
+                self.sampler_anisotropy,
+            );
+        }
+        self.buffers = buffers.clone();
+        self.shader_descriptor = shader_descriptor;
+        self.recreate_command_buffers();
+        self.reset_accumulation();
+
+        Ok(())
+    }
+
+    #[inline]
+    /// Sets the exposure of the next frames, in stops.
+    ///
+    /// As the accumulated colors are linear, they are kept.
+    pub const fn set_exposure(&mut self, exposure: f32) {
+        self.shader_descriptor.exposure = exposure;
+    }
+
+    #[inline]
+    /// Sets the white point of the next frames.
+    ///
+    /// As the accumulated colors are linear, they are kept.
+    pub const fn set_white_point(&mut self, white_point: [f32; 3]) {
+        self.shader_descriptor.white_point = white_point;
+    }
+
+    #[inline]
+    /// Sets the camera the next frames are rendered from.
+    ///
+    /// The camera is written to the uniform of the next frame when it is submitted,
+    /// so that the frames in flight keep reading theirs.
+    pub const fn set_camera(&mut self, camera: crate::shader::source::Camera) {
+        self.camera = camera;
+    }
+
+    #[inline]
+    /// Discards the accumulated frames, so that the next frame starts a new average.
+    ///
+    /// This must be called whenever the rendered image changes, typically when the camera moves.
+    pub const fn reset_accumulation(&mut self) {
+        self.frame_count = 0;
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the number of frames accumulated since the last reset.
+    pub const fn accumulated_frames(&self) -> u32 {
+        self.frame_count
+    }
+
+    /// Notifies the render surface that it has been resized to the given physical size.
+    ///
+    /// The surface recreates its views before the next frame, after which the output, accumulation
+    /// and auxiliary images are reallocated and the command buffers are recorded again
+    /// with work-group counts matching the new size.
+    ///
+    /// Nothing happens if the size of the views does not change, typically when only
+    /// the scale factor of the monitor changed, so that the accumulated frames are kept.
+    pub fn resize(&mut self, new_size: (u32, u32)) {
+        if new_size == Self::views_size(&*self.render_surface) {
+            tracing::trace!("Surface resized to its current extent, nothing to recreate");
+            return;
+        }
+
+        self.render_surface.resized();
+    }
+
+    #[inline]
+    /// Switches the render surface to the given window mode, if it is a window.
+    pub fn set_window_mode(&mut self, mode: window::Mode) {
+        self.render_surface.set_window_mode(mode);
+    }
+
+    #[inline]
+    /// Sets the present mode of the render surface, if it is a window.
+    pub fn set_present_mode(&mut self, present_mode: window::PresentMode) {
+        self.render_surface.set_present_mode(present_mode);
+    }
+
+    #[inline]
+    /// Grabs or releases the cursor, if the render surface is a window.
+    pub fn set_cursor_grab(&mut self, grab: bool) {
+        self.render_surface.set_cursor_grab(grab);
+    }
+
+    #[inline]
+    /// Notifies the render surface that it gained or lost the focus.
+    pub fn set_focused(&mut self, focused: bool) {
+        self.render_surface.set_focused(focused);
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the current size of the render surface.
+    pub fn surface_size(&self) -> (u32, u32) {
+        self.render_surface.size()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the render surface is closed, see [`RenderSurface::is_closed`].
+    pub fn is_surface_closed(&self) -> bool {
+        self.render_surface.is_closed()
+    }
+
+    /// Waits for the GPU to finish rendering every submitted frame.
+    ///
+    /// This must be called before writing to any buffer read by the shader,
+    /// such as the models buffer, as the frames in flight may still be reading it.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames failed to render.
+    pub fn wait_for_frames_in_flight(&mut self) -> Result<(), RenderError> {
+        // Oldest first, so that the timestamps read last are the ones of the last frame
+        for offset in 0..self.frame_slots.len() {
+            let slot = (self.current_slot + offset) % self.frame_slots.len();
+            self.wait_for_slot(slot)?;
+        }
+        Ok(())
+    }
+
+    /// Waits for the GPU to finish rendering the last frame submitted with the given frame slot, if any.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the frame failed to render.
+    fn wait_for_slot(&mut self, slot: usize) -> Result<(), RenderError> {
+        if let Some(frame_future) = self.frame_slots[slot].in_flight.take() {
+            frame_future
+                .wait(None)
+                .map_err(vulkano::Validated::unwrap)?;
+            self.read_gpu_time();
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    /// Reads the timestamps of the last completed frame, if GPU profiling is enabled.
+    fn read_gpu_time(&mut self) {
+        let Some(timestamp_queries) = &self.timestamp_queries else {
+            return;
+        };
+
+        let mut timestamps = [0_u64; 2];
+        match timestamp_queries
+            .pool
+            .get_results(0..2, &mut timestamps, QueryResultFlags::empty())
+        {
+            Ok(true) => {
+                let ticks = timestamps[1].wrapping_sub(timestamps[0]) & timestamp_queries.mask;
+                self.last_gpu_time = Some(std::time::Duration::from_secs_f64(
+                    ticks as f64 * timestamp_queries.period * 1e-9,
+                ));
+            }
+            // The frame did not write its timestamps, typically because it was recorded before profiling
+            Ok(false) => {}
+            Err(error) => tracing::warn!("Failed to read the GPU timestamps: {error}"),
+        }
+    }
+
+    /// Sets whether the duration of the dispatch of every frame is measured on the GPU,
+    /// to be read with [`Renderer::last_gpu_time`].
+    ///
+    /// Profiling is only enabled if the render queue supports timestamps, a warning being logged otherwise.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the query pool cannot be created or if the command buffers cannot be recreated.
+    pub fn set_gpu_profiling(&mut self, enabled: bool) -> Result<(), RenderError> {
+        if enabled == self.timestamp_queries.is_some() {
+            return Ok(());
+        }
+
+        // The command buffers of the frames in flight may write the previous queries
+        self.wait_for_frames_in_flight()?;
+        self.last_gpu_time = None;
+        self.timestamp_queries = if enabled {
+            TimestampQueries::new(&self.queue)
+        } else {
+            None
+        };
+        self.recreate_command_buffers();
+
+        Ok(())
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the time the GPU spent on the dispatch of the last completed frame,
+    /// or `None` if GPU profiling is disabled, see [`Renderer::set_gpu_profiling`].
+    pub const fn last_gpu_time(&self) -> Option<std::time::Duration> {
+        self.last_gpu_time
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the size of the work groups the shader is specialized with.
+    pub const fn work_group_size(&self) -> [u32; 2] {
+        self.work_group_size
+    }
+
+    /// Sets whether every presented frame is copied to host memory, to be read with [`Renderer::read_frame`].
+    ///
+    /// As the copy costs a transfer per frame, it is disabled by default.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames in flight failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffers cannot be recreated.
+    pub fn set_frame_readback(&mut self, enabled: bool) -> Result<(), RenderError> {
+        if enabled == self.frame_readback.is_some() {
+            return Ok(());
+        }
+
+        // The command buffers of the frames in flight may reference the previous readback
+        self.wait_for_frames_in_flight()?;
+        self.frame_readback = enabled
+            .then(|| Self::create_frame_readback(&self.memory_allocator, &self.output_image));
+        self.recreate_command_buffers();
+
+        Ok(())
+    }
+
+    /// Waits for the last presented frame to finish rendering, then returns its pixels.
+    ///
+    /// Returns `None` if the frames are not read back, see [`Renderer::set_frame_readback`].
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the last frame failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the frame readback is still in use by the GPU.
+    pub fn read_frame(&mut self) -> Result<Option<FrameView<'_>>, RenderError> {
+        self.wait_for_frames_in_flight()?;
+
+        let Some(frame_readback) = &self.frame_readback else {
+            return Ok(None);
+        };
+        let [width, height, _] = self.output_image.image().extent();
+
+        Ok(Some(FrameView {
+            width,
+            height,
+            pixels: frame_readback.read().unwrap(),
+        }))
+    }
+
+    /// Waits for the GPU to finish all the work of the renderer,
+    /// then releases the command buffers and the descriptor sets they reference.
+    ///
+    /// The renderer must not render afterwards, as it has no command buffers left.
+    /// Errors are only logged, as the renderer is being torn down anyway.
+    pub fn shutdown(&mut self) {
+        if let Err(error) = self.wait_for_frames_in_flight() {
+            tracing::warn!("Last frame failed while shutting down: {error}");
+        }
+        if let Err(error) = self.queue.with(|mut queue| queue.wait_idle()) {
+            tracing::error!("Failed to wait for the render queue to be idle: {error}");
+        }
+
+        for slot in &mut *self.frame_slots {
+            slot.command_buffers = Box::new([]);
+        }
+    }
+
     /// Renders the scene.
     ///
     /// ## Note
     ///
     /// Use the argument `on_waiting_for_render` to update anything unrelated to rendering while waiting for the render to complete.
     ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the scene cannot be rendered, typically due to an error
+    /// during rendering on the GPU or because the render surface is out of date.
+    /// In the latter case, the frame is skipped and the surface is recreated before the next one,
+    /// see [`RenderError::is_recoverable`].
+    ///
     /// ## Panics
     ///
-    /// This function panics if the renderer cannot render the scene, typically due to an error
-    /// during rendering on the GPU.
-    pub fn render(&mut self, on_waiting_for_render: &mut dyn FnMut(u32)) {
-        let (view_index, future) = self.render_surface.acquire().unwrap();
+    /// This function panics if the command buffers cannot be recorded again after the surface is recreated.
+    pub fn render(
+        &mut self,
+        on_waiting_for_render: &mut dyn FnMut(u32),
+    ) -> Result<(), RenderError> {
+        let (view_index, future) = self.render_surface.acquire()?;
+        if self.render_surface.take_recreated_views() {
+            self.recreate_command_buffers();
+        }
+        let command_buffer =
+            self.frame_slots[self.current_slot].command_buffers[view_index as usize].clone();
 
-        let render_future = future
-            .then_execute(
-                self.queue.clone(),
-                self.render_command_buffers[view_index as usize].clone(),
-            )
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
+        self.submit(view_index, future, command_buffer, on_waiting_for_render)
+    }
+
+    /// Renders the given region of the scene, keeping the rest of the previous frame.
+    ///
+    /// The region is clamped to the render surface, and nothing is rendered if it is empty.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the region cannot be rendered, typically due to an error
+    /// during rendering on the GPU or because the render surface is out of date.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffer cannot be recorded.
+    pub fn render_region(
+        &mut self,
+        region: Region,
+        on_waiting_for_render: &mut dyn FnMut(u32),
+    ) -> Result<(), RenderError> {
+        let (width, height) = Self::views_size(&*self.render_surface);
+        let region = region.clamped(width, height);
+        if region.is_empty() {
+            return Ok(());
+        }
+
+        let (view_index, future) = self.render_surface.acquire()?;
+        if self.render_surface.take_recreated_views() {
+            self.recreate_command_buffers();
+        }
+        let command_buffer = self.record_command_buffer(
+            self.current_slot,
+            Some(view_index as usize),
+            region,
+            CommandBufferUsage::OneTimeSubmit,
+        );
+
+        self.submit(view_index, future, command_buffer, on_waiting_for_render)
+    }
+
+    /// Renders and accumulates the given number of frames without presenting them,
+    /// typically before rendering a final frame to an image.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if one of the frames failed to render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the command buffer cannot be recorded.
+    pub fn accumulate(&mut self, frames: u32) -> Result<(), RenderError> {
+        if frames == 0 {
+            return Ok(());
+        }
+
+        let region = self.frame_region();
+        let command_buffers = (0..self.frame_slots.len())
+            .map(|slot| {
+                self.record_command_buffer(slot, None, region, CommandBufferUsage::MultipleSubmit)
+            })
+            .collect::<Vec<_>>();
+
+        for _ in 0..frames {
+            let now = vulkano::sync::now(self.queue.device().clone()).boxed();
+            self.execute(now, command_buffers[self.current_slot].clone())?;
+        }
+        tracing::trace!("{frames} frames accumulated");
+
+        Ok(())
+    }
+
+    /// Executes the given command buffer once the view is acquired, and presents it.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the command buffer fails to execute or if the view cannot be presented.
+    fn submit(
+        &mut self,
+        view_index: u32,
+        future: Box<dyn GpuFuture>,
+        command_buffer: RenderCommandBuffer,
+        on_waiting_for_render: &mut dyn FnMut(u32),
+    ) -> Result<(), RenderError> {
+        let render_future = self.execute(future, command_buffer)?;
 
         on_waiting_for_render(view_index);
 
         self.render_surface
             .present(render_future.boxed(), &self.queue)
-            .unwrap();
+    }
+
+    /// Executes the given command buffer of the current frame slot after the given future,
+    /// as the next accumulated frame.
+    ///
+    /// Returns the future of the frame, which is also kept in its slot until the slot is used again.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if a previous frame or the command buffer fails to execute.
+    fn execute(
+        &mut self,
+        future: Box<dyn GpuFuture>,
+        command_buffer: RenderCommandBuffer,
+    ) -> Result<FrameFuture, RenderError> {
+        let slot_count = self.frame_slots.len();
+
+        // Only the uniforms of the slot need the frame that last used them to be finished
+        self.wait_for_slot(self.current_slot)?;
+        let slot = &mut self.frame_slots[self.current_slot];
+        slot.camera_uniform.write()?.camera = self.camera;
+        let mut accumulation = slot.frame_count_uniform.write()?;
+        accumulation.frame_count = self.frame_count;
+        accumulation.color_scale = self.shader_descriptor.color_scale();
+        drop(accumulation);
+        self.frame_count = self.frame_count.saturating_add(1);
+
+        // The output and accumulation images are shared by all the frames, so a frame still rendering
+        // in another slot must finish on the GPU before this one starts. With a single slot,
+        // the previous frame is the one of this slot and has already been waited for
+        let previous_frame = self.frame_slots[(self.current_slot + slot_count - 1) % slot_count]
+            .in_flight
+            .clone()
+            .filter(|frame| !frame.is_signaled().unwrap_or(false));
+        let future = match previous_frame {
+            Some(previous_frame) => future.join(previous_frame).boxed(),
+            None => future,
+        };
+        // Fence signal futures can only be shared as GPU futures through an `Arc`
+        #[allow(clippy::arc_with_non_send_sync)]
+        let render_future = Arc::new(
+            future
+                .then_execute(self.queue.clone(), command_buffer)?
+                .then_signal_fence_and_flush()
+                .map_err(vulkano::Validated::unwrap)?,
+        );
+        self.frame_slots[self.current_slot].in_flight = Some(render_future.clone());
+        self.current_slot = (self.current_slot + 1) % slot_count;
+
+        Ok(render_future)
     }
 }
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use vulkano::{
+    device::{DeviceOwned, Queue},
+    image::{view::ImageView, ImageUsage},
+    sync::GpuFuture,
+};
+
+/// The callback the future of every rendered frame is handed to,
+/// along with the queue the frame was rendered on.
+type OnRendered = Box<dyn FnMut(Box<dyn GpuFuture>, &Arc<Queue>) -> Result<(), super::RenderError>>;
+
+/// Represents an image owned by the caller, typically a texture of a larger render graph,
+/// such as the one of an existing Vulkan or egui application.
+///
+/// Instead of being presented or saved, every frame is handed to the caller as a future,
+/// to which it can chain its own work, a semaphore signal or a fence.
+/// The image must not be read before the future completes.
+///
+/// The surface is given to [`crate::RayTracingApp::with_surface`],
+/// whose context provides the device the image must be created on.
+pub struct ExternalImage {
+    /// The view of the image, as the single view of the surface.
+    views: [Arc<ImageView>; 1],
+    /// The callback the future of every rendered frame is handed to.
+    on_rendered: OnRendered,
+}
+
+impl ExternalImage {
+    #[must_use]
+    /// Creates a surface rendering into the given view, handing the future of every frame to `on_rendered`.
+    ///
+    /// The future has already been submitted, so it only has to be chained,
+    /// for instance with `then_signal_semaphore_and_flush` before the caller's own commands.
+    /// The errors returned by `on_rendered` are returned by the render.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the image does not have the `TRANSFER_DST` usage,
+    /// or if its format is neither `R8G8B8A8_UNORM` nor `R32G32B32A32_SFLOAT`.
+    pub fn new(
+        view: Arc<ImageView>,
+        on_rendered: impl FnMut(Box<dyn GpuFuture>, &Arc<Queue>) -> Result<(), super::RenderError>
+            + 'static,
+    ) -> Self {
+        assert!(
+            view.image().usage().intersects(ImageUsage::TRANSFER_DST),
+            "the external image must have the TRANSFER_DST usage"
+        );
+        assert!(
+            matches!(
+                view.format(),
+                vulkano::format::Format::R8G8B8A8_UNORM
+                    | vulkano::format::Format::R32G32B32A32_SFLOAT
+            ),
+            "the external image must have the R8G8B8A8_UNORM or R32G32B32A32_SFLOAT format, not {:?}",
+            view.format()
+        );
+
+        Self {
+            views: [view],
+            on_rendered: Box::new(on_rendered),
+        }
+    }
+
+    #[must_use]
+    /// Creates a surface rendering into the given view, waiting for every frame on a fence.
+    ///
+    /// It suits callers that read the image on the CPU side of their loop, between two frames.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics under the same conditions as [`ExternalImage::new`].
+    pub fn blocking(view: Arc<ImageView>) -> Self {
+        Self::new(view, |render_future, _queue| {
+            render_future
+                .then_signal_fence_and_flush()
+                .map_err(vulkano::Validated::unwrap)?
+                .wait(None)
+                .map_err(|error| vulkano::Validated::unwrap(error).into())
+        })
+    }
+}
+
+impl super::RenderSurface for ExternalImage {
+    #[must_use]
+    #[inline]
+    fn size(&self) -> (u32, u32) {
+        let [width, height, _] = self.views[0].image().extent();
+        (width, height)
+    }
+
+    #[must_use]
+    #[inline]
+    fn views(&self) -> &[Arc<ImageView>] {
+        &self.views
+    }
+
+    fn acquire(&mut self) -> Result<(u32, Box<dyn GpuFuture>), super::RenderError> {
+        Ok((
+            0,
+            Box::new(vulkano::sync::now(self.views[0].device().clone())),
+        ))
+    }
+
+    fn present(
+        &mut self,
+        render_future: Box<dyn GpuFuture>,
+        queue: &Arc<Queue>,
+    ) -> Result<(), super::RenderError> {
+        (self.on_rendered)(render_future, queue)
+    }
+}
@@ -1,5 +1,11 @@
 use std::io::BufWriter;
-use std::{path::PathBuf, sync::Arc};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use png;
 
@@ -12,12 +18,114 @@ use vulkano::image::{ImageCreateInfo, ImageUsage};
 use vulkano::memory::allocator::{AllocationCreateInfo, StandardMemoryAllocator};
 use vulkano::sync::{self, GpuFuture};
 
+/// The slots shared by all the offscreen renders of the process.
+static RENDER_SLOTS: RenderSlots = RenderSlots::new();
+
+/// Sets the maximum number of offscreen renders running concurrently in the process.
+///
+/// The limit is shared by all the images, whatever the application rendering them.
+/// An image takes a slot before allocating anything and holds it until it is dropped,
+/// so images created beyond the limit wait for one to be dropped, which bounds the GPU memory
+/// used when rendering images from several threads.
+/// `None`, the default, means that images never wait.
+///
+/// Lowering the limit does not affect the images already created.
+pub fn set_max_concurrent_renders(limit: Option<NonZeroUsize>) {
+    RENDER_SLOTS.set_limit(limit);
+}
+
+/// Bounds the number of offscreen renders running concurrently.
+struct RenderSlots {
+    /// The number of renders currently running.
+    running: Mutex<usize>,
+    /// The maximum number of renders running concurrently, `0` if there is none.
+    limit: AtomicUsize,
+    /// Notified every time a render finishes or the limit changes.
+    released: Condvar,
+}
+
+impl RenderSlots {
+    #[must_use]
+    /// Creates a new set of slots, with no render running and no limit.
+    const fn new() -> Self {
+        Self {
+            running: Mutex::new(0),
+            limit: AtomicUsize::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Replaces the maximum number of renders running concurrently, waking up the waiting renders.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if another render panicked while holding the lock.
+    fn set_limit(&self, limit: Option<NonZeroUsize>) {
+        // Changed under the lock, so that no waiting render misses the notification
+        let running = self.running.lock().unwrap();
+        self.limit
+            .store(limit.map_or(0, NonZeroUsize::get), Ordering::Relaxed);
+        drop(running);
+        self.released.notify_all();
+    }
+
+    #[must_use]
+    /// Blocks until less renders than the limit are running, then takes a slot.
+    ///
+    /// The slot is released when the returned guard is dropped.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if another render panicked while holding the lock.
+    fn acquire(&'static self) -> RenderSlot {
+        let is_full = |running: usize| {
+            let limit = self.limit.load(Ordering::Relaxed);
+            limit != 0 && running >= limit
+        };
+
+        let mut running = self.running.lock().unwrap();
+        if is_full(*running) {
+            tracing::debug!("Offscreen render queued, {} renders running", *running);
+        }
+        while is_full(*running) {
+            running = self.released.wait(running).unwrap();
+        }
+        *running += 1;
+        drop(running);
+
+        RenderSlot { slots: self }
+    }
+}
+
+/// A slot taken by a running offscreen render.
+struct RenderSlot {
+    /// The slots the slot was taken from.
+    slots: &'static RenderSlots,
+}
+
+impl Drop for RenderSlot {
+    fn drop(&mut self) {
+        // Release the slot even if another render panicked
+        let mut running = self
+            .slots
+            .running
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        *running -= 1;
+        drop(running);
+        self.slots.released.notify_one();
+    }
+}
+
 /// Represents an image that can be rendered to.
 ///
 /// The image is saved to the disk when `present` is called.
+/// It holds a render slot from its creation until it is dropped, see [`set_max_concurrent_renders`].
 pub struct Image {
     /// Used to save the image to the disk.
     path: PathBuf,
+    /// The format the image is saved in.
+    format: ImageFormat,
     /// The width of the image.
     width: u32,
     /// The height of the image.
@@ -32,12 +140,35 @@ pub struct Image {
     command_buffer: Arc<PrimaryAutoCommandBuffer<Arc<StandardCommandBufferAllocator>>>,
     /// Used to benchmark the rendering time.
     start_time: std::time::Instant,
+    /// The auxiliary outputs saved next to the image, if requested.
+    auxiliary: Option<AuxiliaryOutputs>,
+    #[cfg(feature = "denoise")]
+    /// Whether the image is denoised before being saved.
+    denoise: bool,
+    /// The slot held from the creation of the image until it is dropped.
+    ///
+    /// Declared last so that it is released after the buffers of the image.
+    _render_slot: RenderSlot,
+}
+
+/// Represents the auxiliary outputs of the renderer saved next to an image.
+struct AuxiliaryOutputs {
+    /// The views the renderer copies its auxiliary outputs to.
+    views: [super::AuxiliaryViews; 1],
+    /// CPU accessible buffer the depth is copied to.
+    depth_buffer: Subbuffer<[u8]>,
+    /// CPU accessible buffer the normals are copied to.
+    normal_buffer: Subbuffer<[u8]>,
+    /// CPU accessible buffer the albedo is copied to.
+    albedo_buffer: Subbuffer<[u8]>,
 }
 
 impl Image {
     #[must_use]
     /// Creates a new image from the given image descriptor.
     ///
+    /// This function blocks until a render slot is free, see [`set_max_concurrent_renders`].
+    ///
     /// ## Panics
     ///
     /// This function will panic if the inner image / buffer creation fails.
@@ -49,42 +180,58 @@ impl Image {
     ) -> Self {
         let ImageDescriptor {
             path,
+            format,
             width,
             height,
+            save_auxiliary_outputs,
+            denoise,
+            ..
         } = image_descriptor;
 
-        let image = vulkano::image::Image::new(
-            memory_allocator.clone(),
-            ImageCreateInfo {
-                format: vulkano::format::Format::R8G8B8A8_UNORM,
-                extent: [*width, *height, 1],
-                usage: ImageUsage::TRANSFER_SRC | ImageUsage::STORAGE,
-                ..Default::default()
-            },
-            AllocationCreateInfo::default(),
-        )
-        .unwrap();
+        // Taken before allocating anything, so that waiting images use no GPU memory
+        let render_slot = RENDER_SLOTS.acquire();
 
-        let image_view = ImageView::new(image.clone(), ImageViewCreateInfo::from_image(&image))
-            .expect("Could not create image view");
+        #[cfg(not(feature = "denoise"))]
+        if *denoise {
+            tracing::warn!(
+                "Denoising requires the `denoise` feature, the image will be saved as is"
+            );
+        }
 
-        let inner_buffer = vulkano::buffer::Buffer::new_unsized(
-            memory_allocator,
-            vulkano::buffer::BufferCreateInfo {
-                usage: vulkano::buffer::BufferUsage::TRANSFER_DST,
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: vulkano::memory::allocator::MemoryTypeFilter::PREFER_HOST
-                    | vulkano::memory::allocator::MemoryTypeFilter::HOST_RANDOM_ACCESS,
-                ..Default::default()
-            },
-            u64::from(*width)
-                * u64::from(*height)
-                * vulkano::format::Format::R8G8B8A8_UNORM.block_size()
-                * size_of::<u8>() as u64,
-        )
-        .unwrap();
+        let (image_view, inner_buffer) =
+            Self::create_readback(&memory_allocator, format.vulkan_format(), (*width, *height));
+
+        let auxiliary = save_auxiliary_outputs.then(|| {
+            #[cfg(not(feature = "exr"))]
+            tracing::warn!("Saving the depth requires the `exr` feature, it will be skipped");
+
+            let (depth, depth_buffer) = Self::create_readback(
+                &memory_allocator,
+                vulkano::format::Format::R32_SFLOAT,
+                (*width, *height),
+            );
+            let (normal, normal_buffer) = Self::create_readback(
+                &memory_allocator,
+                vulkano::format::Format::R32G32B32A32_SFLOAT,
+                (*width, *height),
+            );
+            let (albedo, albedo_buffer) = Self::create_readback(
+                &memory_allocator,
+                vulkano::format::Format::R32G32B32A32_SFLOAT,
+                (*width, *height),
+            );
+
+            AuxiliaryOutputs {
+                views: [super::AuxiliaryViews {
+                    depth,
+                    normal,
+                    albedo,
+                }],
+                depth_buffer,
+                normal_buffer,
+                albedo_buffer,
+            }
+        });
 
         let command_buffer = {
             let mut builder = vulkano::command_buffer::AutoCommandBufferBuilder::primary(
@@ -96,16 +243,33 @@ impl Image {
 
             builder
                 .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
-                    image,
+                    image_view.image().clone(),
                     inner_buffer.clone(),
                 ))
                 .unwrap();
 
+            if let Some(auxiliary) = &auxiliary {
+                let [views] = &auxiliary.views;
+                for (view, buffer) in [
+                    (&views.depth, &auxiliary.depth_buffer),
+                    (&views.normal, &auxiliary.normal_buffer),
+                    (&views.albedo, &auxiliary.albedo_buffer),
+                ] {
+                    builder
+                        .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
+                            view.image().clone(),
+                            buffer.clone(),
+                        ))
+                        .unwrap();
+                }
+            }
+
             builder.build().unwrap()
         };
 
         Self {
             path: path.clone(),
+            format: *format,
             width: *width,
             height: *height,
             image_view: [image_view],
@@ -113,10 +277,299 @@ impl Image {
             compute_queue,
             command_buffer,
             start_time: std::time::Instant::now(),
+            auxiliary,
+            #[cfg(feature = "denoise")]
+            denoise: *denoise,
+            _render_slot: render_slot,
+        }
+    }
+
+    #[must_use]
+    /// Creates an image the renderer can copy to, and the CPU accessible buffer it is read back from.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the image or the buffer cannot be allocated.
+    fn create_readback(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        format: vulkano::format::Format,
+        (width, height): (u32, u32),
+    ) -> (Arc<ImageView>, Subbuffer<[u8]>) {
+        let image = vulkano::image::Image::new(
+            memory_allocator.clone(),
+            ImageCreateInfo {
+                format,
+                extent: [width, height, 1],
+                usage: ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )
+        .unwrap();
+
+        let image_view = ImageView::new(image.clone(), ImageViewCreateInfo::from_image(&image))
+            .expect("Could not create image view");
+
+        let buffer = vulkano::buffer::Buffer::new_unsized(
+            memory_allocator.clone(),
+            vulkano::buffer::BufferCreateInfo {
+                usage: vulkano::buffer::BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: vulkano::memory::allocator::MemoryTypeFilter::PREFER_HOST
+                    | vulkano::memory::allocator::MemoryTypeFilter::HOST_RANDOM_ACCESS,
+                ..Default::default()
+            },
+            u64::from(width) * u64::from(height) * format.block_size(),
+        )
+        .unwrap();
+
+        (image_view, buffer)
+    }
+}
+
+impl Image {
+    /// Saves the given RGBA8 pixels to a PNG file at the given path.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`super::RenderError::Save`] if the file cannot be written, the cause being logged.
+    fn save_png(&self, path: &Path, data: &[u8]) -> Result<(), super::RenderError> {
+        let save = || -> Result<(), png::EncodingError> {
+            let file = std::fs::File::create(path)?;
+            let file_writer = &mut BufWriter::new(file);
+
+            let mut encoder = png::Encoder::new(file_writer, self.width, self.height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+
+            let mut png_writer = encoder.write_header()?;
+            png_writer.write_image_data(data)?;
+            png_writer.finish()
+        };
+
+        save().map_err(|error| {
+            tracing::error!("Failed to save {}: {error}", path.display());
+            super::RenderError::Save
+        })
+    }
+
+    #[cfg(feature = "exr")]
+    /// Saves the given RGBA32F pixels to an EXR file, without their alpha channel.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`super::RenderError::Save`] if the file cannot be written, the cause being logged.
+    fn save_exr(&self, data: &[u8]) -> Result<(), super::RenderError> {
+        let pixels = data
+            .chunks_exact(16)
+            .map(|texel| {
+                let [r, g, b, _] = texel_channels(texel);
+                (r, g, b)
+            })
+            .collect::<Vec<_>>();
+
+        let width = self.width as usize;
+        exr::prelude::write_rgb_file(&self.path, width, self.height as usize, |x, y| {
+            pixels[y * width + x]
+        })
+        .map_err(|error| {
+            tracing::error!("Failed to save {}: {error}", self.path.display());
+            super::RenderError::Save
+        })
+    }
+
+    #[cfg(feature = "exr")]
+    /// Saves the given R32F depths to an EXR file at the given path, as its `Z` channel.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`super::RenderError::Save`] if the file cannot be written, the cause being logged.
+    fn save_depth(&self, path: &Path, data: &[u8]) -> Result<(), super::RenderError> {
+        let depths = data
+            .chunks_exact(4)
+            .map(|texel| f32::from_ne_bytes([texel[0], texel[1], texel[2], texel[3]]))
+            .collect::<Vec<_>>();
+
+        let width = self.width as usize;
+        let channels = exr::prelude::SpecificChannels::build()
+            .with_channel("Z")
+            .with_pixel_fn(|position: exr::prelude::Vec2<usize>| {
+                (depths[position.y() * width + position.x()],)
+            });
+        exr::prelude::Image::from_channels((width, self.height as usize), channels)
+            .write()
+            .to_file(path)
+            .map_err(|error| {
+                tracing::error!("Failed to save {}: {error}", path.display());
+                super::RenderError::Save
+            })
+    }
+
+    /// Saves the auxiliary outputs next to the image, named after it:
+    /// `render.depth.exr` with the `exr` feature, `render.normal.png` and `render.albedo.png` for `render.png`.
+    ///
+    /// Normals are remapped from `[-1, 1]` to `[0, 1]`, and the albedo is gamma-corrected like the image.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`super::RenderError::HostAccess`] if the buffers cannot be read,
+    /// or [`super::RenderError::Save`] if the files cannot be written.
+    fn save_auxiliary_outputs(
+        &self,
+        auxiliary: &AuxiliaryOutputs,
+    ) -> Result<(), super::RenderError> {
+        #[cfg(feature = "exr")]
+        self.save_depth(
+            &auxiliary_path(&self.path, "depth.exr"),
+            &auxiliary.depth_buffer.read()?,
+        )?;
+
+        // Only the RGB channels are kept, the alpha channel being opaque
+        let to_rgba8 = |data: &[u8], map: fn(f32) -> f32| {
+            data.chunks_exact(16)
+                .flat_map(|texel| {
+                    let [r, g, b, _] = texel_channels(texel);
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let [r, g, b] = [r, g, b]
+                        .map(|channel| (map(channel).clamp(0.0, 1.0) * 255.0).round() as u8);
+                    [r, g, b, u8::MAX]
+                })
+                .collect::<Vec<_>>()
+        };
+
+        self.save_png(
+            &auxiliary_path(&self.path, "normal.png"),
+            &to_rgba8(&auxiliary.normal_buffer.read()?, |channel| {
+                channel.mul_add(0.5, 0.5)
+            }),
+        )?;
+        self.save_png(
+            &auxiliary_path(&self.path, "albedo.png"),
+            &to_rgba8(&auxiliary.albedo_buffer.read()?, |channel| {
+                channel.powf(1.0 / 2.2)
+            }),
+        )
+    }
+}
+
+#[cfg(feature = "denoise")]
+impl Image {
+    #[must_use]
+    /// Denoises the given pixels, in the format of the image, with Intel Open Image Denoise.
+    ///
+    /// The albedo and normals guide the filter if the auxiliary outputs are saved,
+    /// otherwise only the colors are used.
+    ///
+    /// If the filter fails, the error is logged and the pixels are returned as is.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the auxiliary buffers cannot be read.
+    fn denoise(&self, data: &[u8]) -> Vec<u8> {
+        let start = std::time::Instant::now();
+
+        let to_rgb = |data: &[u8]| {
+            data.chunks_exact(16)
+                .flat_map(|texel| {
+                    let [r, g, b, _] = texel_channels(texel);
+                    [r, g, b]
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut color = match self.format {
+            ImageFormat::Png8 => data
+                .chunks_exact(4)
+                .flat_map(|texel| {
+                    [texel[0], texel[1], texel[2]].map(|channel| f32::from(channel) / 255.0)
+                })
+                .collect::<Vec<_>>(),
+            #[cfg(feature = "exr")]
+            ImageFormat::ExrF32 => to_rgb(data),
+        };
+
+        let device = oidn::Device::new();
+        let mut filter = oidn::RayTracing::new(&device);
+        filter
+            .image_dimensions(self.width as usize, self.height as usize)
+            // 8-bit colors are gamma-corrected and clamped, float ones are linear
+            .srgb(self.format == ImageFormat::Png8)
+            .hdr(self.format != ImageFormat::Png8);
+
+        let auxiliary = self.auxiliary.as_ref().map(|auxiliary| {
+            (
+                to_rgb(&auxiliary.albedo_buffer.read().unwrap()),
+                to_rgb(&auxiliary.normal_buffer.read().unwrap()),
+            )
+        });
+        if let Some((albedo, normal)) = &auxiliary {
+            filter.albedo_normal(albedo, normal);
+        }
+
+        if let Err(error) = filter.filter_in_place(&mut color) {
+            tracing::error!("Failed to denoise the image: {error:?}");
+            return data.to_vec();
+        }
+        if let Err((error, message)) = device.get_error() {
+            tracing::error!("Failed to denoise the image: {error:?} {message}");
+            return data.to_vec();
+        }
+        tracing::debug!(
+            "Image denoised in {:?}{}",
+            start.elapsed(),
+            if auxiliary.is_some() {
+                ""
+            } else {
+                ", from its colors only"
+            }
+        );
+
+        match self.format {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            ImageFormat::Png8 => color
+                .chunks_exact(3)
+                .flat_map(|texel| {
+                    let [r, g, b] = [texel[0], texel[1], texel[2]]
+                        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+                    [r, g, b, u8::MAX]
+                })
+                .collect(),
+            #[cfg(feature = "exr")]
+            ImageFormat::ExrF32 => color
+                .chunks_exact(3)
+                .flat_map(|texel| [texel[0], texel[1], texel[2], 1.0])
+                .flat_map(f32::to_ne_bytes)
+                .collect(),
         }
     }
 }
 
+#[must_use]
+/// Returns the path of the auxiliary output with the given name saved next to the image at `path`,
+/// such as `render.depth.exr` for the depth of `render.png`.
+fn auxiliary_path(path: &Path, name: &str) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(name);
+    path.with_file_name(file_name)
+}
+
+#[must_use]
+#[inline]
+/// Returns the four channels of a RGBA32F texel.
+fn texel_channels(texel: &[u8]) -> [f32; 4] {
+    std::array::from_fn(|i| {
+        f32::from_ne_bytes([
+            texel[i * 4],
+            texel[i * 4 + 1],
+            texel[i * 4 + 2],
+            texel[i * 4 + 3],
+        ])
+    })
+}
+
 impl super::RenderSurface for Image {
     #[must_use]
     #[inline]
@@ -130,9 +583,17 @@ impl super::RenderSurface for Image {
         &self.image_view
     }
 
+    #[must_use]
+    #[inline]
+    fn auxiliary_views(&self) -> &[super::AuxiliaryViews] {
+        self.auxiliary
+            .as_ref()
+            .map_or(&[], |auxiliary| &auxiliary.views)
+    }
+
     #[must_use = "The function returns a future that must be awaited"]
     #[inline]
-    fn acquire(&mut self) -> Result<(u32, Box<dyn vulkano::sync::GpuFuture>), super::AcquireError> {
+    fn acquire(&mut self) -> Result<(u32, Box<dyn vulkano::sync::GpuFuture>), super::RenderError> {
         Ok((0, Box::new(sync::now(self.compute_queue.device().clone()))))
     }
 
@@ -140,31 +601,34 @@ impl super::RenderSurface for Image {
         &mut self,
         render_future: Box<dyn vulkano::sync::GpuFuture>,
         _queue: &std::sync::Arc<vulkano::device::Queue>,
-    ) -> Result<(), super::PresentError> {
+    ) -> Result<(), super::RenderError> {
         let future = render_future.then_signal_fence_and_flush();
 
         match future.map_err(vulkano::Validated::unwrap) {
             Ok(future) => {
                 let future = future
-                    .then_execute(self.compute_queue.clone(), self.command_buffer.clone())
-                    .unwrap()
+                    .then_execute(self.compute_queue.clone(), self.command_buffer.clone())?
                     .then_signal_fence_and_flush()
-                    .unwrap();
-
-                let file = std::fs::File::create(&self.path).unwrap();
-                let file_writer = &mut BufWriter::new(file);
-
-                let mut encoder = png::Encoder::new(file_writer, self.width, self.height);
-                encoder.set_color(png::ColorType::Rgba);
-                encoder.set_depth(png::BitDepth::Eight);
+                    .map_err(vulkano::Validated::unwrap)?;
 
-                let mut png_writer = encoder.write_header().unwrap();
+                future.wait(None).map_err(vulkano::Validated::unwrap)?;
 
-                future.wait(None).unwrap();
+                let reader = self.inner_buffer.read()?;
+                #[cfg(feature = "denoise")]
+                let reader = if self.denoise {
+                    std::borrow::Cow::Owned(self.denoise(&reader))
+                } else {
+                    std::borrow::Cow::Borrowed(&*reader)
+                };
 
-                let reader = self.inner_buffer.read().unwrap();
-
-                png_writer.write_image_data(&reader).unwrap();
+                match self.format {
+                    ImageFormat::Png8 => self.save_png(&self.path, &reader)?,
+                    #[cfg(feature = "exr")]
+                    ImageFormat::ExrF32 => self.save_exr(&reader)?,
+                }
+                if let Some(auxiliary) = &self.auxiliary {
+                    self.save_auxiliary_outputs(auxiliary)?;
+                }
 
                 let elapsed = self.start_time.elapsed();
                 tracing::info!(
@@ -175,10 +639,34 @@ impl super::RenderSurface for Image {
 
                 Ok(())
             }
-            Err(e) => {
-                tracing::error!("Failed to flush rendering future: {e}");
-                Err(super::PresentError)
-            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Represents the file format an image is saved in.
+pub enum ImageFormat {
+    #[default]
+    /// An 8-bit PNG file, holding gamma-corrected colors clamped to `[0, 1]`.
+    Png8,
+    #[cfg(feature = "exr")]
+    /// A 32-bit float EXR file, holding linear colors with their full dynamic range.
+    ///
+    /// This is better suited to compositing, as nothing is clamped.
+    ExrF32,
+}
+
+impl ImageFormat {
+    #[must_use]
+    #[inline]
+    /// Returns the format of the image the renderer copies its output to.
+    const fn vulkan_format(self) -> vulkano::format::Format {
+        match self {
+            Self::Png8 => vulkano::format::Format::R8G8B8A8_UNORM,
+            #[cfg(feature = "exr")]
+            Self::ExrF32 => vulkano::format::Format::R32G32B32A32_SFLOAT,
         }
     }
 }
@@ -189,8 +677,48 @@ impl super::RenderSurface for Image {
 pub struct ImageDescriptor {
     /// The path to save the image to.
     pub path: PathBuf,
+    /// The format to save the image in.
+    pub format: ImageFormat,
     /// The width of the image.
     pub width: u32,
     /// The height of the image.
     pub height: u32,
+    /// The number of frames averaged together before the image is saved.
+    ///
+    /// More frames reduce the noise of the image. `0` and `1` both render a single frame.
+    pub accumulated_frames: u32,
+    /// Whether to also save the auxiliary outputs of the renderer next to the image,
+    /// typically to feed an external denoiser.
+    ///
+    /// They are named after the image, such as `render.depth.exr`, which requires the `exr` feature,
+    /// `render.normal.png` and `render.albedo.png` for an image saved as `render.png`,
+    /// so that images saved in the same directory keep their own.
+    pub save_auxiliary_outputs: bool,
+    /// Whether to denoise the image before saving it, which requires the `denoise` feature.
+    ///
+    /// The auxiliary outputs guide the denoiser if they are saved,
+    /// otherwise the image is denoised from its colors only.
+    pub denoise: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// The auxiliary outputs are named after the image, in its directory.
+    fn auxiliary_outputs_are_named_after_the_image() {
+        assert_eq!(
+            auxiliary_path(Path::new("out/render.png"), "depth.exr"),
+            Path::new("out/render.depth.exr")
+        );
+        assert_eq!(
+            auxiliary_path(Path::new("render.v2.exr"), "normal.png"),
+            Path::new("render.v2.normal.png")
+        );
+        assert_eq!(
+            auxiliary_path(Path::new("out/render"), "albedo.png"),
+            Path::new("out/render.albedo.png")
+        );
+    }
 }
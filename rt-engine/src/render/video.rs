@@ -0,0 +1,275 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::Arc,
+};
+
+use vulkano::{
+    buffer::Subbuffer,
+    command_buffer::{
+        allocator::StandardCommandBufferAllocator, CopyImageToBufferInfo, PrimaryAutoCommandBuffer,
+    },
+    device::Queue,
+    image::{
+        view::{ImageView, ImageViewCreateInfo},
+        ImageCreateInfo, ImageUsage,
+    },
+    memory::allocator::{AllocationCreateInfo, StandardMemoryAllocator},
+    sync::{self, GpuFuture},
+};
+
+/// Represents a video that can be rendered to, one frame at a time.
+///
+/// Every presented frame is piped to an `ffmpeg` subprocess, which must be found in the `PATH`.
+/// The video is finalized once its last frame is presented, or when the surface is dropped.
+pub struct VideoSurface {
+    /// The path the video is saved to.
+    path: PathBuf,
+    /// The width of the video.
+    width: u32,
+    /// The height of the video.
+    height: u32,
+    /// The number of frames of the video.
+    frame_count: u32,
+    /// The number of frames already piped to the encoder.
+    written_frames: u32,
+    /// The internal image view of the frames.
+    image_view: [Arc<ImageView>; 1],
+    /// CPU accessible buffer the frames are read back from.
+    inner_buffer: Subbuffer<[u8]>,
+    /// The queue the frames are copied to the buffer on.
+    compute_queue: Arc<Queue>,
+    /// Command buffer used to copy the image to the buffer.
+    command_buffer: Arc<PrimaryAutoCommandBuffer<Arc<StandardCommandBufferAllocator>>>,
+    /// The `ffmpeg` process and its standard input, until the video is finalized.
+    encoder: Option<(Child, ChildStdin)>,
+    /// Used to benchmark the rendering time.
+    start_time: std::time::Instant,
+}
+
+impl VideoSurface {
+    /// Creates a new video from the given video descriptor, and starts its encoder.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the size of the video is odd, which the encoder does not support,
+    /// or if `ffmpeg` cannot be started. The cause is logged.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the inner image, buffer or command buffer creation fails.
+    pub fn new(
+        video_descriptor: &VideoDescriptor,
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+        compute_queue: Arc<Queue>,
+    ) -> Result<Self, crate::InitError> {
+        let VideoDescriptor {
+            path,
+            width,
+            height,
+            fps,
+            frame_count,
+        } = video_descriptor;
+
+        // Chroma subsampling halves both dimensions
+        if width % 2 != 0 || height % 2 != 0 {
+            tracing::error!("Video size {width}x{height} must be even");
+            return Err(crate::InitError::SurfaceCreation);
+        }
+
+        let encoder = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{width}x{height}"), "-r", &fps.to_string()])
+            .args(["-i", "-", "-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|error| {
+                tracing::error!("Failed to start ffmpeg: {error}");
+                crate::InitError::SurfaceCreation
+            })?;
+        let encoder = {
+            let mut encoder = encoder;
+            let stdin = encoder.stdin.take().unwrap();
+            (encoder, stdin)
+        };
+
+        let image = vulkano::image::Image::new(
+            memory_allocator.clone(),
+            ImageCreateInfo {
+                format: vulkano::format::Format::R8G8B8A8_UNORM,
+                extent: [*width, *height, 1],
+                usage: ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )
+        .unwrap();
+        let image_view =
+            ImageView::new(image.clone(), ImageViewCreateInfo::from_image(&image)).unwrap();
+
+        let inner_buffer = vulkano::buffer::Buffer::new_unsized(
+            memory_allocator.clone(),
+            vulkano::buffer::BufferCreateInfo {
+                usage: vulkano::buffer::BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: vulkano::memory::allocator::MemoryTypeFilter::PREFER_HOST
+                    | vulkano::memory::allocator::MemoryTypeFilter::HOST_RANDOM_ACCESS,
+                ..Default::default()
+            },
+            u64::from(*width) * u64::from(*height) * 4,
+        )
+        .unwrap();
+
+        let command_buffer = {
+            let mut builder = vulkano::command_buffer::AutoCommandBufferBuilder::primary(
+                command_buffer_allocator,
+                compute_queue.queue_family_index(),
+                vulkano::command_buffer::CommandBufferUsage::MultipleSubmit,
+            )
+            .unwrap();
+
+            builder
+                .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
+                    image,
+                    inner_buffer.clone(),
+                ))
+                .unwrap();
+
+            builder.build().unwrap()
+        };
+
+        Ok(Self {
+            path: path.clone(),
+            width: *width,
+            height: *height,
+            frame_count: *frame_count,
+            written_frames: 0,
+            image_view: [image_view],
+            inner_buffer,
+            compute_queue,
+            command_buffer,
+            encoder: Some(encoder),
+            start_time: std::time::Instant::now(),
+        })
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether every frame of the video has been presented.
+    pub const fn is_finished(&self) -> bool {
+        self.written_frames >= self.frame_count
+    }
+
+    /// Closes the input of the encoder and waits for it to write the video, if it is still running.
+    fn finish(&mut self) {
+        let Some((mut encoder, stdin)) = self.encoder.take() else {
+            return;
+        };
+
+        // Closing the input tells the encoder that there are no more frames
+        drop(stdin);
+        match encoder.wait() {
+            Ok(status) if status.success() => tracing::info!(
+                "Video of {} frames succesfully rendered and saved to {:?} in {:?}.",
+                self.written_frames,
+                self.path,
+                self.start_time.elapsed()
+            ),
+            Ok(status) => tracing::error!("ffmpeg failed to encode {:?}: {status}", self.path),
+            Err(error) => tracing::error!("Failed to wait for ffmpeg: {error}"),
+        }
+    }
+}
+
+impl Drop for VideoSurface {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl super::RenderSurface for VideoSurface {
+    #[must_use]
+    #[inline]
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    #[must_use]
+    #[inline]
+    fn views(&self) -> &[Arc<ImageView>] {
+        &self.image_view
+    }
+
+    #[must_use = "The function returns a future that must be awaited"]
+    #[inline]
+    fn acquire(&mut self) -> Result<(u32, Box<dyn vulkano::sync::GpuFuture>), super::RenderError> {
+        Ok((0, Box::new(sync::now(self.compute_queue.device().clone()))))
+    }
+
+    fn present(
+        &mut self,
+        render_future: Box<dyn vulkano::sync::GpuFuture>,
+        _queue: &std::sync::Arc<vulkano::device::Queue>,
+    ) -> Result<(), super::RenderError> {
+        render_future
+            .then_signal_fence_and_flush()
+            .map_err(vulkano::Validated::unwrap)?
+            .then_execute(self.compute_queue.clone(), self.command_buffer.clone())?
+            .then_signal_fence_and_flush()
+            .map_err(vulkano::Validated::unwrap)?
+            .wait(None)
+            .map_err(vulkano::Validated::unwrap)?;
+
+        let Some((_, stdin)) = &mut self.encoder else {
+            tracing::warn!("Video {:?} is already finalized, frame dropped", self.path);
+            return Ok(());
+        };
+
+        let reader = self.inner_buffer.read()?;
+        if let Err(error) = stdin.write_all(&reader) {
+            // The encoder exited, its own error explains why
+            tracing::error!("Failed to pipe frame to ffmpeg: {error}");
+            drop(reader);
+            self.finish();
+            return Ok(());
+        }
+        drop(reader);
+
+        self.written_frames += 1;
+        tracing::trace!(
+            "Frame {}/{} of {:?} encoded",
+            self.written_frames,
+            self.frame_count,
+            self.path
+        );
+        if self.is_finished() {
+            self.finish();
+        }
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug)]
+/// Represents a video descriptor.
+pub struct VideoDescriptor {
+    /// The path to save the video to, whose extension selects the container, such as `.mp4`.
+    pub path: PathBuf,
+    /// The width of the video, which must be even.
+    pub width: u32,
+    /// The height of the video, which must be even.
+    pub height: u32,
+    /// The number of frames per second of the video.
+    ///
+    /// The cameras advance by `1 / fps` seconds between two frames, whatever the time spent rendering them.
+    pub fps: u32,
+    /// The number of frames of the video.
+    pub frame_count: u32,
+}
@@ -1,14 +1,18 @@
-use std::sync::Arc;
+use std::{collections::VecDeque, sync::Arc};
 
 use vulkano::{
-    device::{Device, Queue},
+    device::{Device, DeviceOwned, Queue},
     image::{view::ImageView, ImageUsage},
     swapchain::{self, Surface, SurfaceInfo, Swapchain, SwapchainCreateInfo},
-    sync::GpuFuture,
+    sync::{future::FenceSignalFuture, GpuFuture},
+};
+use winit::{
+    dpi::LogicalSize,
+    monitor::{MonitorHandle, VideoMode},
+    window::{CursorGrabMode, Fullscreen},
 };
-use winit::{dpi::LogicalSize, window::CursorGrabMode};
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 /// Represents a window.
 pub struct Window {
     /// Inner `winit` window.
@@ -19,51 +23,56 @@ pub struct Window {
     image_views: Vec<Arc<ImageView>>,
     /// Whether the swapchain needs to be recreated.
     recreate_swapchain: bool,
+    /// Whether the views have been recreated since the renderer last recorded its command buffers.
+    views_recreated: bool,
     /// The index of the image to be rendered.
     image_index: u32,
-    /// The present mode of the window.
+    /// The present mode requested for the window.
+    ///
+    /// The swapchain falls back to [`PresentMode::Fifo`] if it is not supported.
     present_mode: PresentMode,
+    /// The mode of the window.
+    mode: Mode,
+    /// The inner size of the window in windowed mode, restored when leaving fullscreen.
+    windowed_size: winit::dpi::Size,
+    /// The position of the window in windowed mode, restored when leaving fullscreen.
+    windowed_position: Option<winit::dpi::Position>,
+    /// Whether the cursor is grabbed while the window has the focus.
+    cursor_locked: bool,
+    /// Whether the cursor is visible while it is grabbed.
+    cursor_visible: bool,
+    /// The number of frames that may be rendered at the same time, at most the number of swapchain images.
+    frames_in_flight: usize,
+    /// The presentations that may still be in progress, oldest first.
+    presented_frames: VecDeque<Arc<PresentedFrame>>,
 }
 
+/// The future of a presented frame, signaled once the presentation is complete.
+type PresentedFrame = FenceSignalFuture<swapchain::PresentFuture<Box<dyn GpuFuture>>>;
+
 impl Window {
-    #[must_use]
     /// Creates a new window.
     ///
+    /// ## Errors
+    ///
+    /// The function returns an error if the window or its surface could not be created,
+    /// or if the surface does not support the requested usage of the swapchain images.
+    ///
     /// ## Panics
     ///
-    /// The function will panic if anything goes wrong during window creation.
+    /// The function will panic if anything else goes wrong during window creation.
     pub fn new(
         event_loop: &winit::event_loop::EventLoop<()>,
         device: &Arc<Device>,
         window_descriptor: &WindowDescriptor,
-    ) -> Self {
+    ) -> Result<Self, crate::InitError> {
         let mut winit_window_builder: winit::window::WindowBuilder =
             winit::window::WindowBuilder::new().with_title(&window_descriptor.title);
 
+        let fullscreen = Self::fullscreen(window_descriptor.mode, event_loop.primary_monitor());
         winit_window_builder = match window_descriptor.mode {
-            Mode::BorderlessFullscreen => winit_window_builder.with_fullscreen(Some(
-                winit::window::Fullscreen::Borderless(event_loop.primary_monitor()),
-            )),
-            Mode::Fullscreen => {
-                winit_window_builder.with_fullscreen(Some(if cfg!(target_os = "macos") {
-                    winit::window::Fullscreen::Borderless(event_loop.primary_monitor())
-                } else {
-                    // FIXME: Exclusive fullscreen
-                    winit::window::Fullscreen::Exclusive({
-                        let video_mode = Self::get_best_videomode(
-                            &event_loop
-                                .primary_monitor()
-                                .expect("could not find primary monitor"),
-                        );
-                        tracing::debug!(
-                            "Best video mode: {}x{} @ {}Hz",
-                            video_mode.size().width,
-                            video_mode.size().height,
-                            video_mode.refresh_rate_millihertz() / 1000
-                        );
-                        video_mode
-                    })
-                }))
+            Mode::BorderlessFullscreen | Mode::Fullscreen => {
+                winit_window_builder.with_fullscreen(fullscreen.clone())
             }
             Mode::Windowed => {
                 let WindowDescriptor {
@@ -104,53 +113,201 @@ impl Window {
                 winit_window_builder.with_min_inner_size(min_inner_size)
             };
 
-        let winit_window = winit_window_builder.build(event_loop).unwrap();
+        let winit_window = winit_window_builder.build(event_loop).map_err(|error| {
+            tracing::error!("Failed to create window: {error}");
+            crate::InitError::SurfaceCreation
+        })?;
 
         if let Some(monitor) = winit_window.current_monitor() {
             if let Some(name) = monitor.name() {
                 tracing::info!("Window created on monitor {}", name);
             }
         }
+        Self::check_exclusive_fullscreen(&winit_window, fullscreen.as_ref());
 
         if window_descriptor.cursor_locked {
-            match winit_window.set_cursor_grab(if cfg!(target_os = "macos") {
-                CursorGrabMode::Locked
-            } else {
-                CursorGrabMode::Confined
-            }) {
-                Ok(()) => (),
-                Err(winit::error::ExternalError::NotSupported(_)) => {
-                    tracing::warn!("Cursor confinement is not supported on this platform");
-                }
-                Err(err) => tracing::error!("Error confining cursor: {err:?}"),
-            }
+            Self::grab_cursor(&winit_window, true, window_descriptor.cursor_visible);
+        } else {
+            winit_window.set_cursor_visible(window_descriptor.cursor_visible);
         }
 
-        winit_window.set_cursor_visible(window_descriptor.cursor_visible);
-
         let window = Arc::new(winit_window);
 
         let (swapchain, final_views) =
-            Self::create_swapchain(device.clone(), &window, window_descriptor);
+            Self::create_swapchain(device.clone(), &window, window_descriptor)?;
 
-        Self {
+        // Frames in flight beyond the number of images would wait for an image to be released anyway
+        let frames_in_flight =
+            (window_descriptor.frames_in_flight.max(1) as usize).min(final_views.len());
+        if frames_in_flight < window_descriptor.frames_in_flight as usize {
+            tracing::warn!(
+                "{} frames in flight requested, but the swapchain only has {} images",
+                window_descriptor.frames_in_flight,
+                final_views.len()
+            );
+        }
+
+        Ok(Self {
             window,
             recreate_swapchain: false,
+            views_recreated: false,
             image_index: 0,
             present_mode: window_descriptor.present_mode,
+            mode: window_descriptor.mode,
+            windowed_size: LogicalSize::new(window_descriptor.width, window_descriptor.height)
+                .into(),
+            windowed_position: window_descriptor.position.map(|position| {
+                winit::dpi::LogicalPosition::new(f64::from(position[0]), f64::from(position[1]))
+                    .into()
+            }),
+            cursor_locked: window_descriptor.cursor_locked,
+            cursor_visible: window_descriptor.cursor_visible,
+            frames_in_flight,
+            presented_frames: VecDeque::new(),
             swapchain,
             image_views: final_views,
+        })
+    }
+
+    /// Grabs or releases the cursor.
+    ///
+    /// A grabbed cursor is confined to the window, or locked in place on macOS,
+    /// and hidden unless `cursor_visible` was set in the window descriptor.
+    /// It is released while the window does not have the focus, then grabbed again.
+    /// A released cursor is always visible, for instance to click on a menu.
+    pub fn set_cursor_grab(&mut self, grab: bool) {
+        if grab == self.cursor_locked {
+            return;
         }
+
+        self.cursor_locked = grab;
+        Self::grab_cursor(&self.window, grab, self.cursor_visible);
+        tracing::debug!("Cursor {}", if grab { "grabbed" } else { "released" });
+    }
+
+    /// Releases the cursor when the window loses the focus, and grabs it again when the window regains it,
+    /// if the cursor is grabbed.
+    pub fn set_focused(&mut self, focused: bool) {
+        if self.cursor_locked {
+            Self::grab_cursor(&self.window, focused, self.cursor_visible);
+        }
+    }
+
+    /// Grabs or releases the cursor of the given window, showing it when it is released.
+    ///
+    /// Platforms that cannot grab the cursor only report it.
+    fn grab_cursor(window: &winit::window::Window, grab: bool, cursor_visible: bool) {
+        let mode = if !grab {
+            CursorGrabMode::None
+        } else if cfg!(target_os = "macos") {
+            CursorGrabMode::Locked
+        } else {
+            CursorGrabMode::Confined
+        };
+
+        match window.set_cursor_grab(mode) {
+            Ok(()) => (),
+            Err(winit::error::ExternalError::NotSupported(_)) => {
+                tracing::warn!("Cursor confinement is not supported on this platform");
+            }
+            Err(err) => tracing::error!("Error confining cursor: {err:?}"),
+        }
+        window.set_cursor_visible(cursor_visible || !grab);
+    }
+
+    /// Switches the window to the given mode.
+    ///
+    /// Going back to windowed mode restores the size and position
+    /// the window had before going fullscreen.
+    /// The swapchain is recreated at the new size before the next frame.
+    pub fn set_mode(&mut self, mode: Mode) {
+        if mode == self.mode {
+            return;
+        }
+
+        if self.mode == Mode::Windowed {
+            self.windowed_size = self.window.inner_size().into();
+            self.windowed_position = self.window.outer_position().ok().map(Into::into);
+        }
+
+        let fullscreen = Self::fullscreen(mode, self.window.current_monitor());
+        self.window.set_fullscreen(fullscreen.clone());
+        if mode == Mode::Windowed {
+            self.window.set_inner_size(self.windowed_size);
+            if let Some(position) = self.windowed_position {
+                self.window.set_outer_position(position);
+            }
+        } else {
+            Self::check_exclusive_fullscreen(&self.window, fullscreen.as_ref());
+        }
+
+        self.mode = mode;
+        self.recreate_swapchain = true;
+        tracing::debug!("Window mode set to {mode:?}");
     }
 
     #[must_use]
+    /// Returns the `winit` fullscreen mode matching the given window mode on the given monitor.
+    ///
+    /// Exclusive fullscreen uses the best video mode of the monitor.
+    /// It is replaced by borderless fullscreen on macOS, or if the monitor has no video mode.
+    fn fullscreen(mode: Mode, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        match mode {
+            Mode::Windowed => None,
+            Mode::Fullscreen if !cfg!(target_os = "macos") => {
+                let Some(video_mode) = monitor.as_ref().and_then(Self::get_best_videomode) else {
+                    tracing::warn!(
+                        "No video mode available for exclusive fullscreen, using borderless fullscreen"
+                    );
+                    return Some(Fullscreen::Borderless(monitor));
+                };
+                tracing::debug!(
+                    "Best video mode: {}x{} @ {}Hz",
+                    video_mode.size().width,
+                    video_mode.size().height,
+                    video_mode.refresh_rate_millihertz() / 1000
+                );
+                Some(Fullscreen::Exclusive(video_mode))
+            }
+            Mode::BorderlessFullscreen | Mode::Fullscreen => Some(Fullscreen::Borderless(monitor)),
+        }
+    }
+
+    /// Warns if the window is not in the exclusive fullscreen mode it requested,
+    /// typically because the compositor does not allow it.
+    fn check_exclusive_fullscreen(window: &winit::window::Window, requested: Option<&Fullscreen>) {
+        if let Some(Fullscreen::Exclusive(video_mode)) = requested {
+            if !matches!(window.fullscreen(), Some(Fullscreen::Exclusive(_))) {
+                tracing::warn!(
+                    "Exclusive fullscreen at {}x{} was rejected, the window stays in {}",
+                    video_mode.size().width,
+                    video_mode.size().height,
+                    if window.fullscreen().is_some() {
+                        "borderless fullscreen"
+                    } else {
+                        "windowed mode"
+                    }
+                );
+            }
+        }
+    }
+
     /// Creates a new swapchain.
+    ///
+    /// ## Errors
+    ///
+    /// The function returns an error if the surface of the window could not be created,
+    /// or if it does not support the requested usage of the swapchain images.
     fn create_swapchain(
         device: Arc<Device>,
         window: &Arc<winit::window::Window>,
         window_descriptor: &WindowDescriptor,
-    ) -> (Arc<Swapchain>, Vec<Arc<ImageView>>) {
-        let surface = Surface::from_window(device.instance().clone(), window.clone()).unwrap();
+    ) -> Result<(Arc<Swapchain>, Vec<Arc<ImageView>>), crate::InitError> {
+        let surface =
+            Surface::from_window(device.instance().clone(), window.clone()).map_err(|error| {
+                tracing::error!("Failed to create window surface: {error}");
+                crate::InitError::SurfaceCreation
+            })?;
         let surface_capabilities = device
             .physical_device()
             .surface_capabilities(&surface, SurfaceInfo::default())
@@ -166,26 +323,23 @@ impl Window {
             "required surface format R8G8B8A8_UNORM is not supported"
         );
 
-        let mut available_swapchain_present_modes = device
-            .physical_device()
-            .surface_present_modes(&surface, SurfaceInfo::default())
-            .unwrap();
+        let present_mode =
+            Self::supported_present_mode(&device, &surface, window_descriptor.present_mode);
 
-        let present_mode = if available_swapchain_present_modes
-            .any(|p| p == window_descriptor.present_mode.into())
+        let image_usage = ImageUsage::TRANSFER_DST
+            | ImageUsage::COLOR_ATTACHMENT
+            | window_descriptor.extra_swapchain_usage;
+        if !surface_capabilities
+            .supported_usage_flags
+            .contains(image_usage)
         {
-            window_descriptor.present_mode
-        } else {
-            /// This present mode is guaranteed to be supported,
-            /// so we can safely fall back to it.
-            const FALLBACK_PRESENT_MODE: PresentMode = PresentMode::Fifo;
-            tracing::warn!(
-                "request present mode {:?} not supported, falling back to {:?}",
-                window_descriptor.present_mode,
-                FALLBACK_PRESENT_MODE
+            tracing::error!(
+                "Requested swapchain image usage {:?} is not supported by the surface (supported: {:?})",
+                image_usage,
+                surface_capabilities.supported_usage_flags
             );
-            FALLBACK_PRESENT_MODE
-        };
+            return Err(crate::InitError::SurfaceCreation);
+        }
 
         let (swapchain, images) = Swapchain::new(
             device,
@@ -194,7 +348,7 @@ impl Window {
                 min_image_count: surface_capabilities.min_image_count + 1,
                 image_format: vulkano::format::Format::R8G8B8A8_UNORM,
                 image_extent: window.inner_size().into(),
-                image_usage: ImageUsage::STORAGE | ImageUsage::COLOR_ATTACHMENT,
+                image_usage,
                 present_mode: present_mode.into(),
                 ..Default::default()
             },
@@ -206,46 +360,99 @@ impl Window {
             .map(|image| ImageView::new_default(image).unwrap())
             .collect::<Vec<_>>();
 
-        (swapchain, images_views)
+        Ok((swapchain, images_views))
     }
 
     #[must_use]
-    /// Returns the best video mode of the given monitor.
-    fn get_best_videomode(monitor: &winit::monitor::MonitorHandle) -> winit::monitor::VideoMode {
-        monitor
-            .video_modes()
-            .max_by(|a, b| {
-                (a.size().width, a.size().height, a.refresh_rate_millihertz()).cmp(&(
-                    b.size().width,
-                    b.size().height,
-                    b.refresh_rate_millihertz(),
-                ))
-            })
-            .unwrap()
+    /// Returns the given present mode if the surface supports it,
+    /// or [`PresentMode::Fifo`] otherwise.
+    fn supported_present_mode(
+        device: &Arc<Device>,
+        surface: &Surface,
+        present_mode: PresentMode,
+    ) -> PresentMode {
+        let mut available_swapchain_present_modes = device
+            .physical_device()
+            .surface_present_modes(surface, SurfaceInfo::default())
+            .unwrap();
+
+        if available_swapchain_present_modes.any(|p| p == present_mode.into()) {
+            present_mode
+        } else {
+            /// This present mode is guaranteed to be supported,
+            /// so we can safely fall back to it.
+            const FALLBACK_PRESENT_MODE: PresentMode = PresentMode::Fifo;
+            tracing::warn!(
+                "request present mode {:?} not supported, falling back to {:?}",
+                present_mode,
+                FALLBACK_PRESENT_MODE
+            );
+            FALLBACK_PRESENT_MODE
+        }
+    }
+
+    /// Sets the present mode of the window, typically to toggle vertical synchronization.
+    ///
+    /// The swapchain is recreated with the new present mode before the next frame,
+    /// falling back to [`PresentMode::Fifo`] if it is not supported.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        if present_mode == self.present_mode {
+            return;
+        }
+
+        self.present_mode = present_mode;
+        self.recreate_swapchain = true;
+        tracing::debug!("Present mode set to {present_mode:?}");
+    }
+
+    #[must_use]
+    /// Returns the best video mode of the given monitor, or `None` if it has none.
+    fn get_best_videomode(monitor: &MonitorHandle) -> Option<VideoMode> {
+        monitor.video_modes().max_by(|a, b| {
+            (a.size().width, a.size().height, a.refresh_rate_millihertz()).cmp(&(
+                b.size().width,
+                b.size().height,
+                b.refresh_rate_millihertz(),
+            ))
+        })
     }
 
     /// Recreates the swapchain and its views.
-    fn recreate_swapchain_and_views(&mut self) {
+    ///
+    /// Does nothing if the window is minimized.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the swapchain or its views cannot be created,
+    /// in which case the swapchain is recreated again at the next acquisition.
+    fn recreate_swapchain_and_views(&mut self) -> Result<(), super::RenderError> {
         let [desired_width, desired_height]: [u32; 2] = self.window.inner_size().into();
 
         if desired_width == 0 || desired_height == 0 {
-            return;
+            return Ok(());
         }
 
+        let present_mode = Self::supported_present_mode(
+            self.swapchain.device(),
+            self.swapchain.surface(),
+            self.present_mode,
+        );
+
         let (new_swapchain, new_images) = self
             .swapchain
             .recreate(SwapchainCreateInfo {
                 image_extent: [desired_width, desired_height],
-                present_mode: self.present_mode.into(),
+                present_mode: present_mode.into(),
                 ..self.swapchain.create_info()
             })
-            .expect("failed to recreate swapchain");
+            .map_err(vulkano::Validated::unwrap)?;
 
         self.swapchain = new_swapchain;
         let new_images = new_images
             .into_iter()
-            .map(|image| ImageView::new_default(image).unwrap())
-            .collect::<Vec<_>>();
+            .map(ImageView::new_default)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(vulkano::Validated::unwrap)?;
         self.image_views = new_images;
         #[cfg(target_os = "ios")]
         unsafe {
@@ -253,9 +460,23 @@ impl Window {
         }
 
         self.recreate_swapchain = false;
+        self.views_recreated = true;
+
+        tracing::debug!("Swapchain recreated with size {desired_width}x{desired_height}");
+        if let Some(Fullscreen::Exclusive(video_mode)) = self.window.fullscreen() {
+            let size = video_mode.size();
+            if [size.width, size.height] != [desired_width, desired_height] {
+                // The window is resized to the video mode asynchronously on some platforms,
+                // the swapchain is recreated again once it is
+                tracing::debug!(
+                    "Swapchain size differs from the exclusive video mode {}x{}",
+                    size.width,
+                    size.height
+                );
+            }
+        }
 
-        // TODO: Recreate command buffers
-        todo!("recreate command buffers");
+        Ok(())
     }
 }
 
@@ -278,11 +499,11 @@ impl super::RenderSurface for Window {
     ///
     /// ## Errors
     ///
-    /// The function will return a non-fatal error if the swapchain couldn't be acquired.
-    fn acquire(&mut self) -> Result<(u32, Box<dyn vulkano::sync::GpuFuture>), super::AcquireError> {
+    /// The function will return a recoverable error if the swapchain is out of date,
+    /// and a fatal error if the swapchain couldn't be acquired.
+    fn acquire(&mut self) -> Result<(u32, Box<dyn vulkano::sync::GpuFuture>), super::RenderError> {
         if self.recreate_swapchain {
-            self.recreate_swapchain_and_views();
-            // on_recreate_swapchain(&self.final_views);
+            self.recreate_swapchain_and_views()?;
         }
 
         let (image_index, suboptimal, acquire_future) =
@@ -292,9 +513,9 @@ impl super::RenderSurface for Window {
                 Ok(r) => r,
                 Err(vulkano::VulkanError::OutOfDate) => {
                     self.recreate_swapchain = true;
-                    return Err(super::AcquireError);
+                    return Err(super::RenderError::OutOfDate);
                 }
-                Err(e) => panic!("failed to acquire next image: {e}"),
+                Err(e) => return Err(e.into()),
             };
 
         self.recreate_swapchain |= suboptimal;
@@ -307,12 +528,13 @@ impl super::RenderSurface for Window {
     ///
     /// ## Errors
     ///
-    /// The function will return a non-fatal error if the swapchain couldn't be presented.
+    /// The function will return an error if the swapchain couldn't be presented
+    /// or if an error occured while rendering the frame.
     fn present(
         &mut self,
         render_future: Box<dyn vulkano::sync::GpuFuture>,
         queue: &Arc<Queue>,
-    ) -> Result<(), super::PresentError> {
+    ) -> Result<(), super::RenderError> {
         let future = render_future
             .then_swapchain_present(
                 queue.clone(),
@@ -325,21 +547,60 @@ impl super::RenderSurface for Window {
 
         match future.map_err(vulkano::Validated::unwrap) {
             Ok(future) => {
-                future.wait(None).unwrap_or_else(|e| {
-                    tracing::error!("An error occured while rendering next frame: {e}");
-                });
+                // Only the presentations of the other frames in flight may be left in progress,
+                // shared through an `Arc` as the window can be cloned
+                #[allow(clippy::arc_with_non_send_sync)]
+                self.presented_frames.push_back(Arc::new(future));
+                while self.presented_frames.len() >= self.frames_in_flight {
+                    let presented_frame = self.presented_frames.pop_front().unwrap();
+                    presented_frame
+                        .wait(None)
+                        .map_err(vulkano::Validated::unwrap)?;
+                }
                 Ok(())
             }
             Err(vulkano::VulkanError::OutOfDate) => {
                 self.recreate_swapchain = true;
                 Ok(())
             }
-            Err(e) => {
-                tracing::error!("Failed to flush rendering future: {e}");
-                Err(super::PresentError)
-            }
+            Err(e) => Err(e.into()),
         }
     }
+
+    #[inline]
+    fn resized(&mut self) {
+        self.recreate_swapchain = true;
+    }
+
+    #[inline]
+    fn take_recreated_views(&mut self) -> bool {
+        std::mem::take(&mut self.views_recreated)
+    }
+
+    #[inline]
+    fn set_window_mode(&mut self, mode: Mode) {
+        self.set_mode(mode);
+    }
+
+    #[inline]
+    fn set_present_mode(&mut self, present_mode: PresentMode) {
+        Self::set_present_mode(self, present_mode);
+    }
+
+    #[inline]
+    fn set_cursor_grab(&mut self, grab: bool) {
+        Self::set_cursor_grab(self, grab);
+    }
+
+    #[inline]
+    fn frames_in_flight(&self) -> usize {
+        self.frames_in_flight
+    }
+
+    #[inline]
+    fn set_focused(&mut self, focused: bool) {
+        Self::set_focused(self, focused);
+    }
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -365,6 +626,18 @@ pub struct WindowDescriptor {
     pub cursor_locked: bool,
     pub mode: Mode,
     pub present_mode: PresentMode,
+    /// Additional usage flags for the swapchain images.
+    ///
+    /// They are added to the `TRANSFER_DST | COLOR_ATTACHMENT` flags the renderer requires,
+    /// for instance `TRANSFER_SRC` to take screenshots.
+    /// The window cannot be created if its surface does not support them.
+    pub extra_swapchain_usage: ImageUsage,
+    /// The number of frames that may be rendered at the same time.
+    ///
+    /// With 1, the default, the application waits for every frame before preparing the next one.
+    /// With 2 or 3, it prepares the next frames while the GPU renders, at the cost of some latency.
+    /// It is limited to the number of images of the swapchain.
+    pub frames_in_flight: u32,
 }
 
 impl Default for WindowDescriptor {
@@ -380,6 +653,8 @@ impl Default for WindowDescriptor {
             cursor_visible: true,
             mode: Mode::Windowed,
             present_mode: PresentMode::Fifo,
+            extra_swapchain_usage: ImageUsage::empty(),
+            frames_in_flight: 1,
         }
     }
 }
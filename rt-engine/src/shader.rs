@@ -1,5 +1,20 @@
 pub mod model;
 
+/// Loading and importance sampling of environment maps.
+mod environment;
+/// Presets for commonly used materials.
+mod material;
+#[cfg(feature = "cpu-reference")]
+/// Single-threaded CPU path tracer, used as a reference to validate the output of the shader.
+pub(crate) mod reference;
+#[cfg(feature = "scene-file")]
+/// Loading of the scenes described by RON or JSON files.
+mod scene_file;
+/// Direction numbers of the Sobol sequence drawn from by the shader.
+pub(crate) mod sobol;
+/// Loading of the diffuse textures of the materials.
+mod texture;
+
 /// Source code of the shader, this module defines
 /// all the structs used in the shader.
 pub(crate) mod source {
@@ -9,27 +24,236 @@ pub(crate) mod source {
                 ty: "compute",
                 path: r"src/shader/ray_trace.comp",
             },
-        }
+        },
+        custom_derives: [Clone, Copy, Debug],
     }
 }
 
-pub use source::{BvhBuffer, CameraBuffer, Materials, ModelsBuffer, TrianglesBuffer};
+#[cfg(feature = "scene-file")]
+pub use scene_file::{CameraPose, SceneFile, SceneFileError};
 
-#[derive(Debug, Clone)]
+pub use source::{
+    BvhBuffer, CameraBuffer, EmissiveTrianglesBuffer, EnvironmentCdfBuffer, LightsBuffer, Material,
+    Materials, ModelsBuffer, TlasBuffer, TrianglesBuffer,
+};
+
+/// The size of the work groups of the shader, in pixels, when none is given.
+pub(crate) const DEFAULT_WORK_GROUP_SIZE: [u32; 2] = [16, 16];
+
+#[derive(Debug, Clone, Default)]
 /// This struct is used at the initialization of the application.
 ///
-/// It contains the paths of the models and their positions.
+/// It contains the paths of the models, their transforms and their materials.
 pub struct SceneDescriptor {
     /// A vector of path to `.obj files`.
     pub model_paths: Vec<String>,
-    /// A vector of positions for the models.
+    /// A vector of transforms for the models, one for each model.
+    ///
+    /// Their rotation and scale are applied to the vertices on load,
+    /// while their translation is applied by the shader, so that models can be moved afterward.
+    /// Translations convert into transforms, for models that are only positioned.
+    pub transforms: Vec<Transform>,
+    /// Additional placements of the models, sharing their triangles and BVH, each with its own transform.
+    ///
+    /// Instances come after the models in the models buffer,
+    /// so the index of the first instance is the number of models.
+    pub instances: Vec<Instance>,
+    /// The materials of the scene.
+    ///
+    /// If empty, the materials are read from the MTL files of the OBJ models instead,
+    /// each model using its own, and a single default material is used by the models and faces without any.
+    /// Their diffuse textures are then loaded after the ones of `textures`.
+    pub materials: Vec<Material>,
+    /// A vector of indices into `materials`, one for each model.
+    ///
+    /// Models with several materials, such as OBJ files with faces using different `usemtl`,
+    /// use the materials starting at their index, in the order of the file.
+    ///
+    /// If empty, every model uses the first material. It is ignored if `materials` is empty.
+    pub material_indices: Vec<u32>,
+    /// The lights of the scene, in addition to the emissive materials.
+    pub lights: Vec<Light>,
+    /// The path to an equirectangular Radiance `.hdr` image, seen by the rays missing the scene.
+    ///
+    /// As it is seen by every bounce, it also lights the scene.
+    /// If `None`, the background of the shader descriptor is used instead.
+    pub environment: Option<std::path::PathBuf>,
+    /// The paths to the PNG images of the diffuse textures of the scene, which requires the `image` feature.
+    ///
+    /// Materials reference them by index with [`Material::with_texture`],
+    /// their color being multiplied by the texture at the texture coordinates of the hit point.
+    /// Textures of different sizes are resampled to the size of the largest one.
+    pub textures: Vec<std::path::PathBuf>,
+    /// The filter used when sampling the textures of the scene.
+    pub texture_filter: TextureFilter,
+    /// The order in which the vertices of the triangles are given in the models.
+    pub winding: Winding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Represents the placement of a model in the scene.
+///
+/// The model is scaled, then rotated around its origin, then translated.
+pub struct Transform {
+    /// The translation of the model.
+    pub translation: [f32; 3],
+    /// The rotation of the model, in degrees around the X, Y and Z axes, applied in this order.
+    pub rotation_euler: [f32; 3],
+    /// The scale of the model along the X, Y and Z axes.
+    ///
+    /// Negative factors mirror the model, whose triangles are turned back to counter-clockwise order.
+    pub scale: [f32; 3],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::from_translation([0.0; 3])
+    }
+}
+
+impl From<[f32; 3]> for Transform {
+    fn from(translation: [f32; 3]) -> Self {
+        Self::from_translation(translation)
+    }
+}
+
+impl Transform {
+    #[must_use]
+    #[inline]
+    /// Creates a transform that only translates the model.
+    pub const fn from_translation(translation: [f32; 3]) -> Self {
+        Self {
+            translation,
+            rotation_euler: [0.0; 3],
+            scale: [1.0; 3],
+        }
+    }
+
+    #[must_use]
+    /// Returns the matrix of the rotation and the scale of the transform, row by row.
+    pub(crate) fn linear_part(&self) -> [[f32; 3]; 3] {
+        let [(sin_x, cos_x), (sin_y, cos_y), (sin_z, cos_z)] = self
+            .rotation_euler
+            .map(|angle| angle.to_radians().sin_cos());
+
+        // Rotation around Z after Y after X
+        let rotation = [
+            [
+                cos_z * cos_y,
+                (cos_z * sin_y).mul_add(sin_x, -(sin_z * cos_x)),
+                (cos_z * sin_y).mul_add(cos_x, sin_z * sin_x),
+            ],
+            [
+                sin_z * cos_y,
+                (sin_z * sin_y).mul_add(sin_x, cos_z * cos_x),
+                (sin_z * sin_y).mul_add(cos_x, -(cos_z * sin_x)),
+            ],
+            [-sin_y, cos_y * sin_x, cos_y * cos_x],
+        ];
+
+        rotation.map(|row| std::array::from_fn(|column| row[column] * self.scale[column]))
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the transform mirrors the model, reversing the order of the vertices of its triangles.
+    pub(crate) fn is_mirroring(&self) -> bool {
+        self.scale[0] * self.scale[1] * self.scale[2] < 0.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Represents another placement of a model of the scene.
+///
+/// As the triangles and the BVH of the model are shared, an instance only costs an entry
+/// of the models buffer, which suits scenes with many repeated props such as trees or rocks.
+/// The instance also shares the materials of the model.
+pub struct Instance {
+    /// The index of the model in `model_paths`.
+    pub model: usize,
+    /// The placement of the instance, whose translation replaces the one of the model.
+    ///
+    /// Its rotation and scale are applied by the shader on top of the ones of the model,
+    /// which are baked into the shared triangles. The scale must not have a null factor.
+    pub transform: Transform,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Represents a light of the scene.
+///
+/// Lights are sampled explicitly at every bounce, which makes them converge much faster
+/// than emissive materials. They are not visible themselves.
+pub enum Light {
+    /// A light infinitely far away, such as the sun.
+    Directional {
+        /// The direction the light travels in.
+        direction: [f32; 3],
+        /// The color of the light.
+        color: [f32; 3],
+        /// The intensity of the light.
+        intensity: f32,
+    },
+    /// A light emitted from a small sphere, whose intensity decreases with the squared distance.
+    Point {
+        /// The position of the center of the light.
+        position: [f32; 3],
+        /// The color of the light.
+        color: [f32; 3],
+        /// The intensity of the light at a distance of 1.
+        intensity: f32,
+        /// The radius of the light, a larger radius giving softer shadows.
+        radius: f32,
+    },
+}
+
+impl From<Light> for source::Light {
+    fn from(light: Light) -> Self {
+        match light {
+            Light::Directional {
+                direction,
+                color,
+                intensity,
+            } => Self {
+                vector: direction,
+                kind: 0,
+                color,
+                intensity,
+                radius: 0.0,
+            },
+            Light::Point {
+                position,
+                color,
+                intensity,
+                radius,
+            } => Self {
+                vector: position,
+                kind: 1,
+                color,
+                intensity,
+                radius,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Represents the order in which the vertices of a triangle are given,
+/// when looking at its front face.
+///
+/// It determines the direction of the normals computed at load.
+pub enum Winding {
+    #[default]
+    /// Counter-clockwise order.
+    CounterClockwise,
+    /// Clockwise order.
+    Clockwise,
+    /// Detect the order of each model by comparing the triangles to the normals of the model.
     ///
-    /// They represent translations that will be applied
-    /// to models on load.
-    pub positions: Vec<[f32; 3]>,
+    /// Falls back to counter-clockwise order for models without normals.
+    Auto,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 #[allow(clippy::module_name_repetitions)]
 /// This struct is used at the initialization of the application.
 ///
@@ -37,15 +261,347 @@ pub struct SceneDescriptor {
 pub struct ShaderDescriptor {
     /// Max number of bounces for a ray.
     pub max_bounces: u8,
-    /// Max number of samples for a pixel.
+    /// Number of samples traced for each pixel within a single dispatch, and averaged.
+    ///
+    /// Every sample draws its own random numbers, seeded from the pixel and the frame, so this
+    /// improves the quality of single-shot renders independently of the accumulation over frames.
+    /// A value of 0 is treated as 1.
     pub samples: u16,
+    /// Offset applied along the surface normal to the origin of bounced rays,
+    /// to avoid self-intersection.
+    pub ray_epsilon: f32,
+    /// Threshold under which a ray is considered parallel to a triangle, and misses it.
+    ///
+    /// The test is made against the cross product of two edges of the triangle,
+    /// so the value scales with the area of the triangles:
+    /// thin or small triangles may need a smaller value not to be missed.
+    pub intersection_epsilon: f32,
+    /// Distance under which primary rays ignore intersections.
+    ///
+    /// It applies along the rays, on top of the clip planes of the camera.
+    pub near_plane: f32,
+    /// Distance above which intersections are considered as misses.
+    ///
+    /// Primary rays are also limited by the far clip plane of the camera.
+    pub max_distance: f32,
+    /// Whether to derive `ray_epsilon`, `near_plane` and `max_distance`
+    /// from the size of the loaded scene, instead of using the given values.
+    pub auto_scale_precision: bool,
+    /// Anisotropic filtering level used when sampling textures, from 1 (disabled) to 16.
+    ///
+    /// Values outside of this range are clamped, and the level is also limited by the device.
+    /// If the device does not support anisotropic filtering, it is disabled.
+    pub anisotropy: u8,
+    /// The projection used to generate primary rays.
+    pub projection: Projection,
+    /// Whether to spread the primary rays of successive samples over the whole pixel
+    /// along a low-discrepancy pattern, which anti-aliases the edges once samples are accumulated.
+    ///
+    /// When disabled, primary rays are jittered randomly around a corner of the pixel.
+    pub anti_aliasing: bool,
+    /// The operator mapping the rendered colors to the displayable range.
+    ///
+    /// It only applies to the gamma-corrected output, views with a float format
+    /// and the auxiliary outputs still receive linear colors.
+    pub tonemap: ToneMap,
+    /// The exposure of the output in stops, each stop doubling the brightness of the colors.
+    ///
+    /// It applies before tonemapping, whatever the operator, and 0 leaves the colors untouched.
+    /// Like the tonemapping, it does not apply to views with a float format nor to the auxiliary outputs.
+    pub exposure: f32,
+    /// The color rendered as white, the colors being divided by it before tonemapping
+    /// to balance them, such as to neutralize the tint of warm lights.
+    ///
+    /// The default, `[1.0, 1.0, 1.0]`, leaves the colors untouched.
+    /// Non-positive components are treated as 1.
+    pub white_point: [f32; 3],
+    /// The way the random numbers of the samples are drawn.
+    pub sampler: SamplerKind,
+    /// Whether shadow rays are traced towards the lights and the emissive triangles.
+    ///
+    /// Disabling them skips a traversal of the scene per light at every bounce,
+    /// which speeds up weak devices, but the lights then shine through the models.
+    pub shadows: bool,
+    /// The seed of the random numbers drawn by the shader.
+    ///
+    /// The random numbers only depend on the seed, the pixel and the number of accumulated frames,
+    /// so the same scene rendered from the same camera with the same seed gives the same image,
+    /// which allows comparing renders. Other seeds give other noise.
+    pub seed: u64,
+    /// What the shader renders, the scene or a visualization of its acceleration structure.
+    pub debug_mode: DebugMode,
+    /// Number of rays cast from the first hit of each sample by [`DebugMode::AmbientOcclusion`].
+    ///
+    /// A value of 0 is treated as 1.
+    pub ambient_occlusion_rays: u32,
+    /// Distance under which the rays of [`DebugMode::AmbientOcclusion`] are occluded.
+    ///
+    /// Surfaces further away do not darken the hit point.
+    pub ambient_occlusion_distance: f32,
+    /// The color seen by the rays missing the scene, when it has no environment map.
+    ///
+    /// As it is seen by every bounce, it also lights the scene.
+    pub background: Background,
+    /// The width and the height of the work groups of the shader, in pixels.
+    ///
+    /// The fastest size depends on the device, the default being 16 by 16.
+    /// Sizes that are null or exceed the limits of the device are replaced by the default.
+    pub work_group_size: [u32; 2],
+    /// The name of the compute entry point of the shader.
+    ///
+    /// It allows to switch between several kernels of the same shader module,
+    /// such as a fast preview kernel and a high quality one.
+    pub entry_point: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Represents the way primary rays are generated from the camera.
+pub enum Projection {
+    #[default]
+    /// Rays go through a plane in front of the camera, like a pinhole camera.
+    Perspective,
+    /// Rays cover the whole sphere around the camera, mapped to the image by longitude and latitude.
+    ///
+    /// The center of the image is the direction of the camera.
+    /// The image should have a 2:1 aspect ratio to avoid distortion,
+    /// and can be used as-is by VR viewers or as a sky dome.
+    Equirectangular,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Represents the operator mapping the rendered colors to the displayable range.
+///
+/// Without tonemapping, colors above 1.0, such as the ones of emissive materials, are clipped.
+pub enum ToneMap {
+    #[default]
+    /// Colors are clipped to the displayable range.
+    None,
+    /// Colors are compressed with the Reinhard operator, which preserves the dark tones.
+    Reinhard,
+    /// Colors are compressed with an approximation of the ACES filmic curve,
+    /// which gives more contrast and saturation than the Reinhard operator.
+    AcesFilmic,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Represents the filter used when sampling the diffuse textures.
+pub enum TextureFilter {
+    #[default]
+    /// Texels are blended bilinearly, which smooths magnified textures.
+    ///
+    /// The anisotropy level of the shader descriptor applies, which keeps textures sharp at grazing angles.
+    Linear,
+    /// The closest texel is used, which keeps the hard edges of pixel art.
+    ///
+    /// Anisotropic filtering is disabled.
+    Nearest,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Represents the way the random numbers of the path tracer are drawn.
+pub enum SamplerKind {
+    #[default]
+    /// Every random number is drawn independently, from a hash of the pixel and the sample.
+    WhiteNoise,
+    /// The random numbers of a sample follow the dimensions of a Sobol sequence,
+    /// indexed by the sample and scrambled per pixel.
+    ///
+    /// Samples are spread more evenly than with white noise, so images converge faster
+    /// at equal sample count. Only the first 16 random numbers of a sample follow the sequence,
+    /// which covers the primary ray and the first bounces, the next ones being white noise.
+    Sobol,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Represents what the shader renders, the scene or a debug view of it.
+///
+/// The debug views of the BVHs trace a single primary ray through the center of each pixel,
+/// with neither shading nor accumulation, so they show how the BVHs are built
+/// and make pathological splits stand out.
+pub enum DebugMode {
+    #[default]
+    /// The scene is path traced.
+    None,
+    /// Pixels are colored by the number of BVH nodes traversed by their primary ray,
+    /// from blue for none to red for 128 and more, through green.
+    ///
+    /// Both the nodes of the top-level BVH and of the BVHs of the models are counted.
+    BvhHeatmap,
+    /// Pixels are colored by the BVH leaf holding the triangle hit by their primary ray,
+    /// each leaf having its own color, so that the boxes of the leaves can be told apart.
+    BvhLeaves,
+    /// Pixels are shaded in grayscale by the ambient occlusion at the first hit of their primary ray,
+    /// from black for a fully occluded surface to white for an open one.
+    ///
+    /// Instead of bouncing, each sample casts [`ShaderDescriptor::ambient_occlusion_rays`] rays
+    /// distributed in the hemisphere around the normal, which is far cheaper than path tracing.
+    /// Unlike the views of the BVHs, the samples are accumulated, but neither tonemapped nor exposed.
+    AmbientOcclusion,
 }
 
-impl From<ShaderDescriptor> for source::ShaderConstants {
-    fn from(descriptor: ShaderDescriptor) -> Self {
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// Represents the color seen by the rays missing the scene.
+pub enum Background {
+    #[default]
+    /// A procedural sky, with a ground below the horizon.
+    Sky,
+    /// A uniform color, typically a neutral one for studio renders.
+    Color([f32; 3]),
+    /// A vertical gradient, blended linearly between the color seen when looking straight up
+    /// and the one seen when looking straight down.
+    Gradient {
+        /// The color seen when looking straight up.
+        top: [f32; 3],
+        /// The color seen when looking straight down.
+        bottom: [f32; 3],
+    },
+}
+
+impl Default for ShaderDescriptor {
+    fn default() -> Self {
+        Self {
+            max_bounces: 6,
+            samples: 10,
+            ray_epsilon: 1e-4,
+            intersection_epsilon: 1e-4,
+            near_plane: 0.0,
+            max_distance: f32::INFINITY,
+            auto_scale_precision: false,
+            anisotropy: 1,
+            projection: Projection::Perspective,
+            anti_aliasing: false,
+            tonemap: ToneMap::None,
+            exposure: 0.0,
+            white_point: [1.0; 3],
+            sampler: SamplerKind::WhiteNoise,
+            shadows: true,
+            seed: 0,
+            debug_mode: DebugMode::None,
+            ambient_occlusion_rays: 4,
+            ambient_occlusion_distance: 1.0,
+            background: Background::Sky,
+            work_group_size: DEFAULT_WORK_GROUP_SIZE,
+            entry_point: String::from("main"),
+        }
+    }
+}
+
+impl ShaderDescriptor {
+    #[must_use]
+    /// Returns the factors the colors are multiplied by before tonemapping,
+    /// combining the exposure and the white point, the last one being 1.
+    pub(crate) fn color_scale(&self) -> [f32; 4] {
+        let gain = self.exposure.exp2();
+        let [r, g, b] = self.white_point.map(|component| {
+            if component > 0.0 {
+                gain / component
+            } else {
+                gain
+            }
+        });
+        [r, g, b, 1.0]
+    }
+
+    #[must_use]
+    /// Returns the descriptor with its precision parameters derived from the diagonal
+    /// of the given scene bounds, if `auto_scale_precision` is enabled.
+    pub(crate) fn scaled_to_scene(self, (min_bound, max_bound): ([f32; 3], [f32; 3])) -> Self {
+        if !self.auto_scale_precision {
+            return self;
+        }
+
+        let [dx, dy, dz] = [
+            max_bound[0] - min_bound[0],
+            max_bound[1] - min_bound[1],
+            max_bound[2] - min_bound[2],
+        ];
+        let diagonal = dz.mul_add(dz, dx.mul_add(dx, dy * dy)).sqrt();
+
+        if !diagonal.is_normal() {
+            tracing::warn!("Scene is empty or degenerate, precision parameters left untouched");
+            return self;
+        }
+
+        let scaled = Self {
+            ray_epsilon: diagonal * 1e-5,
+            near_plane: diagonal * 1e-4,
+            max_distance: diagonal * 10.0,
+            ..self
+        };
+        tracing::debug!(
+            "Precision parameters scaled to scene diagonal {diagonal}: epsilon {}, near {}, max distance {}",
+            scaled.ray_epsilon,
+            scaled.near_plane,
+            scaled.max_distance
+        );
+
+        scaled
+    }
+}
+
+impl Background {
+    #[must_use]
+    /// Returns the mode of the background in the shader, and its top and bottom colors.
+    const fn to_constants(self) -> (u32, [f32; 4], [f32; 4]) {
+        match self {
+            Self::Sky => (0, [0.0; 4], [0.0; 4]),
+            Self::Color([r, g, b]) => (1, [r, g, b, 1.0], [r, g, b, 1.0]),
+            Self::Gradient { top, bottom } => (
+                1,
+                [top[0], top[1], top[2], 1.0],
+                [bottom[0], bottom[1], bottom[2], 1.0],
+            ),
+        }
+    }
+}
+
+impl From<&ShaderDescriptor> for source::ShaderConstants {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from(descriptor: &ShaderDescriptor) -> Self {
+        let (background_mode, background_top, background_bottom) =
+            descriptor.background.to_constants();
+
         Self {
             max_bounce_count: u32::from(descriptor.max_bounces),
-            nb_samples: u32::from(descriptor.samples),
+            nb_samples: u32::from(descriptor.samples.max(1)),
+            ray_epsilon: descriptor.ray_epsilon,
+            near_plane: descriptor.near_plane,
+            max_distance: descriptor.max_distance,
+            projection: match descriptor.projection {
+                Projection::Perspective => 0,
+                Projection::Equirectangular => 1,
+            },
+            intersection_epsilon: descriptor.intersection_epsilon,
+            region_x: 0,
+            region_y: 0,
+            region_width: u32::MAX,
+            region_height: u32::MAX,
+            use_environment_map: 0,
+            write_auxiliary_outputs: 0,
+            background_mode,
+            anti_aliasing: u32::from(descriptor.anti_aliasing),
+            tonemap: match descriptor.tonemap {
+                ToneMap::None => 0,
+                ToneMap::Reinhard => 1,
+                ToneMap::AcesFilmic => 2,
+            },
+            sampler: match descriptor.sampler {
+                SamplerKind::WhiteNoise => 0,
+                SamplerKind::Sobol => 1,
+            },
+            shadows: u32::from(descriptor.shadows),
+            seed_low: descriptor.seed as u32,
+            seed_high: (descriptor.seed >> 32) as u32,
+            debug_mode: match descriptor.debug_mode {
+                DebugMode::None => 0,
+                DebugMode::BvhHeatmap => 1,
+                DebugMode::BvhLeaves => 2,
+                DebugMode::AmbientOcclusion => 3,
+            },
+            ambient_occlusion_rays: descriptor.ambient_occlusion_rays.max(1),
+            ambient_occlusion_distance: descriptor.ambient_occlusion_distance,
+            background_top,
+            background_bottom,
         }
     }
 }
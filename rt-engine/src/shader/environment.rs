@@ -0,0 +1,234 @@
+use std::{path::Path, sync::Arc};
+
+use vulkano::{
+    buffer::{BufferUsage, Subbuffer},
+    command_buffer::allocator::StandardCommandBufferAllocator,
+    device::Queue,
+    image::view::ImageView,
+    memory::allocator::StandardMemoryAllocator,
+};
+
+/// The module containing the Radiance `.hdr` decoder.
+mod hdr;
+
+#[must_use]
+/// Loads the equirectangular environment map at the given path and sends it to the device,
+/// returning a view of the sampled image, the distribution used to importance sample it and the send future.
+///
+/// The map must be a Radiance `.hdr` image, whose top row is the direction +Y.
+/// A map that does not emit any light has no distribution, see [`EnvironmentCdf::new`].
+///
+/// Returns `None` if the map cannot be loaded, the cause being logged.
+///
+/// ## Panics
+///
+/// This function panics if the map cannot be sent to the device.
+pub fn load(
+    path: &Path,
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+    queue: &Arc<Queue>,
+) -> Option<(
+    Arc<ImageView>,
+    Option<EnvironmentCdf>,
+    crate::buffer::SendBufferFuture,
+)> {
+    let start = std::time::Instant::now();
+
+    let (width, height, texels) = std::fs::File::open(path)
+        .and_then(|file| hdr::decode(std::io::BufReader::new(file)))
+        .map_err(|error| {
+            tracing::error!("Failed to load environment map {}: {error}", path.display());
+        })
+        .ok()?;
+
+    let max_dimension = queue
+        .device()
+        .physical_device()
+        .properties()
+        .max_image_dimension2_d;
+    if width == 0 || height == 0 || width > max_dimension || height > max_dimension {
+        tracing::error!(
+            "Environment map {} has an unsupported size of {width}x{height}",
+            path.display()
+        );
+        return None;
+    }
+
+    let cdf = EnvironmentCdf::new(width, height, &texels);
+
+    let texels = texels
+        .into_iter()
+        .map(|[r, g, b]| [r, g, b, 1.0])
+        .collect::<Vec<_>>();
+    let (image, future) = crate::buffer::send_image_to_device(
+        memory_allocator,
+        command_buffer_allocator,
+        queue,
+        vulkano::format::Format::R32G32B32A32_SFLOAT,
+        [width, height, 1],
+        &texels,
+    )
+    .unwrap();
+
+    tracing::trace!(
+        "Environment map of {width}x{height} loaded in {:?}",
+        start.elapsed()
+    );
+
+    Some((ImageView::new_default(image).unwrap(), cdf, future))
+}
+
+/// Sends the given distribution of the environment map to the device, returning its buffer and the send future.
+///
+/// Without distribution, a buffer holding a single entry is sent instead,
+/// which the shader takes as a map to be sampled uniformly.
+///
+/// ## Panics
+///
+/// This function panics if the buffer cannot be sent.
+pub fn send_cdf(
+    cdf: Option<&EnvironmentCdf>,
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+    queue: &Arc<Queue>,
+) -> (
+    Subbuffer<crate::shader::EnvironmentCdfBuffer>,
+    crate::buffer::SendBufferFuture,
+) {
+    // Buffers cannot be empty, and a distribution always has more than one entry
+    let len = cdf.map_or(1, EnvironmentCdf::buffer_len);
+
+    crate::buffer::send_to_device(
+        memory_allocator,
+        command_buffer_allocator,
+        queue,
+        len as u64,
+        BufferUsage::STORAGE_BUFFER,
+        |data: &mut crate::shader::EnvironmentCdfBuffer| match cdf {
+            Some(cdf) => cdf.write_to(&mut data.environment_cdf),
+            None => data.environment_cdf[0] = 0.0,
+        },
+    )
+    .unwrap()
+}
+
+#[derive(Debug, Clone)]
+/// A tabulated 2D distribution over the luminance of an equirectangular environment map,
+/// used to importance sample directions toward its bright regions.
+///
+/// It is made of a marginal CDF over the rows of the map,
+/// and of a conditional CDF over the columns of each row.
+/// Every CDF starts at 0 and ends at 1, so it has one more entry than the dimension it covers.
+pub struct EnvironmentCdf {
+    /// The marginal CDF over the rows, with `height + 1` entries.
+    marginal: Vec<f32>,
+    /// The conditional CDFs over the columns of each row, with `width + 1` entries per row.
+    conditional: Vec<f32>,
+}
+
+impl EnvironmentCdf {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    /// Builds the distribution of the given equirectangular map,
+    /// whose linear RGB texels are given row by row, starting from the top.
+    ///
+    /// Returns `None` if the map does not emit any light, in which case it should be sampled uniformly.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the number of texels does not match the size of the map.
+    pub fn new(width: u32, height: u32, texels: &[[f32; 3]]) -> Option<Self> {
+        assert_eq!(
+            texels.len(),
+            width as usize * height as usize,
+            "texels must contain width * height elements"
+        );
+
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let mut conditional = Vec::with_capacity(height as usize * (width as usize + 1));
+        let row_weights = texels
+            .chunks_exact(width as usize)
+            .enumerate()
+            .map(|(y, row)| {
+                // Rows close to the poles cover a smaller solid angle
+                let sin_theta = (std::f32::consts::PI * (y as f32 + 0.5) / height as f32).sin();
+                Self::push_cdf(
+                    &mut conditional,
+                    row.iter().map(|&texel| luminance(texel) * sin_theta),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut marginal = Vec::with_capacity(height as usize + 1);
+        let integral = Self::push_cdf(&mut marginal, row_weights.into_iter());
+
+        if !(integral.is_finite() && integral > 0.0) {
+            tracing::debug!("Environment map is black, importance sampling disabled");
+            return None;
+        }
+
+        Some(Self {
+            marginal,
+            conditional,
+        })
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    /// Appends the normalized CDF of the given weights to `cdf`, returning the sum of the weights.
+    ///
+    /// Negative and NaN weights are ignored. If all the weights are zero, the appended CDF is uniform.
+    fn push_cdf(cdf: &mut Vec<f32>, weights: impl ExactSizeIterator<Item = f32>) -> f32 {
+        let count = weights.len();
+        let start = cdf.len();
+
+        cdf.push(0.0);
+        let mut sum = 0.0;
+        for weight in weights {
+            sum += weight.max(0.0);
+            cdf.push(sum);
+        }
+
+        let entries = &mut cdf[start..];
+        if sum > 0.0 {
+            for entry in entries {
+                *entry /= sum;
+            }
+        } else {
+            for (i, entry) in entries.iter_mut().enumerate() {
+                *entry = i as f32 / count as f32;
+            }
+        }
+
+        sum
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the number of floats needed to store the distribution in a buffer.
+    pub const fn buffer_len(&self) -> usize {
+        self.marginal.len() + self.conditional.len()
+    }
+
+    /// Writes the distribution to the given buffer,
+    /// the marginal CDF first and then the conditional CDFs row by row.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the buffer is not exactly [`EnvironmentCdf::buffer_len`] long.
+    pub fn write_to(&self, buffer: &mut [f32]) {
+        let (marginal, conditional) = buffer.split_at_mut(self.marginal.len());
+        marginal.copy_from_slice(&self.marginal);
+        conditional.copy_from_slice(&self.conditional);
+    }
+}
+
+#[must_use]
+#[inline]
+/// Returns the relative luminance of a linear RGB color.
+fn luminance([r, g, b]: [f32; 3]) -> f32 {
+    0.0722_f32.mul_add(b, 0.2126_f32.mul_add(r, 0.7152 * g))
+}
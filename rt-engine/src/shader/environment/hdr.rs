@@ -0,0 +1,143 @@
+use std::io::{self, BufRead, Read};
+
+/// Decodes a Radiance `.hdr` image, returning its size and its linear RGB texels
+/// row by row, starting from the top.
+///
+/// Only the standard `-Y height +X width` orientation is supported,
+/// with flat or run-length encoded scanlines. Old-style run-length encoding is not supported.
+///
+/// ## Errors
+///
+/// This function returns an error if the image cannot be read or is not a supported Radiance image.
+pub(super) fn decode(mut reader: impl BufRead) -> io::Result<(u32, u32, Vec<[f32; 3]>)> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if !line.starts_with("#?") {
+        return Err(invalid_data("missing Radiance signature"));
+    }
+
+    // The header ends with an empty line
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_data("unexpected end of header"));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid_data("unsupported pixel format"));
+            }
+        }
+    }
+
+    line.clear();
+    reader.read_line(&mut line)?;
+    let ["-Y", height, "+X", width] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(invalid_data("unsupported resolution or orientation"));
+    };
+    let (Ok(width), Ok(height)) = (width.parse::<u32>(), height.parse::<u32>()) else {
+        return Err(invalid_data("invalid resolution"));
+    };
+
+    let mut texels = Vec::with_capacity(width as usize * height as usize);
+    let mut scanline = vec![[0; 4]; width as usize];
+    for _ in 0..height {
+        read_scanline(&mut reader, &mut scanline)?;
+        texels.extend(scanline.iter().map(|&texel| rgbe_to_rgb(texel)));
+    }
+
+    Ok((width, height, texels))
+}
+
+/// Reads a scanline of RGBE texels, either flat or run-length encoded.
+///
+/// ## Errors
+///
+/// This function returns an error if the scanline cannot be read or its runs are invalid.
+fn read_scanline(reader: &mut impl Read, scanline: &mut [[u8; 4]]) -> io::Result<()> {
+    let width = scanline.len();
+    if width == 0 {
+        return Ok(());
+    }
+
+    let mut header = [0; 4];
+    reader.read_exact(&mut header)?;
+
+    // Only scanlines of 8 to 32767 texels can be run-length encoded
+    let is_encoded = (8..0x8000).contains(&width)
+        && header[0] == 2
+        && header[1] == 2
+        && usize::from(u16::from_be_bytes([header[2], header[3]])) == width;
+    if !is_encoded {
+        // The header was the first texel of a flat scanline
+        scanline[0] = header;
+        for texel in &mut scanline[1..] {
+            reader.read_exact(texel)?;
+        }
+        return Ok(());
+    }
+
+    // Each channel is encoded separately, as a sequence of runs and literals
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = read_byte(reader)?;
+            let (length, is_run) = if count > 128 {
+                (usize::from(count - 128), true)
+            } else {
+                (usize::from(count), false)
+            };
+            let Some(texels) = scanline.get_mut(x..x + length).filter(|_| length > 0) else {
+                return Err(invalid_data("invalid run length"));
+            };
+
+            if is_run {
+                let value = read_byte(reader)?;
+                for texel in texels {
+                    texel[channel] = value;
+                }
+            } else {
+                for texel in texels {
+                    texel[channel] = read_byte(reader)?;
+                }
+            }
+            x += length;
+        }
+    }
+
+    Ok(())
+}
+
+#[inline]
+/// Reads a single byte.
+///
+/// ## Errors
+///
+/// This function returns an error if the byte cannot be read.
+fn read_byte(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+#[must_use]
+/// Converts an RGBE texel, made of three mantissas sharing an exponent, to linear RGB.
+fn rgbe_to_rgb([r, g, b, exponent]: [u8; 4]) -> [f32; 3] {
+    if exponent == 0 {
+        return [0.0; 3];
+    }
+
+    // The mantissas are fixed-point values in [0, 1), offset by half a step
+    let scale = 2_f32.powi(i32::from(exponent) - 136);
+    [r, g, b].map(|mantissa| (f32::from(mantissa) + 0.5) * scale)
+}
+
+#[must_use]
+#[inline]
+/// Returns an error for a file that is not a supported Radiance image.
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
@@ -0,0 +1,207 @@
+use crate::shader::source::Material;
+
+impl Material {
+    #[must_use]
+    #[inline]
+    /// A perfectly diffuse, non-reflective material.
+    pub const fn matte(color: [f32; 3]) -> Self {
+        Self {
+            color,
+            emission_strength: 0.0,
+            albedo: 0.9,
+            smoothness: 0.0,
+            opacity: 1.0,
+            texture_index: -1,
+            transmission: 0.0,
+            ior: 1.5,
+            alpha_cutout: 0.0,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// A mostly diffuse material with a faint glossy reflection.
+    pub const fn rough_plastic(color: [f32; 3]) -> Self {
+        Self {
+            color,
+            emission_strength: 0.0,
+            albedo: 0.8,
+            smoothness: 0.3,
+            opacity: 1.0,
+            texture_index: -1,
+            transmission: 0.0,
+            ior: 1.5,
+            alpha_cutout: 0.0,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// A shiny metal, tinted by its color.
+    pub const fn polished_metal(color: [f32; 3]) -> Self {
+        Self {
+            color,
+            emission_strength: 0.0,
+            albedo: 0.95,
+            smoothness: 0.9,
+            opacity: 1.0,
+            texture_index: -1,
+            transmission: 0.0,
+            ior: 1.5,
+            alpha_cutout: 0.0,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// A perfect, untinted mirror.
+    pub const fn mirror() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            emission_strength: 0.0,
+            albedo: 1.0,
+            smoothness: 1.0,
+            opacity: 1.0,
+            texture_index: -1,
+            transmission: 0.0,
+            ior: 1.5,
+            alpha_cutout: 0.0,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// A light emitting material.
+    ///
+    /// `strength` is not bounded, values above 1.0 are brighter than the sky.
+    pub const fn emissive(color: [f32; 3], strength: f32) -> Self {
+        Self {
+            color,
+            emission_strength: strength,
+            albedo: 1.0,
+            smoothness: 0.0,
+            opacity: 1.0,
+            texture_index: -1,
+            transmission: 0.0,
+            ior: 1.5,
+            alpha_cutout: 0.0,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// A clear dielectric, reflecting and refracting the rays according to its index of refraction.
+    ///
+    /// The color tints the light going through it, white giving a colorless material.
+    pub const fn dielectric(color: [f32; 3], ior: f32) -> Self {
+        Self {
+            color,
+            emission_strength: 0.0,
+            albedo: 1.0,
+            smoothness: 1.0,
+            opacity: 1.0,
+            texture_index: -1,
+            transmission: 1.0,
+            ior,
+            alpha_cutout: 0.0,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// A colorless glass.
+    pub const fn glass() -> Self {
+        Self::dielectric([1.0, 1.0, 1.0], 1.5)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Clear water.
+    pub const fn water() -> Self {
+        Self::dielectric([1.0, 1.0, 1.0], 1.33)
+    }
+
+    #[must_use]
+    #[inline]
+    /// A white light emitting material.
+    pub const fn emissive_white() -> Self {
+        Self::emissive([1.0, 1.0, 1.0], 5.0)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the material with the given opacity, from 0 (invisible) to 1 (opaque).
+    ///
+    /// Rays pass through the surface with a probability of `1 - opacity`,
+    /// which makes it look semi-transparent once the samples are accumulated.
+    pub const fn with_opacity(self, opacity: f32) -> Self {
+        Self { opacity, ..self }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the material cut out where the alpha of its texture is below the given threshold,
+    /// such as the leaves of foliage or the gaps of fences.
+    ///
+    /// Rays go through the cut out parts as if they missed the surface, including shadow rays,
+    /// without using up a bounce. A threshold of 0, the default, disables cutting out,
+    /// as does a material without texture.
+    pub const fn with_alpha_cutout(self, alpha_cutout: f32) -> Self {
+        Self {
+            alpha_cutout,
+            ..self
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the material with its color multiplied by the texture at the given index
+    /// in the textures of the scene, or without texture if the index is negative.
+    ///
+    /// See [`crate::shader::SceneDescriptor::textures`].
+    pub const fn with_texture(self, texture_index: i32) -> Self {
+        Self {
+            texture_index,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Every preset has its components in range, and an index of refraction the shader can refract with.
+    fn presets_are_valid() {
+        let color = [0.8, 0.2, 0.4];
+        for (name, material) in [
+            ("matte", Material::matte(color)),
+            ("rough_plastic", Material::rough_plastic(color)),
+            ("polished_metal", Material::polished_metal(color)),
+            ("mirror", Material::mirror()),
+            ("emissive", Material::emissive(color, 2.0)),
+            ("emissive_white", Material::emissive_white()),
+            ("dielectric", Material::dielectric(color, 2.4)),
+            ("glass", Material::glass()),
+            ("water", Material::water()),
+        ] {
+            let unit = 0.0..=1.0;
+            assert!(
+                material
+                    .color
+                    .iter()
+                    .all(|component| unit.contains(component)),
+                "{name}"
+            );
+            assert!(unit.contains(&material.albedo), "{name}");
+            assert!(unit.contains(&material.smoothness), "{name}");
+            assert!(unit.contains(&material.opacity), "{name}");
+            assert!(unit.contains(&material.transmission), "{name}");
+            assert!(unit.contains(&material.alpha_cutout), "{name}");
+            assert!(material.emission_strength >= 0.0, "{name}");
+            assert!(material.ior >= 1.0, "{name}");
+            assert_eq!(material.texture_index, -1, "{name}");
+        }
+    }
+}
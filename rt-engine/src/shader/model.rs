@@ -1,17 +1,27 @@
 use std::sync::Arc;
 
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use vulkano::{
     buffer::{BufferUsage, Subbuffer},
     command_buffer::allocator::StandardCommandBufferAllocator,
     device::Queue,
+    image::view::ImageView,
     memory::allocator::StandardMemoryAllocator,
-    sync::GpuFuture,
+    padded::Padded,
+    sync::{future::FenceSignalFuture, GpuFuture},
 };
 
 /// The module containing the BVH construction implementation.
 mod bvh;
+/// The module containing the on-disk cache of the triangles and BVHs of the models.
+mod cache;
+#[cfg(feature = "gltf")]
+/// The module containing the glTF model loading implementation.
+mod gltf_import;
 /// The module containing the model loading implementation.
 mod load;
+/// The module containing the placement of the models in the scene.
+mod placement;
 
 #[derive(Clone)]
 #[allow(clippy::module_name_repetitions)]
@@ -25,42 +35,369 @@ pub struct LoadedModels {
     pub models_buffer: Subbuffer<crate::shader::ModelsBuffer>,
     /// The buffer containing the BVHs of the models.
     pub bvhs_buffer: Subbuffer<crate::shader::BvhBuffer>,
+    /// The buffer containing the top-level BVH over the models, whose leaves reference them.
+    pub tlas_buffer: Subbuffer<crate::shader::TlasBuffer>,
+    /// The buffer containing the lights of the scene.
+    pub lights_buffer: Subbuffer<crate::shader::LightsBuffer>,
+    /// The buffer containing the emissive triangles of the models, sampled by the shader as area lights.
+    pub emissive_triangles_buffer: Subbuffer<crate::shader::EmissiveTrianglesBuffer>,
+    /// The environment map of the scene, if any.
+    pub environment_map: Option<Arc<ImageView>>,
+    /// The buffer containing the distribution used to importance sample the environment map,
+    /// a single entry if the map is sampled uniformly.
+    pub environment_cdf_buffer: Subbuffer<crate::shader::EnvironmentCdfBuffer>,
+    /// The diffuse textures of the scene, as the layers of an array, if any.
+    pub textures: Option<Arc<ImageView>>,
+    /// The filter used when sampling the textures of the scene.
+    pub texture_filter: super::TextureFilter,
+    /// Statistics about the loading of each model, in the order of the scene descriptor.
+    pub load_stats: Vec<ModelLoadStats>,
+    /// The models of the scene, as they are in the models buffer.
+    models: Vec<Padded<crate::shader::source::Model, 12>>,
+    /// The bounding box of each model, in the space of its triangles.
+    model_bounds: Vec<([f32; 3], [f32; 3])>,
+    /// The number of materials of the scene.
+    material_count: usize,
+    /// The power emitted per unit of area by each material, 0 for materials that do not emit light.
+    material_powers: Vec<f32>,
+    /// The emissive triangles of the models, as they are in the emissive triangles buffer.
+    emissive_triangles: Vec<crate::shader::source::EmissiveTriangle>,
+    /// The winding of the models of the scene, also used for the models added later.
+    winding: super::Winding,
+    /// The axis-aligned bounding box of the whole scene.
+    bounds: ([f32; 3], [f32; 3]),
+    /// The upload of the buffers of the scene to the device, until it is known to be complete.
+    upload: Option<Arc<FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>>>,
+    #[cfg(feature = "cpu-reference")]
+    /// The triangles of the models, as they are in the triangles buffer, for the reference path tracer.
+    triangles: Vec<Padded<crate::shader::source::Triangle, 4>>,
+    #[cfg(feature = "cpu-reference")]
+    /// The BVHs of the models, as they are in the BVH buffer, for the reference path tracer.
+    bvhs: Vec<Padded<crate::shader::source::Bvh, 4>>,
+    #[cfg(feature = "cpu-reference")]
+    /// The materials of the scene, for the reference path tracer.
+    materials: Vec<crate::shader::source::Material>,
+    #[cfg(feature = "cpu-reference")]
+    /// The lights of the scene, for the reference path tracer.
+    lights: Vec<super::Light>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+/// Represents the reason a model could not be loaded.
+pub enum ModelLoadError {
+    /// The file does not exist or cannot be opened.
+    NotFound,
+    /// The file is not a valid model, the parser giving the cause.
+    Parse(String),
+    /// A face references a vertex without the given attribute, which cannot be derived from the others.
+    MissingAttribute(&'static str),
+    /// Loading the file requires the given feature of the engine.
+    MissingFeature(&'static str),
+}
+
+impl std::fmt::Display for ModelLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("file not found"),
+            Self::Parse(cause) => write!(f, "invalid file: {cause}"),
+            Self::MissingAttribute(attribute) => {
+                write!(f, "a face references a vertex without {attribute}")
+            }
+            Self::MissingFeature(feature) => write!(f, "the `{feature}` feature is required"),
+        }
+    }
+}
+
+impl std::error::Error for ModelLoadError {}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+/// Statistics about the loading of a model.
+pub struct ModelLoadStats {
+    /// The path the model was loaded from.
+    pub path: String,
+    /// The time spent reading the file and building the triangles.
+    pub parse_time: std::time::Duration,
+    /// The time spent building the BVH of the model.
+    pub bvh_build_time: std::time::Duration,
+    /// The number of triangles of the model.
+    pub triangle_count: usize,
+    /// The number of nodes of the BVH of the model.
+    pub bvh_node_count: usize,
+}
+
+/// The models of a scene loaded on the host, with their triangles and BVHs concatenated in order.
+struct Geometry {
+    /// The models, in the order of their paths.
+    models: Vec<crate::shader::source::Model>,
+    /// Statistics about the loading of each model.
+    load_stats: Vec<ModelLoadStats>,
+    /// The triangles of all the models.
+    triangles: Vec<Padded<crate::shader::source::Triangle, 4>>,
+    /// The BVH nodes of all the models.
+    bvhs: Vec<Padded<crate::shader::source::Bvh, 4>>,
+    /// The range of the triangles of each model in `triangles`.
+    triangle_ranges: Vec<std::ops::Range<usize>>,
 }
 
 impl LoadedModels {
-    #[must_use]
-    /// Load the models from the given paths and positions.
+    /// Load the models from the given paths and transforms, waiting for their buffers to be uploaded.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the same errors as [`LoadedModels::load_async`].
     ///
     /// ## Panics
     ///
-    /// This function will panic if one of the models cannot be loaded,
-    /// or if the given positions and paths do not have the same length.
+    /// This function panics in the same cases as [`LoadedModels::load_async`], or if the upload fails.
     pub fn load(
         memory_allocator: &Arc<StandardMemoryAllocator>,
         command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
         queue: &Arc<Queue>,
         scene_descriptor: &super::SceneDescriptor,
-    ) -> Self {
+    ) -> Result<Self, crate::InitError> {
+        let mut scene = Self::load_async(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            scene_descriptor,
+        )?;
+        scene.wait_for_upload();
+        Ok(scene)
+    }
+
+    /// Load the models from the given paths and transforms, without waiting for their buffers to be uploaded.
+    ///
+    /// The models are read and their BVHs built before returning, but the buffers are sent on the given queue
+    /// in the background. They must not be used by the GPU before [`LoadedModels::is_uploaded`] returns `true`
+    /// or [`LoadedModels::wait_for_upload`] returns, which is left to the caller.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`crate::InitError::ModelLoad`] if one of the models cannot be loaded,
+    /// [`crate::InitError::MaterialOutOfRange`] if a model references a material that does not exist,
+    /// [`crate::InitError::InstanceOutOfRange`] if an instance references a model that does not exist,
+    /// [`crate::InitError::TextureOutOfRange`] if a material references a texture that does not exist,
+    /// [`crate::InitError::EnvironmentLoad`] if the environment map cannot be loaded,
+    /// or [`crate::InitError::TextureLoad`] if one of the textures cannot be loaded.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the given transforms and paths do not have the same length,
+    /// or if material indices are given but not for every model.
+    pub fn load_async(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+        queue: &Arc<Queue>,
+        scene_descriptor: &super::SceneDescriptor,
+    ) -> Result<Self, crate::InitError> {
+        Self::load_async_within(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            scene_descriptor,
+            u64::MAX,
+        )
+        .map(|scene| scene.expect("every scene fits in an unlimited memory budget"))
+    }
+
+    /// Same as [`LoadedModels::load_async`], but returns `None` without allocating anything on the device
+    /// if the estimated size of the geometry of the scene exceeds `memory_budget` bytes.
+    ///
+    /// The estimate is made from the load statistics of the models, once they are read on the host.
+    /// The environment map and the textures are not part of it, as their size is only known once loaded.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the same errors as [`LoadedModels::load_async`].
+    ///
+    /// ## Panics
+    ///
+    /// This function panics in the same cases as [`LoadedModels::load_async`].
+    pub(crate) fn load_async_within(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+        queue: &Arc<Queue>,
+        scene_descriptor: &super::SceneDescriptor,
+        memory_budget: u64,
+    ) -> Result<Option<Self>, crate::InitError> {
         let super::SceneDescriptor {
             model_paths,
-            positions,
+            transforms,
+            instances,
+            materials,
+            material_indices,
+            lights,
+            environment,
+            textures,
+            texture_filter,
+            winding,
         } = scene_descriptor;
 
         assert_eq!(
             model_paths.len(),
-            positions.len(),
-            "model_paths and positions must have the same length"
+            transforms.len(),
+            "model_paths and transforms must have the same length"
+        );
+        assert!(
+            material_indices.is_empty() || material_indices.len() == model_paths.len(),
+            "material_indices must be empty or have the same length as model_paths"
         );
 
-        let mut triangles = Vec::new();
-        let mut bvhs = Vec::new();
-        let models = model_paths
+        if let Some((index, instance)) = instances
             .iter()
-            .zip(positions)
-            .map(|(path, position)| {
-                crate::shader::source::Model::load(&mut triangles, &mut bvhs, path, position)
+            .enumerate()
+            .find(|(_, instance)| instance.model >= model_paths.len())
+        {
+            return Err(crate::InitError::InstanceOutOfRange(index, instance.model));
+        }
+
+        if let Some((index, material)) = materials.iter().enumerate().find(|(_, material)| {
+            usize::try_from(material.texture_index).is_ok_and(|texture| texture >= textures.len())
+        }) {
+            return Err(crate::InitError::TextureOutOfRange(
+                index,
+                material.texture_index,
+            ));
+        }
+
+        // Without materials in the scene, the ones of the model files follow a default one,
+        // which is used by the models and faces without any, and their textures follow the ones of the scene
+        let file_materials = if materials.is_empty() {
+            model_paths
+                .par_iter()
+                .map(|path| crate::shader::source::Model::load_materials(path))
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        let mut textures = textures.clone();
+        let materials = if materials.is_empty() {
+            let mut merged = vec![crate::shader::source::Material {
+                color: [0.8, 0.6, 0.6],
+                albedo: 1.0,
+                smoothness: 0.98,
+                emission_strength: 0.0,
+                opacity: 1.0,
+                texture_index: -1,
+                transmission: 0.0,
+                ior: 1.5,
+                alpha_cutout: 0.0,
+            }];
+            for model_materials in &file_materials {
+                merged.extend(model_materials.iter().map(|(material, texture)| {
+                    let Some(texture) = texture else {
+                        return *material;
+                    };
+                    let index = textures
+                        .iter()
+                        .position(|path| path == texture)
+                        .unwrap_or_else(|| {
+                            textures.push(texture.clone());
+                            textures.len() - 1
+                        });
+                    material.with_texture(i32::try_from(index).expect("too many textures"))
+                }));
+            }
+            merged
+        } else {
+            materials.clone()
+        };
+
+        // The index of the first material of each model, and of the one of its faces without material
+        let mut file_material_offset = 1;
+        let material_ids = model_paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let ids = match file_materials.get(i) {
+                    Some(model_materials) if !model_materials.is_empty() => {
+                        let material_id = u32::try_from(file_material_offset).unwrap_or(u32::MAX);
+                        file_material_offset += model_materials.len();
+                        (material_id, 0)
+                    }
+                    Some(_) => (0, 0),
+                    None => {
+                        let material_id = material_indices.get(i).copied().unwrap_or(0);
+                        (material_id, material_id)
+                    }
+                };
+                if ids.0 as usize >= materials.len() {
+                    return Err(crate::InitError::MaterialOutOfRange(path.into(), ids.0));
+                }
+                Ok(ids)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Geometry {
+            models,
+            load_stats,
+            triangles,
+            bvhs,
+            triangle_ranges,
+        } = Self::load_geometry(
+            model_paths,
+            transforms,
+            &material_ids,
+            *winding,
+            materials.len(),
+        )?;
+
+        let estimated_size =
+            Self::estimated_size(&load_stats, models.len() + instances.len(), materials.len());
+        if estimated_size > memory_budget {
+            tracing::debug!(
+                "Scene estimated at {estimated_size} bytes, over the budget of {memory_budget} bytes"
+            );
+            return Ok(None);
+        }
+
+        let mut models = models.into_iter().map(Padded::from).collect::<Vec<_>>();
+        // Instances reference the BVH of their model, only their transform differs
+        let instance_models = instances
+            .iter()
+            .map(|instance| Padded::from(models[instance.model].placed(&instance.transform)))
+            .collect::<Vec<_>>();
+        models.extend(instance_models);
+        let model_bounds = models
+            .iter()
+            .map(|model| {
+                let root = &bvhs[model.bvh_index as usize];
+                (*root.min_bound, root.max_bound)
             })
             .collect::<Vec<_>>();
+        let bounds = Self::compute_bounds(&models, &model_bounds);
+        let tlas = Self::build_tlas(&models, &model_bounds);
+
+        // Instances emit the light of the triangles of their model
+        let material_powers = materials
+            .iter()
+            .map(Self::material_power)
+            .collect::<Vec<_>>();
+        let mut emissive_triangles = Vec::new();
+        let model_triangle_ranges = triangle_ranges.iter().cloned().chain(
+            instances
+                .iter()
+                .map(|instance| triangle_ranges[instance.model].clone()),
+        );
+        for (model_index, range) in model_triangle_ranges.enumerate() {
+            Self::push_emissive_triangles(
+                &mut emissive_triangles,
+                model_index,
+                &models[model_index],
+                &triangles[range.clone()],
+                range.start,
+                &material_powers,
+            );
+        }
+        if !emissive_triangles.is_empty() {
+            tracing::debug!(
+                "{} emissive triangles sampled as lights",
+                emissive_triangles.len()
+            );
+        }
 
         let (triangles_buffer, triangles_future) = {
             use crate::shader::TrianglesBuffer;
@@ -77,15 +414,7 @@ impl LoadedModels {
         };
 
         let (materials_buffer, material_future) = {
-            use crate::shader::source::{Material, Materials};
-
-            let materials = [Material {
-                color: [0.8, 0.6, 0.6],
-                albedo: 1.0,
-                smoothness: 0.98,
-                emission_strength: 0.0,
-            }
-            .into()];
+            use crate::shader::source::Materials;
 
             crate::buffer::send_to_device(
                 memory_allocator,
@@ -93,7 +422,13 @@ impl LoadedModels {
                 queue,
                 materials.len() as u64,
                 BufferUsage::STORAGE_BUFFER,
-                |data: &mut Materials| data.materials.copy_from_slice(&materials),
+                |data: &mut Materials| {
+                    let materials = materials
+                        .iter()
+                        .map(|&material| material.into())
+                        .collect::<Vec<_>>();
+                    data.materials.copy_from_slice(&materials);
+                },
             )
             .unwrap()
         };
@@ -126,19 +461,863 @@ impl LoadedModels {
             .unwrap()
         };
 
-        triangles_future
+        let (tlas_buffer, tlas_future) =
+            Self::send_tlas(memory_allocator, command_buffer_allocator, queue, &tlas);
+
+        let (lights_buffer, lights_future) = {
+            use crate::shader::LightsBuffer;
+
+            // Buffers cannot be empty, a light without intensity is ignored by the shader
+            let lights = if lights.is_empty() {
+                vec![crate::shader::source::Light {
+                    vector: [0.0, -1.0, 0.0],
+                    kind: 0,
+                    color: [0.0; 3],
+                    intensity: 0.0,
+                    radius: 0.0,
+                }
+                .into()]
+            } else {
+                lights
+                    .iter()
+                    .map(|&light| crate::shader::source::Light::from(light).into())
+                    .collect::<Vec<_>>()
+            };
+
+            crate::buffer::send_to_device(
+                memory_allocator,
+                command_buffer_allocator,
+                queue,
+                lights.len() as u64,
+                BufferUsage::STORAGE_BUFFER,
+                |data: &mut LightsBuffer| data.lights.copy_from_slice(&lights),
+            )
+            .unwrap()
+        };
+
+        let (emissive_triangles_buffer, emissive_triangles_future) = Self::send_emissive_triangles(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            &emissive_triangles,
+        );
+
+        let (environment_map, environment_cdf, environment_future) = match environment {
+            Some(path) => {
+                let (map, cdf, future) = super::environment::load(
+                    path,
+                    memory_allocator,
+                    command_buffer_allocator,
+                    queue,
+                )
+                .ok_or_else(|| crate::InitError::EnvironmentLoad(path.clone()))?;
+                (Some(map), cdf, Some(future))
+            }
+            None => (None, None, None),
+        };
+        let (environment_cdf_buffer, environment_cdf_future) = super::environment::send_cdf(
+            environment_cdf.as_ref(),
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+        );
+
+        let (textures, textures_future) = if textures.is_empty() {
+            (None, None)
+        } else {
+            let (array, future) = super::texture::load_array(
+                &textures,
+                memory_allocator,
+                command_buffer_allocator,
+                queue,
+            )
+            .map_err(crate::InitError::TextureLoad)?;
+            (Some(array), Some(future))
+        };
+
+        let mut upload = triangles_future
             .join(material_future)
             .join(models_future)
             .join(bvh_future)
-            .then_signal_fence()
-            .wait(None)
-            .unwrap();
+            .join(tlas_future)
+            .join(lights_future)
+            .join(emissive_triangles_future)
+            .join(environment_cdf_future)
+            .boxed_send_sync();
+        if let Some(environment_future) = environment_future {
+            upload = upload.join(environment_future).boxed_send_sync();
+        }
+        if let Some(textures_future) = textures_future {
+            upload = upload.join(textures_future).boxed_send_sync();
+        }
+        let upload = upload.then_signal_fence_and_flush().unwrap();
 
-        Self {
+        Ok(Some(Self {
             triangles_buffer,
             materials_buffer,
             models_buffer,
             bvhs_buffer,
+            tlas_buffer,
+            lights_buffer,
+            emissive_triangles_buffer,
+            environment_map,
+            environment_cdf_buffer,
+            textures,
+            texture_filter: *texture_filter,
+            load_stats,
+            models,
+            model_bounds,
+            material_count: materials.len(),
+            material_powers,
+            emissive_triangles,
+            winding: *winding,
+            bounds,
+            upload: Some(Arc::new(upload)),
+            #[cfg(feature = "cpu-reference")]
+            triangles,
+            #[cfg(feature = "cpu-reference")]
+            bvhs,
+            #[cfg(feature = "cpu-reference")]
+            materials,
+            #[cfg(feature = "cpu-reference")]
+            lights: lights.clone(),
+        }))
+    }
+
+    /// Loads the models at the given paths in parallel, on the host only.
+    ///
+    /// Each model is parsed and its BVH built on its own, as if it were the first of the buffers.
+    /// Their triangles and BVHs are then concatenated in order, their offsets being shifted accordingly,
+    /// so that the result is the same as loading the models one after the other into the same buffers.
+    ///
+    /// Each model is given the index of its first material and the one of its faces without material,
+    /// as described by [`crate::shader::source::Model::load`].
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`crate::InitError::ModelLoad`] if one of the models cannot be loaded,
+    /// or [`crate::InitError::MaterialOutOfRange`] if one of them uses a material whose index is not below `material_count`.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the scene contains more than `u32::MAX` triangles or BVH nodes.
+    fn load_geometry(
+        model_paths: &[String],
+        transforms: &[super::Transform],
+        material_ids: &[(u32, u32)],
+        winding: super::Winding,
+        material_count: usize,
+    ) -> Result<Geometry, crate::InitError> {
+        let loaded_models = model_paths
+            .par_iter()
+            .zip(transforms.par_iter())
+            .zip(material_ids.par_iter())
+            .map(|((path, transform), &(material_id, default_material_id))| {
+                let mut triangles = Vec::new();
+                let mut bvhs = Vec::new();
+                let (model, stats) = crate::shader::source::Model::load(
+                    &mut triangles,
+                    &mut bvhs,
+                    path,
+                    transform,
+                    material_id,
+                    default_material_id,
+                    winding,
+                )
+                .map_err(|error| crate::InitError::ModelLoad(path.into(), error))?;
+
+                Self::check_sub_materials(path, &triangles, material_count)?;
+
+                Ok((model, stats, triangles, bvhs))
+            })
+            .collect::<Result<Vec<_>, crate::InitError>>()?;
+
+        let mut triangles = Vec::new();
+        let mut bvhs = Vec::new();
+        let mut triangle_ranges = Vec::new();
+        let (models, load_stats) = loaded_models
+            .into_iter()
+            .map(|(mut model, stats, model_triangles, mut model_bvhs)| {
+                let triangle_offset = u32::try_from(triangles.len()).expect("too many triangles");
+                let bvh_offset = u32::try_from(bvhs.len()).expect("too many BVHs");
+                crate::shader::source::Bvh::relocate(&mut model_bvhs, triangle_offset, bvh_offset);
+                model.bvh_index += bvh_offset;
+
+                triangle_ranges.push(triangles.len()..triangles.len() + model_triangles.len());
+                triangles.extend(model_triangles);
+                bvhs.extend(model_bvhs);
+                (model, stats)
+            })
+            .unzip();
+
+        Ok(Geometry {
+            models,
+            load_stats,
+            triangles,
+            bvhs,
+            triangle_ranges,
+        })
+    }
+
+    /// Returns whether the buffers of the scene are uploaded to the device, without blocking.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the upload failed.
+    pub fn is_uploaded(&mut self) -> bool {
+        let Some(upload) = &self.upload else {
+            return true;
+        };
+        if !upload.is_signaled().unwrap() {
+            return false;
+        }
+
+        // Returns immediately, but releases the buffers for the next submissions
+        upload.wait(None).unwrap();
+        self.upload = None;
+        tracing::debug!("Scene uploaded to the device");
+        true
+    }
+
+    /// Blocks until the buffers of the scene are uploaded to the device.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the upload failed.
+    pub fn wait_for_upload(&mut self) {
+        if let Some(upload) = self.upload.take() {
+            upload.wait(None).unwrap();
+        }
+    }
+
+    /// Moves the model at the given index to the given position,
+    /// by rewriting its entry in the models buffer.
+    ///
+    /// As the triangles and the BVH of the model are relative to its position, they are left untouched,
+    /// only the top-level BVH being rebuilt.
+    /// The caller must make sure that the buffers are not in use by the GPU.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if there is no model at the given index, or if the buffer cannot be written.
+    pub fn set_model_translation(
+        &mut self,
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+        queue: &Arc<Queue>,
+        model_index: usize,
+        translation: [f32; 3],
+    ) {
+        assert!(
+            model_index < self.models.len(),
+            "model index {model_index} out of range, the scene has {} models",
+            self.models.len()
+        );
+
+        self.wait_for_upload();
+        self.models[model_index].translation = translation;
+
+        let destination = self
+            .models_buffer
+            .clone()
+            .reinterpret::<[Padded<crate::shader::source::Model, 12>]>()
+            .index(model_index as u64);
+        let destination_future = crate::buffer::write_to_device(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            destination,
+            self.models[model_index],
+        )
+        .unwrap();
+
+        // The top-level BVH has the same number of nodes, as long as the number of models is unchanged
+        let tlas = Self::build_tlas(&self.models, &self.model_bounds);
+        let tlas_future = crate::buffer::write_slice_to_device(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            self.tlas_buffer
+                .clone()
+                .reinterpret::<[Padded<crate::shader::source::Bvh, 4>]>(),
+            &tlas,
+        )
+        .unwrap();
+
+        destination_future
+            .join(tlas_future)
+            .then_signal_fence()
+            .wait(None)
+            .unwrap();
+
+        self.bounds = Self::compute_bounds(&self.models, &self.model_bounds);
+    }
+
+    /// Loads a model and appends it to the scene, returning its index.
+    ///
+    /// The model is loaded like the ones of the scene descriptor, with the same winding.
+    /// The triangles, BVH, top-level BVH and models buffers are replaced by larger ones,
+    /// as is the emissive triangles buffer if the model emits light,
+    /// so the buffers given to the renderer must be updated afterward.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`crate::InitError::ModelLoad`] if the model cannot be loaded,
+    /// or [`crate::InitError::MaterialOutOfRange`] if it references a material that does not exist.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the buffers cannot be extended.
+    pub fn add_model(
+        &mut self,
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+        queue: &Arc<Queue>,
+        path: &str,
+        transform: &super::Transform,
+        material_id: u32,
+    ) -> Result<usize, crate::InitError> {
+        if material_id as usize >= self.material_count {
+            return Err(crate::InitError::MaterialOutOfRange(
+                path.into(),
+                material_id,
+            ));
+        }
+
+        let mut triangles = Vec::new();
+        let mut bvhs = Vec::new();
+        let (mut model, stats) = crate::shader::source::Model::load(
+            &mut triangles,
+            &mut bvhs,
+            path,
+            transform,
+            material_id,
+            material_id,
+            self.winding,
+        )
+        .map_err(|error| crate::InitError::ModelLoad(path.into(), error))?;
+
+        Self::check_sub_materials(path, &triangles, self.material_count)?;
+        self.wait_for_upload();
+
+        // The new triangles and nodes come after the ones already on the device
+        let triangle_offset = u32::try_from(
+            self.load_stats
+                .iter()
+                .map(|loaded| loaded.triangle_count)
+                .sum::<usize>(),
+        )
+        .expect("too many triangles");
+        let bvh_offset = u32::try_from(
+            self.load_stats
+                .iter()
+                .map(|loaded| loaded.bvh_node_count)
+                .sum::<usize>(),
+        )
+        .expect("too many BVHs");
+        crate::shader::source::Bvh::relocate(&mut bvhs, triangle_offset, bvh_offset);
+        let root = &bvhs[model.bvh_index as usize];
+        let bounds = (*root.min_bound, root.max_bound);
+        model.bvh_index += bvh_offset;
+        let model = Padded::from(model);
+
+        let (triangles_buffer, triangles_future) = crate::buffer::extend_on_device(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            &self.triangles_buffer,
+            triangles.len() as u64,
+            BufferUsage::STORAGE_BUFFER,
+            |data: &mut crate::shader::TrianglesBuffer| {
+                data.triangles.copy_from_slice(&triangles);
+            },
+        )
+        .unwrap();
+        let (bvhs_buffer, bvh_future) = crate::buffer::extend_on_device(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            &self.bvhs_buffer,
+            bvhs.len() as u64,
+            BufferUsage::STORAGE_BUFFER,
+            |data: &mut crate::shader::BvhBuffer| data.bvhs.copy_from_slice(&bvhs),
+        )
+        .unwrap();
+        let (models_buffer, models_future) = crate::buffer::extend_on_device(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            &self.models_buffer,
+            1,
+            BufferUsage::STORAGE_BUFFER,
+            |data: &mut crate::shader::ModelsBuffer| data.models[0] = model,
+        )
+        .unwrap();
+
+        let emissive_count = self.emissive_triangles.len();
+        Self::push_emissive_triangles(
+            &mut self.emissive_triangles,
+            self.models.len(),
+            &model,
+            &triangles,
+            triangle_offset as usize,
+            &self.material_powers,
+        );
+        if self.emissive_triangles.len() > emissive_count {
+            let (emissive_triangles_buffer, emissive_triangles_future) =
+                Self::send_emissive_triangles(
+                    memory_allocator,
+                    command_buffer_allocator,
+                    queue,
+                    &self.emissive_triangles,
+                );
+            emissive_triangles_future.wait(None).unwrap();
+            self.emissive_triangles_buffer = emissive_triangles_buffer;
+        }
+
+        self.models.push(model);
+        self.model_bounds.push(bounds);
+        let tlas = Self::build_tlas(&self.models, &self.model_bounds);
+        let (tlas_buffer, tlas_future) =
+            Self::send_tlas(memory_allocator, command_buffer_allocator, queue, &tlas);
+
+        triangles_future
+            .join(bvh_future)
+            .join(models_future)
+            .join(tlas_future)
+            .then_signal_fence()
+            .wait(None)
+            .unwrap();
+
+        self.triangles_buffer = triangles_buffer;
+        self.bvhs_buffer = bvhs_buffer;
+        self.models_buffer = models_buffer;
+        self.tlas_buffer = tlas_buffer;
+        self.load_stats.push(stats);
+        #[cfg(feature = "cpu-reference")]
+        {
+            self.triangles.extend(triangles);
+            self.bvhs.extend(bvhs);
+        }
+        self.bounds = Self::compute_bounds(&self.models, &self.model_bounds);
+
+        Ok(self.models.len() - 1)
+    }
+
+    #[cfg(feature = "cpu-reference")]
+    #[must_use]
+    /// Returns the data of the scene read by the reference path tracer.
+    pub(crate) fn reference_scene(&self) -> super::reference::Scene<'_> {
+        super::reference::Scene {
+            triangles: &self.triangles,
+            bvhs: &self.bvhs,
+            models: &self.models,
+            tlas: Self::build_tlas(&self.models, &self.model_bounds),
+            materials: &self.materials,
+            lights: &self.lights,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the number of models of the scene, instances included.
+    pub const fn model_count(&self) -> usize {
+        self.models.len()
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns the axis-aligned bounding box of the whole scene, as `(min, max)`.
+    pub const fn scene_bounds(&self) -> ([f32; 3], [f32; 3]) {
+        self.bounds
+    }
+
+    #[must_use]
+    /// Returns the size of the buffers of the scene, in bytes.
+    pub fn size(&self) -> u64 {
+        self.triangles_buffer.size()
+            + self.materials_buffer.size()
+            + self.models_buffer.size()
+            + self.bvhs_buffer.size()
+            + self.tlas_buffer.size()
+            + self.lights_buffer.size()
+            + self.emissive_triangles_buffer.size()
+            + self.environment_cdf_buffer.size()
+            + self.environment_map.as_ref().map_or(0, |map| {
+                let [width, height, _] = map.image().extent();
+                // Texels are made of four 32-bit floats
+                u64::from(width) * u64::from(height) * 16
+            })
+            + self.textures.as_ref().map_or(0, |textures| {
+                let [width, height, _] = textures.image().extent();
+                // Texels are made of four 8-bit channels
+                u64::from(width)
+                    * u64::from(height)
+                    * u64::from(textures.image().array_layers())
+                    * 4
+            })
+    }
+
+    #[must_use]
+    /// Returns an estimate of the size of the geometry buffers of a scene, in bytes,
+    /// from the load statistics of its models and its number of models, instances included, and materials.
+    ///
+    /// The TLAS is counted at its largest, with two nodes per model.
+    fn estimated_size(
+        load_stats: &[ModelLoadStats],
+        model_count: usize,
+        material_count: usize,
+    ) -> u64 {
+        use std::mem::size_of;
+
+        let triangle_count = load_stats
+            .iter()
+            .map(|stats| stats.triangle_count)
+            .sum::<usize>();
+        let bvh_node_count = load_stats
+            .iter()
+            .map(|stats| stats.bvh_node_count)
+            .sum::<usize>();
+
+        (triangle_count * size_of::<Padded<crate::shader::source::Triangle, 4>>()
+            + (bvh_node_count + 2 * model_count)
+                * size_of::<Padded<crate::shader::source::Bvh, 4>>()
+            + model_count * size_of::<Padded<crate::shader::source::Model, 12>>()
+            + material_count * size_of::<Padded<crate::shader::source::Material, 4>>())
+            as u64
+    }
+
+    /// Checks that the sub-materials used by the triangles of a model exist.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns [`crate::InitError::MaterialOutOfRange`] if a triangle uses a material
+    /// whose index is not below `material_count`.
+    fn check_sub_materials(
+        path: &str,
+        triangles: &[Padded<crate::shader::source::Triangle, 4>],
+        material_count: usize,
+    ) -> Result<(), crate::InitError> {
+        let max_material_id = triangles.iter().map(|triangle| triangle.material_id).max();
+        if let Some(max_material_id) = max_material_id {
+            if max_material_id as usize >= material_count {
+                return Err(crate::InitError::MaterialOutOfRange(
+                    path.into(),
+                    max_material_id,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    /// Builds the top-level BVH over the bounds of the models, placed in the scene.
+    fn build_tlas(
+        models: &[Padded<crate::shader::source::Model, 12>],
+        model_bounds: &[([f32; 3], [f32; 3])],
+    ) -> Vec<Padded<crate::shader::source::Bvh, 4>> {
+        let bounds = models
+            .iter()
+            .zip(model_bounds)
+            .map(|(model, bounds)| model.world_bounds(*bounds))
+            .collect::<Vec<_>>();
+
+        crate::shader::source::Bvh::build_top_level(&bounds)
+    }
+
+    /// Sends the given top-level BVH to the device, returning its buffer and the send future.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the buffer cannot be sent.
+    fn send_tlas(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+        queue: &Arc<Queue>,
+        tlas: &[Padded<crate::shader::source::Bvh, 4>],
+    ) -> (
+        Subbuffer<crate::shader::TlasBuffer>,
+        crate::buffer::SendBufferFuture,
+    ) {
+        crate::buffer::send_to_device(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            tlas.len() as u64,
+            BufferUsage::STORAGE_BUFFER,
+            |data: &mut crate::shader::TlasBuffer| data.tlas.copy_from_slice(tlas),
+        )
+        .unwrap()
+    }
+
+    #[must_use]
+    /// Returns the power emitted per unit of area by the given material, 0 if it does not emit light.
+    fn material_power(material: &crate::shader::source::Material) -> f32 {
+        let [r, g, b] = material.color;
+        (material.emission_strength * (r + g + b) / 3.0).max(0.0)
+    }
+
+    /// Appends the emissive triangles among the given triangles of the given model, at the given index,
+    /// to `emissive_triangles`, `triangle_offset` being the index of the first one in the triangles buffer.
+    ///
+    /// Every emissive triangle stores the power emitted by it and the ones before it,
+    /// so that the shader picks them with a probability proportional to their power.
+    /// Their areas are the ones in the scene, once scaled by the model.
+    fn push_emissive_triangles(
+        emissive_triangles: &mut Vec<crate::shader::source::EmissiveTriangle>,
+        model_index: usize,
+        model: &crate::shader::source::Model,
+        triangles: &[Padded<crate::shader::source::Triangle, 4>],
+        triangle_offset: usize,
+        material_powers: &[f32],
+    ) {
+        let mut cumulative_power = emissive_triangles
+            .last()
+            .map_or(0.0, |emissive| emissive.cumulative_power);
+
+        for (index, triangle) in triangles.iter().enumerate() {
+            let power = material_powers[triangle.material_id as usize];
+            let [first, second, third] = triangle
+                .vertices
+                .map(|vertex| model.transform_vector(*vertex));
+            let edge1: [f32; 3] = std::array::from_fn(|axis| second[axis] - first[axis]);
+            let edge2: [f32; 3] = std::array::from_fn(|axis| third[axis] - first[axis]);
+            // The area of a triangle is half the length of the cross product of two of its edges
+            let [x, y, z] = std::array::from_fn(|axis| {
+                let (next, last) = ((axis + 1) % 3, (axis + 2) % 3);
+                edge1[next].mul_add(edge2[last], -(edge1[last] * edge2[next]))
+            });
+            let area = 0.5 * z.mul_add(z, x.mul_add(x, y * y)).sqrt();
+            if power <= 0.0 || !area.is_normal() {
+                continue;
+            }
+
+            cumulative_power += power * area;
+            emissive_triangles.push(crate::shader::source::EmissiveTriangle {
+                triangle_index: u32::try_from(triangle_offset + index).expect("too many triangles"),
+                model_index: u32::try_from(model_index).expect("too many models"),
+                area,
+                cumulative_power,
+            });
+        }
+    }
+
+    /// Sends the given emissive triangles to the device, returning their buffer and the send future.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the buffer cannot be sent.
+    fn send_emissive_triangles(
+        memory_allocator: &Arc<StandardMemoryAllocator>,
+        command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+        queue: &Arc<Queue>,
+        emissive_triangles: &[crate::shader::source::EmissiveTriangle],
+    ) -> (
+        Subbuffer<crate::shader::EmissiveTrianglesBuffer>,
+        crate::buffer::SendBufferFuture,
+    ) {
+        // Buffers cannot be empty, a triangle without power is never picked by the shader
+        let placeholder = [crate::shader::source::EmissiveTriangle {
+            triangle_index: 0,
+            model_index: 0,
+            area: 0.0,
+            cumulative_power: 0.0,
+        }];
+        let emissive_triangles = if emissive_triangles.is_empty() {
+            &placeholder
+        } else {
+            emissive_triangles
+        };
+
+        crate::buffer::send_to_device(
+            memory_allocator,
+            command_buffer_allocator,
+            queue,
+            emissive_triangles.len() as u64,
+            BufferUsage::STORAGE_BUFFER,
+            |data: &mut crate::shader::EmissiveTrianglesBuffer| {
+                data.emissive_triangles.copy_from_slice(emissive_triangles);
+            },
+        )
+        .unwrap()
+    }
+
+    #[must_use]
+    /// Compute the bounds of the scene from the bounds of the models, placed in the scene.
+    ///
+    /// An empty scene has zero-sized bounds at the origin.
+    fn compute_bounds(
+        models: &[Padded<crate::shader::source::Model, 12>],
+        model_bounds: &[([f32; 3], [f32; 3])],
+    ) -> ([f32; 3], [f32; 3]) {
+        if models.is_empty() {
+            return ([0.0; 3], [0.0; 3]);
+        }
+
+        models.iter().zip(model_bounds).fold(
+            ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+            |(mut min_bound, mut max_bound), (model, bounds)| {
+                let (model_min, model_max) = model.world_bounds(*bounds);
+                for axis in 0..3 {
+                    min_bound[axis] = min_bound[axis].min(model_min[axis]);
+                    max_bound[axis] = max_bound[axis].max(model_max[axis]);
+                }
+                (min_bound, max_bound)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+    use crate::shader::{source::Model, Transform, Winding};
+
+    #[must_use]
+    /// Returns an OBJ file made of a grid of `size` by `size` quads, each split into two triangles,
+    /// on a bumpy surface so that its BVH has several levels.
+    fn grid_obj(size: u16) -> String {
+        let mut obj = String::new();
+        for z in 0..=size {
+            for x in 0..=size {
+                let height = f32::from((x * 7 + z * 3) % 5) * 0.1;
+                writeln!(obj, "v {x} {height} {z}").unwrap();
+            }
+        }
+        for z in 0..size {
+            for x in 0..size {
+                // The vertices of an OBJ file are numbered from 1
+                let a = z * (size + 1) + x + 1;
+                let (b, c, d) = (a + 1, a + size + 1, a + size + 2);
+                writeln!(obj, "f {a} {c} {b}").unwrap();
+                writeln!(obj, "f {b} {c} {d}").unwrap();
+            }
+        }
+        obj
+    }
+
+    #[test]
+    /// Loading the models in parallel gives the same buffers as loading them one after the other.
+    fn parallel_load_matches_sequential_load() {
+        let directory =
+            std::env::temp_dir().join(format!("rt-engine-parallel-load-{}", std::process::id()));
+        // Each load reads its own copy of the models, so that none of them reads the caches of the other
+        let write_models = |name: &str| {
+            let directory = directory.join(name);
+            std::fs::create_dir_all(&directory).unwrap();
+            [("grid.obj", 8), ("small_grid.obj", 3)]
+                .into_iter()
+                .map(|(file_name, size)| {
+                    let path = directory.join(file_name);
+                    std::fs::write(&path, grid_obj(size)).unwrap();
+                    path.to_string_lossy().into_owned()
+                })
+                .collect::<Vec<_>>()
+        };
+        let transforms = [
+            Transform::from([0.0; 3]),
+            Transform {
+                translation: [4.0, 1.0, -2.0],
+                rotation_euler: [0.0, 30.0, 0.0],
+                scale: [2.0; 3],
+            },
+        ];
+
+        let parallel = LoadedModels::load_geometry(
+            &write_models("parallel"),
+            &transforms,
+            &[(0, 0), (0, 0)],
+            Winding::CounterClockwise,
+            1,
+        )
+        .unwrap();
+
+        let mut triangles = Vec::new();
+        let mut bvhs = Vec::new();
+        let models = write_models("sequential")
+            .iter()
+            .zip(&transforms)
+            .map(|(path, transform)| {
+                Model::load(
+                    &mut triangles,
+                    &mut bvhs,
+                    path,
+                    transform,
+                    0,
+                    0,
+                    Winding::CounterClockwise,
+                )
+                .unwrap()
+                .0
+            })
+            .collect::<Vec<_>>();
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // The types of the shader can only be compared through their debug representation
+        assert!(
+            parallel.models[1].bvh_index > 1,
+            "the first BVH was not split"
+        );
+        assert_eq!(format!("{:?}", parallel.models), format!("{models:?}"));
+        assert_eq!(format!("{:?}", parallel.bvhs), format!("{bvhs:?}"));
+        assert_eq!(
+            format!("{:?}", parallel.triangles),
+            format!("{triangles:?}")
+        );
+        assert_eq!(parallel.triangle_ranges, [0..128, 128..146]);
+    }
+
+    #[test]
+    /// The rotation and scale of an instance are inverted for the shader, and move its bounds.
+    fn instance_transforms_are_applied_to_the_bounds() {
+        let directory =
+            std::env::temp_dir().join(format!("rt-engine-instance-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("grid.obj");
+        std::fs::write(&path, grid_obj(2)).unwrap();
+
+        let (model, _) = Model::load(
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &path.to_string_lossy(),
+            &Transform::default(),
+            0,
+            0,
+            Winding::CounterClockwise,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let instance = model.placed(&Transform {
+            translation: [10.0, 0.0, 0.0],
+            rotation_euler: [0.0, 90.0, 0.0],
+            scale: [2.0, 1.0, 1.0],
+        });
+        for column in 0..3 {
+            for row in 0..3 {
+                let product = (0..3)
+                    .map(|k| instance.inverse_basis[k][row] * instance.basis[column][k])
+                    .sum::<f32>();
+                let expected = if row == column { 1.0 } else { 0.0 };
+                assert!((product - expected).abs() < 1e-5, "{row} {column}");
+            }
+        }
+
+        // Scaled twice along X, then turned so that X points to -Z
+        let (min_bound, max_bound) = instance.world_bounds(([0.0; 3], [1.0; 3]));
+        for (bound, expected) in min_bound
+            .into_iter()
+            .chain(max_bound)
+            .zip([10.0, 0.0, -2.0, 11.0, 1.0, 0.0])
+        {
+            assert!(
+                (bound - expected).abs() < 1e-5,
+                "{min_bound:?} {max_bound:?}"
+            );
         }
     }
 }
@@ -1,11 +1,94 @@
 use crate::shader::source::{Bvh, Triangle};
 use vulkano::padded::Padded;
 
+/// The number of triangles below which a node and its children are built on the current thread.
+const PARALLEL_THRESHOLD: usize = 4096;
+
+/// The number of bins the centroids of the triangles are bucketed into, per axis, to find the best split.
+const BIN_COUNT: usize = 16;
+
+#[derive(Clone, Copy)]
+/// Aggregates the triangles whose centroids fall in a bin.
+struct Bin {
+    /// The minimum bound of the triangles of the bin.
+    min_bound: [f32; 3],
+    /// The maximum bound of the triangles of the bin.
+    max_bound: [f32; 3],
+    /// The number of triangles in the bin.
+    count: u32,
+}
+
+impl Bin {
+    /// A bin without any triangle.
+    const EMPTY: Self = Self {
+        min_bound: [f32::INFINITY; 3],
+        max_bound: [f32::NEG_INFINITY; 3],
+        count: 0,
+    };
+
+    #[inline]
+    /// Adds the triangles of another bin to this one.
+    fn merge(&mut self, other: &Self) {
+        // The inverted infinite bounds of an empty bin would widen these ones to infinity
+        if other.count == 0 {
+            return;
+        }
+
+        Bvh::grow_to_include(&mut self.min_bound, &mut self.max_bound, &other.min_bound);
+        Bvh::grow_to_include(&mut self.min_bound, &mut self.max_bound, &other.max_bound);
+        self.count += other.count;
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Represents a split of a node between two bins of centroids along an axis.
+struct Split {
+    /// The axis the node is split along.
+    axis: usize,
+    /// The minimum centroid of the triangles of the node along the axis.
+    min_centroid: f32,
+    /// The number of bins per unit along the axis.
+    scale: f32,
+    /// The index of the first bin of the right side.
+    boundary: usize,
+    /// The cost of the split.
+    cost: f64,
+}
+
+impl Split {
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    /// Returns the bin a centroid falls in.
+    fn bin_index(centroid: f32, min_centroid: f32, scale: f32) -> usize {
+        (((centroid - min_centroid) * scale) as usize).min(BIN_COUNT - 1)
+    }
+
+    #[must_use]
+    #[inline]
+    /// Returns whether the triangle belongs to the left side of the split.
+    ///
+    /// Triangles are assigned exactly as they were binned, so that the sides match the evaluated cost.
+    fn is_left(&self, triangle: &Triangle) -> bool {
+        Self::bin_index(
+            Bvh::centroid(triangle, self.axis),
+            self.min_centroid,
+            self.scale,
+        ) < self.boundary
+    }
+}
+
 impl Bvh {
     #[must_use]
     #[inline]
     /// Compute the cost of a BVH node
+    ///
+    /// An empty node has no cost, regardless of its (possibly infinite) bounds.
     fn bvh_cost(min_bound: [f32; 3], max_bound: [f32; 3], count: u32) -> f64 {
+        if count == 0 {
+            return 0.0;
+        }
+
         let dx = max_bound[0] - min_bound[0];
         let dy = max_bound[1] - min_bound[1];
         let dz = max_bound[2] - min_bound[2];
@@ -30,123 +113,149 @@ impl Bvh {
     }
 
     #[must_use]
-    /// Evaluate the cost of a split at the given position
-    ///
-    /// `split_axis` is the axis to split on, must be 0, 1, or 2
-    fn evaluate_split(
-        split_axis: usize,
-        split_position: f32,
-        triangles: &[Padded<Triangle, 8>],
-    ) -> f64 {
-        let mut min_bound_left = [f32::INFINITY; 3];
-        let mut max_bound_left = [f32::NEG_INFINITY; 3];
-        let mut min_bound_right = [f32::INFINITY; 3];
-        let mut max_bound_right = [f32::NEG_INFINITY; 3];
-        let mut count_left = 0;
-        let mut count_right = 0;
-
-        for triangle in triangles {
-            let (min_bound, max_bound, count) = if triangle
-                .vertices
-                .iter()
-                .any(|vertex| vertex[split_axis] < split_position)
-            {
-                (&mut min_bound_left, &mut max_bound_left, &mut count_left)
-            } else {
-                (&mut min_bound_right, &mut max_bound_right, &mut count_right)
-            };
-
-            for vertex in triangle.vertices {
-                Self::grow_to_include(min_bound, max_bound, &vertex);
-            }
-            *count += 1;
-        }
-
-        Self::bvh_cost(min_bound_left, max_bound_left, count_left)
-            + Self::bvh_cost(min_bound_right, max_bound_right, count_right)
+    #[inline]
+    /// Returns the centroid of a triangle along the given axis
+    fn centroid(triangle: &Triangle, axis: usize) -> f32 {
+        (triangle.vertices[0][axis] + triangle.vertices[1][axis] + triangle.vertices[2][axis]) / 3.0
     }
 
     #[must_use]
-    /// Find the best split position for the given axis
-    fn choose_split(bvh: Self, triangles: &[Padded<Triangle, 8>]) -> (usize, f32, f64) {
-        /// The number of different split positions to test.
-        const SPLIT_TEST_COUNT: u8 = 5;
+    /// Find the best split of the node with a binned SAH
+    ///
+    /// The centroids of the triangles are bucketed into `BIN_COUNT` bins per axis in a single pass,
+    /// and every boundary between two bins is evaluated from the aggregated bins.
+    ///
+    /// Returns `None` if the node cannot be split, typically because all the centroids are at the same position.
+    fn choose_split(triangles: &[Padded<Triangle, 4>]) -> Option<Split> {
         /// The minimum number of triangles in a leaf node.
         const MIN_TRIANGLES: usize = 2;
 
         if triangles.len() <= MIN_TRIANGLES {
-            return (0, 0.0, f64::INFINITY);
+            return None;
         }
 
-        let mut best_split_pos = 0.0;
-        let mut best_split_axis = 0;
-        let mut best_cost = f64::INFINITY;
+        let mut min_centroid = [f32::INFINITY; 3];
+        let mut max_centroid = [f32::NEG_INFINITY; 3];
+        for triangle in triangles {
+            let centroid = std::array::from_fn(|axis| Self::centroid(triangle, axis));
+            Self::grow_to_include(&mut min_centroid, &mut max_centroid, &centroid);
+        }
+
+        let mut best_split: Option<Split> = None;
 
         for axis in 0..3 {
-            let delta = bvh.max_bound[axis] - bvh.min_bound[axis];
+            let extent = max_centroid[axis] - min_centroid[axis];
 
-            for i in 0..SPLIT_TEST_COUNT {
-                let split_lambda = f32::from(i + 1) / f32::from(SPLIT_TEST_COUNT + 1);
-                let split_pos = split_lambda.mul_add(delta, bvh.min_bound[axis]);
+            // Flat or degenerate axis, every triangle would fall in the same bin
+            if !extent.is_finite() || extent <= f32::EPSILON * max_centroid[axis].abs().max(1.0) {
+                continue;
+            }
 
-                let cost = Self::evaluate_split(axis, split_pos, triangles);
-                if cost < best_cost {
-                    best_cost = cost;
-                    best_split_pos = split_pos;
-                    best_split_axis = axis;
+            #[allow(clippy::cast_precision_loss)]
+            let scale = BIN_COUNT as f32 / extent;
+
+            let mut bins = [Bin::EMPTY; BIN_COUNT];
+            for triangle in triangles {
+                let bin = &mut bins
+                    [Split::bin_index(Self::centroid(triangle, axis), min_centroid[axis], scale)];
+                bin.count += 1;
+                for vertex in triangle.vertices {
+                    Self::grow_to_include(&mut bin.min_bound, &mut bin.max_bound, &vertex);
+                }
+            }
+
+            // The cost of the left side of each boundary, from the bins before it
+            let mut left_costs = [0.0; BIN_COUNT];
+            let mut left = Bin::EMPTY;
+            for (boundary, bin) in bins.iter().enumerate().take(BIN_COUNT - 1) {
+                left.merge(bin);
+                left_costs[boundary + 1] =
+                    Self::bvh_cost(left.min_bound, left.max_bound, left.count);
+            }
+
+            let mut right = Bin::EMPTY;
+            for boundary in (1..BIN_COUNT).rev() {
+                right.merge(&bins[boundary]);
+
+                // A split that leaves one of the sides empty is useless
+                let left_count =
+                    u32::try_from(triangles.len()).expect("too many triangles") - right.count;
+                if left_count == 0 || right.count == 0 {
+                    continue;
+                }
+
+                let cost = left_costs[boundary]
+                    + Self::bvh_cost(right.min_bound, right.max_bound, right.count);
+                if cost < best_split.map_or(f64::INFINITY, |split| split.cost) {
+                    best_split = Some(Split {
+                        axis,
+                        min_centroid: min_centroid[axis],
+                        scale,
+                        boundary,
+                        cost,
+                    });
                 }
             }
         }
 
-        (best_split_axis, best_split_pos, best_cost)
+        best_split
     }
 
-    /// Recursively split the BVH
-    fn split(bvhs: &mut Vec<Padded<Self, 4>>, triangles: &mut [Padded<Triangle, 8>]) {
-        let start_bvh_len = u32::try_from(bvhs.len()).expect("too many BVHs");
-        let bvh = bvhs.last_mut().unwrap();
-        let triangle_offset = bvh.triangle_offset;
+    #[must_use]
+    /// Returns whether the node should be split at the given cost
+    fn should_split(bvh: &Self, split_cost: f64) -> bool {
         let parent_cost = Self::bvh_cost(*bvh.min_bound, bvh.max_bound, bvh.triangle_count);
+        split_cost.is_finite() && split_cost < 0.9 * parent_cost
+    }
 
-        let (split_axis, split_position, split_cost) = Self::choose_split(**bvh, triangles);
-
-        if split_cost < 0.9 * parent_cost {
-            let mut bvh_left = Self {
-                min_bound: bvh.max_bound.into(),
-                max_bound: *bvh.min_bound,
-                left_offset: 0,
-                right_offset: 0,
-                triangle_offset,
-                triangle_count: 0,
-            };
-            let mut bvh_right = Self {
-                min_bound: bvh.max_bound.into(),
-                max_bound: *bvh.min_bound,
-                left_offset: 0,
-                right_offset: 0,
-                triangle_offset, // incorrect value
-                triangle_count: 0,
-            };
+    #[must_use]
+    /// Partition the triangles of a node along the given split,
+    /// the triangles of the left child being moved to the front of the slice
+    ///
+    /// Returns the left and right children of the node.
+    fn partition(bvh: &Self, triangles: &mut [Padded<Triangle, 4>], split: Split) -> (Self, Self) {
+        let mut bvh_left = Self {
+            min_bound: bvh.max_bound.into(),
+            max_bound: *bvh.min_bound,
+            left_offset: 0,
+            right_offset: 0,
+            triangle_offset: bvh.triangle_offset,
+            triangle_count: 0,
+        };
+        let mut bvh_right = bvh_left;
 
-            for i in 0..triangles.len() {
-                let left = triangles[i]
-                    .vertices
-                    .iter()
-                    .any(|vertex| vertex[split_axis] < split_position);
+        for i in 0..triangles.len() {
+            let left = split.is_left(&triangles[i]);
 
-                triangles.swap(i, bvh_left.triangle_count as usize);
-                let triangle = *triangles[bvh_left.triangle_count as usize];
+            triangles.swap(i, bvh_left.triangle_count as usize);
+            let triangle = *triangles[bvh_left.triangle_count as usize];
 
-                let target_bvh = if left { &mut bvh_left } else { &mut bvh_right };
-                target_bvh.triangle_count += 1;
-                for vertex in triangle.vertices {
-                    Self::grow_to_include(
-                        &mut target_bvh.min_bound,
-                        &mut target_bvh.max_bound,
-                        &vertex,
-                    );
-                }
+            let target_bvh = if left { &mut bvh_left } else { &mut bvh_right };
+            target_bvh.triangle_count += 1;
+            for vertex in triangle.vertices {
+                Self::grow_to_include(
+                    &mut target_bvh.min_bound,
+                    &mut target_bvh.max_bound,
+                    &vertex,
+                );
             }
+        }
+
+        bvh_right.triangle_offset = bvh.triangle_offset + bvh_left.triangle_count;
+
+        (bvh_left, bvh_right)
+    }
+
+    /// Recursively split the BVH
+    fn split(bvhs: &mut Vec<Padded<Self, 4>>, triangles: &mut [Padded<Triangle, 4>]) {
+        let start_bvh_len = u32::try_from(bvhs.len()).expect("too many BVHs");
+        let bvh = bvhs.last_mut().unwrap();
+
+        let split =
+            Self::choose_split(triangles).filter(|split| Self::should_split(bvh, split.cost));
+
+        if let Some(split) = split {
+            let (bvh_left, bvh_right) = Self::partition(bvh, triangles, split);
 
             bvh.left_offset = start_bvh_len;
             // bvh is dropped here, so we can safely borrow bvhs again
@@ -156,16 +265,170 @@ impl Bvh {
             // so that we need to borrow bvh again
             bvhs[start_bvh_len as usize - 1].right_offset =
                 u32::try_from(bvhs.len()).expect("too many BVHs");
-            bvh_right.triangle_offset = triangle_offset + bvh_left.triangle_count;
             bvhs.push(bvh_right.into());
             Self::split(bvhs, &mut triangles[bvh_left.triangle_count as usize..]);
         }
     }
 
+    #[must_use]
+    /// Recursively split the BVH, building the children of large nodes in parallel
+    ///
+    /// Returns the nodes of the subtree in depth-first order, the given node being the first one.
+    /// The offsets of the children are relative to the start of the returned vector.
+    fn split_parallel(bvh: Self, triangles: &mut [Padded<Triangle, 4>]) -> Vec<Padded<Self, 4>> {
+        let mut bvhs = vec![bvh.into()];
+
+        if triangles.len() < PARALLEL_THRESHOLD {
+            Self::split(&mut bvhs, triangles);
+            return bvhs;
+        }
+
+        let Some(split) =
+            Self::choose_split(triangles).filter(|split| Self::should_split(&bvh, split.cost))
+        else {
+            return bvhs;
+        };
+
+        let (bvh_left, bvh_right) = Self::partition(&bvh, triangles, split);
+        let (triangles_left, triangles_right) =
+            triangles.split_at_mut(bvh_left.triangle_count as usize);
+
+        // Subtrees are built into their own vectors, and stitched together afterward
+        let (subtree_left, subtree_right) = rayon::join(
+            || Self::split_parallel(bvh_left, triangles_left),
+            || Self::split_parallel(bvh_right, triangles_right),
+        );
+
+        bvhs[0].left_offset = 1;
+        bvhs[0].right_offset = u32::try_from(1 + subtree_left.len()).expect("too many BVHs");
+
+        let right_offset = bvhs[0].right_offset;
+        Self::append_subtree(&mut bvhs, subtree_left, 1);
+        Self::append_subtree(&mut bvhs, subtree_right, right_offset);
+
+        bvhs
+    }
+
+    /// Append the nodes of a subtree, shifting the offsets of their children by `offset`
+    ///
+    /// Leaves keep their null offsets.
+    fn append_subtree(bvhs: &mut Vec<Padded<Self, 4>>, subtree: Vec<Padded<Self, 4>>, offset: u32) {
+        bvhs.extend(subtree.into_iter().map(|mut bvh| {
+            if bvh.left_offset != 0 {
+                bvh.left_offset += offset;
+                bvh.right_offset += offset;
+            }
+            bvh
+        }));
+    }
+
+    /// Shift the offsets of the given nodes, built as if they were the first ones of the buffers,
+    /// so that they can be appended after `triangle_offset` triangles and `bvh_offset` nodes.
+    pub fn relocate(bvhs: &mut [Padded<Self, 4>], triangle_offset: u32, bvh_offset: u32) {
+        for bvh in bvhs {
+            bvh.triangle_offset += triangle_offset;
+            if bvh.left_offset != 0 {
+                bvh.left_offset += bvh_offset;
+                bvh.right_offset += bvh_offset;
+            }
+        }
+    }
+
+    #[must_use]
+    /// Build a top-level BVH over the given bounding boxes of the models, one model per leaf
+    ///
+    /// Leaves hold the index of their model in `triangle_offset`, with a `triangle_count` of 1.
+    /// As models are far fewer than triangles, nodes are simply split at the median centroid
+    /// along their largest axis, which keeps the tree balanced.
+    ///
+    /// An empty set of models results in a single empty leaf node.
+    pub fn build_top_level(bounds: &[([f32; 3], [f32; 3])]) -> Vec<Padded<Self, 4>> {
+        let mut indices =
+            (0..u32::try_from(bounds.len()).expect("too many models")).collect::<Vec<_>>();
+
+        if indices.is_empty() {
+            return vec![Self {
+                min_bound: [0.0; 3].into(),
+                max_bound: [0.0; 3],
+                left_offset: 0,
+                right_offset: 0,
+                triangle_offset: 0,
+                triangle_count: 0,
+            }
+            .into()];
+        }
+
+        let mut tlas = Vec::with_capacity(2 * indices.len() - 1);
+        Self::split_top_level(&mut tlas, bounds, &mut indices);
+        tlas
+    }
+
+    /// Recursively split the top-level BVH over the models at the given indices
+    fn split_top_level(
+        tlas: &mut Vec<Padded<Self, 4>>,
+        bounds: &[([f32; 3], [f32; 3])],
+        indices: &mut [u32],
+    ) {
+        let centroid = |index: u32, axis: usize| {
+            let (min_bound, max_bound) = bounds[index as usize];
+            (min_bound[axis] + max_bound[axis]) * 0.5
+        };
+
+        let mut min_bound = [f32::INFINITY; 3];
+        let mut max_bound = [f32::NEG_INFINITY; 3];
+        let mut min_centroid = [f32::INFINITY; 3];
+        let mut max_centroid = [f32::NEG_INFINITY; 3];
+        for &index in &*indices {
+            let (model_min, model_max) = &bounds[index as usize];
+            Self::grow_to_include(&mut min_bound, &mut max_bound, model_min);
+            Self::grow_to_include(&mut min_bound, &mut max_bound, model_max);
+            let model_centroid = std::array::from_fn(|axis| centroid(index, axis));
+            Self::grow_to_include(&mut min_centroid, &mut max_centroid, &model_centroid);
+        }
+
+        let node_index = tlas.len();
+        let is_leaf = indices.len() == 1;
+        tlas.push(
+            Self {
+                min_bound: min_bound.into(),
+                max_bound,
+                left_offset: 0,
+                right_offset: 0,
+                triangle_offset: if is_leaf { indices[0] } else { 0 },
+                triangle_count: u32::from(is_leaf),
+            }
+            .into(),
+        );
+        if is_leaf {
+            return;
+        }
+
+        let axis = (0..3)
+            .max_by(|&a, &b| {
+                (max_centroid[a] - min_centroid[a]).total_cmp(&(max_centroid[b] - min_centroid[b]))
+            })
+            .unwrap();
+        let middle = indices.len() / 2;
+        indices.select_nth_unstable_by(middle, |&a, &b| {
+            centroid(a, axis).total_cmp(&centroid(b, axis))
+        });
+        let (left, right) = indices.split_at_mut(middle);
+
+        // The children always come after the root, so a null offset still denotes a leaf
+        tlas[node_index].left_offset = u32::try_from(tlas.len()).expect("too many BVHs");
+        Self::split_top_level(tlas, bounds, left);
+        tlas[node_index].right_offset = u32::try_from(tlas.len()).expect("too many BVHs");
+        Self::split_top_level(tlas, bounds, right);
+    }
+
     /// Build a BVH
+    ///
+    /// The top levels of large BVHs are built in parallel.
+    ///
+    /// An empty or degenerate (zero-volume) set of triangles results in a single leaf node.
     pub fn build(
         bvhs: &mut Vec<Padded<Self, 4>>,
-        triangles: &mut [Padded<Triangle, 8>],
+        triangles: &mut [Padded<Triangle, 4>],
         triangle_offset: u32,
     ) {
         let mut min_bound = [f32::INFINITY; 3];
@@ -177,18 +440,364 @@ impl Bvh {
             }
         }
 
-        bvhs.push(
-            Self {
-                min_bound: min_bound.into(),
-                max_bound,
-                left_offset: 0,
-                right_offset: 0,
-                triangle_offset,
-                triangle_count: u32::try_from(triangles.len()).expect("too many triangles"),
+        // Avoid infinite bounds for an empty model
+        if triangles.is_empty() {
+            min_bound = [0.0; 3];
+            max_bound = [0.0; 3];
+        }
+
+        let root = Self {
+            min_bound: min_bound.into(),
+            max_bound,
+            left_offset: 0,
+            right_offset: 0,
+            triangle_offset,
+            triangle_count: u32::try_from(triangles.len()).expect("too many triangles"),
+        };
+
+        let offset = u32::try_from(bvhs.len()).expect("too many BVHs");
+        let subtree = Self::split_parallel(root, triangles);
+        Self::append_subtree(bvhs, subtree, offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[must_use]
+    /// Returns a triangle with the given vertices, its other attributes being null.
+    fn triangle(vertices: [[f32; 3]; 3]) -> Padded<Triangle, 4> {
+        Triangle {
+            vertices: vertices.map(Into::into),
+            normal: [0.0; 3].into(),
+            vertex_normals: [[0.0; 3].into(); 3],
+            uv: [[0.0; 2]; 3],
+            material_id: 0,
+        }
+        .into()
+    }
+
+    #[must_use]
+    /// Returns `count` small triangles scattered in a cube of side 10, by a fixed pseudo-random sequence.
+    fn scattered_triangles(count: usize) -> Vec<Padded<Triangle, 4>> {
+        let mut state = 0x2545_f491_u32;
+        let mut random = move || {
+            // Xorshift, which is enough to scatter the triangles
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            #[allow(clippy::cast_precision_loss)]
+            let value = state as f32 / u32::MAX as f32;
+            value
+        };
+
+        (0..count)
+            .map(|_| {
+                let center: [f32; 3] = std::array::from_fn(|_| random() * 10.0);
+                triangle(std::array::from_fn(|_| {
+                    std::array::from_fn(|axis| random().mul_add(0.2, center[axis]))
+                }))
+            })
+            .collect()
+    }
+
+    #[must_use]
+    /// Returns a node holding the given triangles, the first one being at `triangle_offset`.
+    fn node<'a>(
+        triangles: impl IntoIterator<Item = &'a Padded<Triangle, 4>>,
+        triangle_offset: u32,
+    ) -> Bvh {
+        let mut bvh = Bvh {
+            min_bound: [f32::INFINITY; 3].into(),
+            max_bound: [f32::NEG_INFINITY; 3],
+            left_offset: 0,
+            right_offset: 0,
+            triangle_offset,
+            triangle_count: 0,
+        };
+        for triangle in triangles {
+            bvh.triangle_count += 1;
+            for vertex in triangle.vertices {
+                Bvh::grow_to_include(&mut bvh.min_bound, &mut bvh.max_bound, &vertex);
             }
-            .into(),
-        );
+        }
+        bvh
+    }
+
+    /// The bounds, the offsets of the children, the first triangle and the number of triangles of a node.
+    type Node = ([f32; 3], [f32; 3], u32, u32, u32, u32);
+
+    #[must_use]
+    /// Returns the fields of the given nodes, which can be compared unlike the nodes themselves.
+    fn nodes(bvhs: &[Padded<Bvh, 4>]) -> Vec<Node> {
+        bvhs.iter()
+            .map(|bvh| {
+                (
+                    *bvh.min_bound,
+                    bvh.max_bound,
+                    bvh.left_offset,
+                    bvh.right_offset,
+                    bvh.triangle_offset,
+                    bvh.triangle_count,
+                )
+            })
+            .collect()
+    }
+
+    #[must_use]
+    /// Returns the vertices of the given triangles, in order.
+    fn vertices(triangles: &[Padded<Triangle, 4>]) -> Vec<[[f32; 3]; 3]> {
+        triangles
+            .iter()
+            .map(|triangle| triangle.vertices.map(|vertex| *vertex))
+            .collect()
+    }
+
+    #[must_use]
+    /// Returns the expected cost of tracing a ray through the BVH with the surface area heuristic,
+    /// relative to the bounds of its root.
+    ///
+    /// Visiting an inner node and intersecting a triangle both cost 1.
+    fn traversal_cost(bvhs: &[Padded<Bvh, 4>]) -> f64 {
+        let area = |bvh: &Bvh| Bvh::bvh_cost(*bvh.min_bound, bvh.max_bound, 1);
+        let root_area = area(&bvhs[0]);
 
-        Self::split(bvhs, triangles);
+        bvhs.iter()
+            .map(|bvh| {
+                let cost = if bvh.left_offset == 0 {
+                    f64::from(bvh.triangle_count)
+                } else {
+                    1.0
+                };
+                cost * area(bvh) / root_area
+            })
+            .sum()
+    }
+
+    #[must_use]
+    /// Builds a BVH as it was before binning, by testing 5 evenly spaced split positions per axis,
+    /// the triangles with a vertex before the position going to the left child.
+    fn build_evenly_spaced(triangles: &mut [Padded<Triangle, 4>]) -> Vec<Padded<Bvh, 4>> {
+        let mut bvhs = vec![node(&*triangles, 0).into()];
+        split_evenly_spaced(&mut bvhs, triangles);
+        bvhs
+    }
+
+    /// Recursively splits the last node of `bvhs`, see [`build_evenly_spaced`].
+    fn split_evenly_spaced(bvhs: &mut Vec<Padded<Bvh, 4>>, triangles: &mut [Padded<Triangle, 4>]) {
+        let index = bvhs.len() - 1;
+        let bvh = *bvhs[index];
+        if triangles.len() <= 2 {
+            return;
+        }
+
+        let is_left = |triangle: &Padded<Triangle, 4>, axis: usize, position: f32| {
+            triangle
+                .vertices
+                .iter()
+                .any(|vertex| vertex[axis] < position)
+        };
+
+        let mut best_split = None;
+        for axis in 0..3 {
+            let delta = bvh.max_bound[axis] - bvh.min_bound[axis];
+            if delta <= f32::EPSILON * bvh.max_bound[axis].abs().max(1.0) {
+                continue;
+            }
+
+            for i in 1..=5_u8 {
+                let position = (f32::from(i) / 6.0).mul_add(delta, bvh.min_bound[axis]);
+                let left = node(triangles.iter().filter(|t| is_left(t, axis, position)), 0);
+                let right = node(triangles.iter().filter(|t| !is_left(t, axis, position)), 0);
+                if left.triangle_count == 0 || right.triangle_count == 0 {
+                    continue;
+                }
+
+                let cost = Bvh::bvh_cost(*left.min_bound, left.max_bound, left.triangle_count)
+                    + Bvh::bvh_cost(*right.min_bound, right.max_bound, right.triangle_count);
+                if cost < best_split.map_or(f64::INFINITY, |(_, _, best_cost)| best_cost) {
+                    best_split = Some((axis, position, cost));
+                }
+            }
+        }
+
+        let Some((axis, position, cost)) = best_split else {
+            return;
+        };
+        if !Bvh::should_split(&bvh, cost) {
+            return;
+        }
+
+        let mut left_count = 0;
+        for i in 0..triangles.len() {
+            if is_left(&triangles[i], axis, position) {
+                triangles.swap(i, left_count);
+                left_count += 1;
+            }
+        }
+        let (left, right) = triangles.split_at_mut(left_count);
+        let right_offset = bvh.triangle_offset + u32::try_from(left_count).unwrap();
+
+        bvhs[index].left_offset = u32::try_from(bvhs.len()).unwrap();
+        bvhs.push(node(&*left, bvh.triangle_offset).into());
+        split_evenly_spaced(bvhs, left);
+        bvhs[index].right_offset = u32::try_from(bvhs.len()).unwrap();
+        bvhs.push(node(&*right, right_offset).into());
+        split_evenly_spaced(bvhs, right);
+    }
+
+    #[test]
+    /// Small nodes have empty bins, which must not prevent them from being split.
+    fn splits_nodes_with_empty_bins() {
+        let mut triangles = (0..8_u8)
+            .map(|i| {
+                let x = f32::from(i) * 10.0;
+                triangle([[x, 0.0, 0.0], [x + 1.0, 0.0, 0.0], [x, 1.0, 0.0]])
+            })
+            .collect::<Vec<_>>();
+
+        let mut bvhs = Vec::new();
+        Bvh::build(&mut bvhs, &mut triangles, 0);
+
+        assert_ne!(bvhs[0].left_offset, 0, "the root was not split");
+        assert!(bvhs
+            .iter()
+            .filter(|bvh| bvh.left_offset == 0)
+            .all(|bvh| bvh.triangle_count <= 2));
+    }
+
+    #[test]
+    /// The binned SAH finds splits at least as good as testing 5 evenly spaced positions.
+    fn binned_sah_beats_evenly_spaced_splits() {
+        let mut triangles = scattered_triangles(2000);
+        let evenly_spaced = build_evenly_spaced(&mut triangles.clone());
+
+        let mut bvhs = Vec::new();
+        Bvh::build(&mut bvhs, &mut triangles, 0);
+
+        assert!(traversal_cost(&bvhs) <= traversal_cost(&evenly_spaced));
+    }
+
+    #[test]
+    /// Building the top levels in parallel gives the same tree and triangle order as building it on one thread.
+    fn parallel_build_matches_serial_build() {
+        // The mesh must be large enough for the root and its children to be split in parallel
+        let mut serial_triangles = scattered_triangles(4 * PARALLEL_THRESHOLD);
+        let mut parallel_triangles = serial_triangles.clone();
+
+        let mut serial = vec![node(&*serial_triangles, 0).into()];
+        Bvh::split(&mut serial, &mut serial_triangles);
+
+        let mut parallel = Vec::new();
+        Bvh::build(&mut parallel, &mut parallel_triangles, 0);
+
+        assert!(parallel.len() > 1, "the root was not split");
+        assert_eq!(nodes(&parallel), nodes(&serial));
+        assert_eq!(vertices(&parallel_triangles), vertices(&serial_triangles));
+    }
+
+    #[test]
+    /// A single triangle, even a degenerate one, builds a single leaf with finite bounds.
+    fn single_triangle_builds_a_leaf() {
+        for vertices in [
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[2.0, 3.0, 4.0]; 3],
+        ] {
+            let mut triangles = vec![triangle(vertices)];
+
+            let mut bvhs = Vec::new();
+            Bvh::build(&mut bvhs, &mut triangles, 7);
+
+            assert_eq!(nodes(&bvhs), nodes(&[node(&triangles, 7).into()]));
+            assert!(bvhs[0]
+                .min_bound
+                .iter()
+                .chain(&bvhs[0].max_bound)
+                .all(|bound| bound.is_finite()));
+        }
+    }
+
+    #[test]
+    /// Coincident triangles cannot be separated, and build a single leaf instead of recursing forever.
+    fn coincident_triangles_build_a_leaf() {
+        for (vertices, count) in [
+            ([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]], 100),
+            ([[1.0, 2.0, 3.0]; 3], 100),
+            // Above the threshold, so that the parallel build is covered too
+            (
+                [[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+                PARALLEL_THRESHOLD + 1,
+            ),
+        ] {
+            let mut triangles = vec![triangle(vertices); count];
+
+            let mut bvhs = Vec::new();
+            Bvh::build(&mut bvhs, &mut triangles, 0);
+
+            assert_eq!(nodes(&bvhs), nodes(&[node(&triangles, 0).into()]));
+            assert!(Bvh::bvh_cost(
+                *bvhs[0].min_bound,
+                bvhs[0].max_bound,
+                bvhs[0].triangle_count
+            )
+            .is_finite());
+        }
+    }
+
+    #[test]
+    #[ignore = "benchmark, needs the sample models of the assets directory"]
+    #[allow(clippy::print_stdout, clippy::use_debug)]
+    /// Compares the BVHs of the sample models built with the binned SAH
+    /// to the ones built by testing 5 evenly spaced positions, as before binning.
+    ///
+    /// Run with `cargo test --release -p rt-engine benchmark -- --ignored --nocapture`.
+    fn benchmark_sample_models() {
+        for path in [
+            "assets/models/cottage/cottage_FREE.obj",
+            "assets/models/gun/Pistol_02.obj",
+        ] {
+            let full_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join(path);
+            let (models, _) = tobj::load_obj(&full_path, &tobj::GPU_LOAD_OPTIONS)
+                .unwrap_or_else(|error| panic!("failed to load {path}: {error}"));
+            let triangles = models
+                .iter()
+                .flat_map(|model| {
+                    let mesh = &model.mesh;
+                    mesh.indices.chunks_exact(3).map(|face| {
+                        triangle(std::array::from_fn(|i| {
+                            let index = face[i] as usize * 3;
+                            [
+                                mesh.positions[index],
+                                mesh.positions[index + 1],
+                                mesh.positions[index + 2],
+                            ]
+                        }))
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut binned_triangles = triangles.clone();
+            let binned_start = std::time::Instant::now();
+            let mut binned = Vec::new();
+            Bvh::build(&mut binned, &mut binned_triangles, 0);
+            let binned_time = binned_start.elapsed();
+
+            let mut evenly_spaced_triangles = triangles.clone();
+            let evenly_spaced_start = std::time::Instant::now();
+            let evenly_spaced = build_evenly_spaced(&mut evenly_spaced_triangles);
+            let evenly_spaced_time = evenly_spaced_start.elapsed();
+
+            println!(
+                "{path}, {} triangles:\n  binned SAH: traversal cost {:.2}, {} nodes, built in {binned_time:?}\n  5 evenly spaced positions: traversal cost {:.2}, {} nodes, built in {evenly_spaced_time:?}",
+                triangles.len(),
+                traversal_cost(&binned),
+                binned.len(),
+                traversal_cost(&evenly_spaced),
+                evenly_spaced.len(),
+            );
+        }
     }
 }
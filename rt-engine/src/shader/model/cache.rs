@@ -0,0 +1,379 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::shader::{
+    source::{Bvh, Triangle},
+    Transform, Winding,
+};
+use vulkano::padded::Padded;
+
+/// The bytes every cache file starts with.
+const MAGIC: [u8; 4] = *b"RTBC";
+/// The version of the format of the cache files.
+///
+/// It must be incremented whenever the format, the triangles or the BVH construction change,
+/// so that older caches are rebuilt.
+const VERSION: u32 = 5;
+
+/// The size of the header of a cache file without its material libraries, in bytes.
+const HEADER_SIZE: usize = 4 + 4 + 1 + 6 * 4 + 4 + 4 + 4;
+/// The size of a serialized triangle, in bytes.
+const TRIANGLE_SIZE: usize = (3 * 3 + 3 + 3 * 3 + 3 * 2 + 1) * 4;
+/// The size of a serialized BVH node, in bytes.
+const BVH_SIZE: usize = (3 + 3 + 4) * 4;
+
+/// The triangles and BVH nodes of a model, as they are stored in its cache.
+pub(super) struct Cached {
+    /// The triangles of the model, whose material index is relative to the one of the model,
+    /// or [`super::load::NO_MATERIAL`] for the faces without material.
+    pub triangles: Vec<Padded<Triangle, 4>>,
+    /// The BVH nodes of the model, whose offsets are relative to the first triangle and node of the model.
+    pub bvhs: Vec<Padded<Bvh, 4>>,
+}
+
+#[must_use]
+/// Returns the path of the cache of the model at the given path.
+///
+/// The extension of the model is kept, so that models differing only by their extension
+/// have different caches.
+fn cache_path(src: &str) -> PathBuf {
+    let mut path = PathBuf::from(src).into_os_string();
+    path.push(".bvhcache");
+    path.into()
+}
+
+#[must_use]
+/// Returns the byte identifying the given winding in the header of the cache files.
+const fn winding_id(winding: Winding) -> u8 {
+    match winding {
+        Winding::CounterClockwise => 0,
+        Winding::Clockwise => 1,
+        Winding::Auto => 2,
+    }
+}
+
+#[must_use]
+/// Returns the rotation and the scale of the given transform, which are baked into the cached vertices.
+const fn baked_transform(transform: &Transform) -> [f32; 6] {
+    let [rx, ry, rz] = transform.rotation_euler;
+    let [sx, sy, sz] = transform.scale;
+    [rx, ry, rz, sx, sy, sz]
+}
+
+#[must_use]
+/// Returns the modification time of the file at the given path, in seconds and nanoseconds since the Unix epoch.
+///
+/// Files whose modification time cannot be read, such as missing ones, give `u64::MAX` seconds.
+fn modified_time(path: &Path) -> (u64, u32) {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or((u64::MAX, u32::MAX), |time| {
+            (time.as_secs(), time.subsec_nanos())
+        })
+}
+
+#[must_use]
+/// Reads the cache of the model at the given path, loaded with the given winding and transform.
+///
+/// Returns `None` if there is no cache, if it is older than the model, if one of the material libraries
+/// of the model changed since it was written, as they give the material indices of the faces,
+/// if it was written by another version or for another winding, rotation or scale, or if it is invalid.
+pub(super) fn read(src: &str, winding: Winding, transform: &Transform) -> Option<Cached> {
+    let path = cache_path(src);
+    let modified = |file: &Path| std::fs::metadata(file).and_then(|metadata| metadata.modified());
+    let (Ok(source_time), Ok(cache_time)) = (modified(Path::new(src)), modified(&path)) else {
+        return None;
+    };
+    if cache_time <= source_time {
+        tracing::debug!("BVH cache of {src} is stale");
+        return None;
+    }
+
+    let data = std::fs::read(&path)
+        .map_err(|error| tracing::warn!("Failed to read BVH cache of {src}: {error}"))
+        .ok()?;
+    let directory = Path::new(src).parent().unwrap_or_else(|| Path::new(""));
+    let cached = parse(&data, winding, transform, directory);
+    if cached.is_none() {
+        tracing::debug!("BVH cache of {src} is outdated or invalid");
+    }
+    cached
+}
+
+#[must_use]
+/// Parses the content of a cache file, returning `None` if it does not match the current format,
+/// the given winding and transform, and the modification times of the material libraries,
+/// which are relative to the given directory.
+fn parse(data: &[u8], winding: Winding, transform: &Transform, directory: &Path) -> Option<Cached> {
+    let mut reader = Reader(data);
+    if reader.take(4)? != MAGIC
+        || reader.u32()? != VERSION
+        || reader.take(1)? != [winding_id(winding)]
+    {
+        return None;
+    }
+    // Compared bit by bit, as any difference changes the vertices
+    for value in baked_transform(transform) {
+        if reader.f32()?.to_bits() != value.to_bits() {
+            return None;
+        }
+    }
+    // The material libraries, whose materials give the material indices of the faces
+    for _ in 0..reader.u32()? {
+        let name_length = reader.u32()? as usize;
+        let name = std::str::from_utf8(reader.take(name_length)?).ok()?;
+        if (reader.u64()?, reader.u32()?) != modified_time(&directory.join(name)) {
+            return None;
+        }
+    }
+
+    let triangle_count = reader.u32()? as usize;
+    let bvh_count = reader.u32()? as usize;
+    if reader.0.len() != triangle_count * TRIANGLE_SIZE + bvh_count * BVH_SIZE {
+        return None;
+    }
+
+    let triangles = (0..triangle_count)
+        .map(|_| {
+            Some(
+                Triangle {
+                    vertices: [
+                        reader.vec3()?.into(),
+                        reader.vec3()?.into(),
+                        reader.vec3()?.into(),
+                    ],
+                    normal: reader.vec3()?.into(),
+                    vertex_normals: [
+                        reader.vec3()?.into(),
+                        reader.vec3()?.into(),
+                        reader.vec3()?.into(),
+                    ],
+                    uv: [reader.vec2()?, reader.vec2()?, reader.vec2()?],
+                    material_id: reader.u32()?,
+                }
+                .into(),
+            )
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let bvhs = (0..bvh_count)
+        .map(|_| {
+            Some(
+                Bvh {
+                    min_bound: reader.vec3()?.into(),
+                    max_bound: reader.vec3()?,
+                    left_offset: reader.u32()?,
+                    right_offset: reader.u32()?,
+                    triangle_offset: reader.u32()?,
+                    triangle_count: reader.u32()?,
+                }
+                .into(),
+            )
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(Cached { triangles, bvhs })
+}
+
+/// Writes the cache of the model at the given path, loaded with the given winding and transform.
+///
+/// The triangles and nodes must be the ones of the model alone, as they are stored in [`Cached`].
+/// A cache that cannot be written is only reported, the model being rebuilt at the next load.
+pub(super) fn write(src: &str, winding: Winding, transform: &Transform, cached: &Cached) {
+    let path = cache_path(src);
+    let libraries = super::load::material_libraries(Path::new(src));
+    let directory = Path::new(src).parent().unwrap_or_else(|| Path::new(""));
+
+    let mut data = Vec::with_capacity(
+        HEADER_SIZE + cached.triangles.len() * TRIANGLE_SIZE + cached.bvhs.len() * BVH_SIZE,
+    );
+    data.extend(MAGIC);
+    data.extend(VERSION.to_le_bytes());
+    data.push(winding_id(winding));
+    for value in baked_transform(transform) {
+        data.extend(value.to_le_bytes());
+    }
+    data.extend(
+        u32::try_from(libraries.len())
+            .expect("too many material libraries")
+            .to_le_bytes(),
+    );
+    for library in &libraries {
+        let (seconds, nanoseconds) = modified_time(&directory.join(library));
+        data.extend(
+            u32::try_from(library.len())
+                .expect("material library name too long")
+                .to_le_bytes(),
+        );
+        data.extend(library.as_bytes());
+        data.extend(seconds.to_le_bytes());
+        data.extend(nanoseconds.to_le_bytes());
+    }
+    data.extend(
+        u32::try_from(cached.triangles.len())
+            .expect("too many triangles")
+            .to_le_bytes(),
+    );
+    data.extend(
+        u32::try_from(cached.bvhs.len())
+            .expect("too many BVHs")
+            .to_le_bytes(),
+    );
+
+    let push_floats = |bytes: &mut Vec<u8>, floats: &[f32]| {
+        for float in floats {
+            bytes.extend(float.to_le_bytes());
+        }
+    };
+    for triangle in &cached.triangles {
+        for vertex in &triangle.vertices {
+            push_floats(&mut data, &**vertex);
+        }
+        push_floats(&mut data, &*triangle.normal);
+        for normal in &triangle.vertex_normals {
+            push_floats(&mut data, &**normal);
+        }
+        for uv in &triangle.uv {
+            push_floats(&mut data, uv);
+        }
+        data.extend(triangle.material_id.to_le_bytes());
+    }
+    for bvh in &cached.bvhs {
+        push_floats(&mut data, &*bvh.min_bound);
+        push_floats(&mut data, &bvh.max_bound);
+        for value in [
+            bvh.left_offset,
+            bvh.right_offset,
+            bvh.triangle_offset,
+            bvh.triangle_count,
+        ] {
+            data.extend(value.to_le_bytes());
+        }
+    }
+
+    // A partially written cache does not have the size given by its header, and is rebuilt
+    match std::fs::File::create(&path).and_then(|mut file| file.write_all(&data)) {
+        Ok(()) => tracing::trace!("BVH cache written to {}", path.display()),
+        Err(error) => tracing::warn!("Failed to write BVH cache {}: {error}", path.display()),
+    }
+}
+
+/// A cursor over the little-endian content of a cache file.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    #[must_use]
+    /// Returns the next `count` bytes, or `None` if there are not enough left.
+    const fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.0.len() < count {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    #[must_use]
+    /// Reads the next unsigned integer.
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)?.try_into().ok().map(u32::from_le_bytes)
+    }
+
+    #[must_use]
+    /// Reads the next 64-bit unsigned integer.
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8)?.try_into().ok().map(u64::from_le_bytes)
+    }
+
+    #[must_use]
+    /// Reads the next float.
+    fn f32(&mut self) -> Option<f32> {
+        self.take(4)?.try_into().ok().map(f32::from_le_bytes)
+    }
+
+    #[must_use]
+    /// Reads the next two floats.
+    fn vec2(&mut self) -> Option<[f32; 2]> {
+        Some([self.f32()?, self.f32()?])
+    }
+
+    #[must_use]
+    /// Reads the next three floats.
+    fn vec3(&mut self) -> Option<[f32; 3]> {
+        Some([self.f32()?, self.f32()?, self.f32()?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sets the modification time of the file at the given path to `seconds` before now.
+    fn set_age(path: &Path, seconds: u64) {
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| {
+                file.set_modified(
+                    std::time::SystemTime::now() - std::time::Duration::from_secs(seconds),
+                )
+            })
+            .unwrap();
+    }
+
+    #[test]
+    /// Changing, removing or adding back a material library of a model makes its cache stale,
+    /// as the material indices of the faces depend on it.
+    fn material_library_changes_invalidate_the_cache() {
+        let directory =
+            std::env::temp_dir().join(format!("rt-engine-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let model = directory.join("model.obj");
+        let library = directory.join("model.mtl");
+        std::fs::write(
+            &model,
+            "mtllib model.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+        )
+        .unwrap();
+        std::fs::write(&library, "newmtl red\nKd 1 0 0\n").unwrap();
+        // The cache must be strictly newer than the model, whatever the resolution of the file times
+        set_age(&model, 60);
+        set_age(&library, 60);
+
+        let src = model.to_str().unwrap();
+        let transform = Transform::default();
+        let is_cached = || read(src, Winding::Auto, &transform).is_some();
+        let write_cache = || {
+            let cached = Cached {
+                triangles: Vec::new(),
+                bvhs: Vec::new(),
+            };
+            write(src, Winding::Auto, &transform, &cached);
+        };
+
+        write_cache();
+        let fresh = is_cached();
+
+        set_age(&library, 30);
+        let changed = is_cached();
+
+        write_cache();
+        std::fs::remove_file(&library).unwrap();
+        let removed = is_cached();
+
+        write_cache();
+        std::fs::write(&library, "newmtl red\nKd 1 0 0\n").unwrap();
+        let added = is_cached();
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(fresh, "the cache was not read back");
+        assert!(!changed, "a changed material library was not detected");
+        assert!(!removed, "a removed material library was not detected");
+        assert!(!added, "an added material library was not detected");
+    }
+}
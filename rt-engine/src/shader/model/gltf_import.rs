@@ -0,0 +1,192 @@
+use super::ModelLoadError;
+
+/// A column-major 4x4 matrix, as used by glTF.
+type Matrix = [[f32; 4]; 4];
+
+/// The identity matrix.
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Loads the meshes of the default scene of a `.gltf` or `.glb` file,
+/// with the transforms of the node hierarchy baked into their vertices.
+///
+/// The meshes are converted to `tobj` models, so that they go through the same path as `.obj` files.
+///
+/// ## Errors
+///
+/// This function returns [`ModelLoadError::NotFound`] if the file cannot be opened,
+/// and [`ModelLoadError::Parse`] if it cannot be parsed or contains no scene.
+pub(super) fn load(src: &str) -> Result<Vec<tobj::Model>, ModelLoadError> {
+    let (document, buffers, _images) = gltf::import(src).map_err(|error| match error {
+        gltf::Error::Io(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            ModelLoadError::NotFound
+        }
+        error => ModelLoadError::Parse(error.to_string()),
+    })?;
+
+    let Some(scene) = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    else {
+        return Err(ModelLoadError::Parse(String::from(
+            "the file contains no scene",
+        )));
+    };
+
+    let mut models = Vec::new();
+    for node in scene.nodes() {
+        load_node(&node, IDENTITY, &buffers, &mut models);
+    }
+
+    Ok(models)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+/// Loads the meshes of a node and of its children, given the transform of its parent.
+fn load_node(
+    node: &gltf::Node,
+    parent_transform: Matrix,
+    buffers: &[gltf::buffer::Data],
+    models: &mut Vec<tobj::Model>,
+) {
+    let transform = multiply(&parent_transform, &node.transform().matrix());
+
+    if let Some(mesh) = node.mesh() {
+        // A mirroring transform turns counter-clockwise triangles into clockwise ones
+        let mirrored = determinant(&transform) < 0.0;
+
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                tracing::warn!(
+                    "Skipping primitive of mesh {:?} in {:?} mode, only triangles are supported",
+                    mesh.name(),
+                    primitive.mode()
+                );
+                continue;
+            }
+
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+
+            let positions = positions
+                .flat_map(|position| transform_point(&transform, position))
+                .collect::<Vec<_>>();
+            let vertex_count = positions.len() / 3;
+
+            let normals = reader
+                .read_normals()
+                .map(|normals| {
+                    normals
+                        .flat_map(|normal| transform_normal(&transform, normal))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            // glTF texture coordinates start from the top of the image, unlike OBJ ones
+            let texcoords = reader.read_tex_coords(0).map_or_else(
+                || vec![0.0; vertex_count * 2],
+                |texcoords| {
+                    texcoords
+                        .into_f32()
+                        .flat_map(|[u, v]| [u, 1.0 - v])
+                        .collect()
+                },
+            );
+
+            let mut indices = reader.read_indices().map_or_else(
+                || (0..vertex_count as u32).collect::<Vec<_>>(),
+                |indices| indices.into_u32().collect(),
+            );
+            if mirrored {
+                for face in indices.chunks_exact_mut(3) {
+                    face.swap(1, 2);
+                }
+            }
+
+            models.push(tobj::Model::new(
+                tobj::Mesh {
+                    positions,
+                    normals,
+                    texcoords,
+                    indices,
+                    ..Default::default()
+                },
+                mesh.name().unwrap_or_default().to_owned(),
+            ));
+        }
+    }
+
+    for child in node.children() {
+        load_node(&child, transform, buffers, models);
+    }
+}
+
+#[must_use]
+/// Returns the product of two column-major matrices.
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    std::array::from_fn(|column| {
+        std::array::from_fn(|row| (0..4).map(|k| a[k][row] * b[column][k]).sum())
+    })
+}
+
+#[must_use]
+/// Applies the given transform to a point.
+fn transform_point(transform: &Matrix, point: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|row| {
+        transform[2][row].mul_add(
+            point[2],
+            transform[1][row].mul_add(
+                point[1],
+                transform[0][row].mul_add(point[0], transform[3][row]),
+            ),
+        )
+    })
+}
+
+#[must_use]
+/// Applies the given transform to a normal.
+///
+/// Normals are transformed by the cofactor matrix of the linear part of the transform,
+/// which keeps them orthogonal to the surface under non-uniform scaling.
+fn transform_normal(transform: &Matrix, normal: [f32; 3]) -> [f32; 3] {
+    let [a, b, c] = linear_columns(transform);
+    let (bc, ca, ab) = (cross(b, c), cross(c, a), cross(a, b));
+    // The cofactor matrix reverses normals for mirroring transforms
+    let sign = determinant(transform).signum();
+
+    std::array::from_fn(|i| {
+        sign * ab[i].mul_add(normal[2], ca[i].mul_add(normal[1], bc[i] * normal[0]))
+    })
+}
+
+#[must_use]
+/// Returns the determinant of the linear part of the given transform.
+fn determinant(transform: &Matrix) -> f32 {
+    let [a, b, c] = linear_columns(transform);
+    let bc = cross(b, c);
+    a[2].mul_add(bc[2], a[0].mul_add(bc[0], a[1] * bc[1]))
+}
+
+#[must_use]
+#[inline]
+/// Returns the columns of the linear part of the given transform.
+fn linear_columns(transform: &Matrix) -> [[f32; 3]; 3] {
+    std::array::from_fn(|column| std::array::from_fn(|row| transform[column][row]))
+}
+
+#[must_use]
+#[inline]
+/// Returns the cross product of two 3D vectors.
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1].mul_add(b[2], -(a[2] * b[1])),
+        a[2].mul_add(b[0], -(a[0] * b[2])),
+        a[0].mul_add(b[1], -(a[1] * b[0])),
+    ]
+}
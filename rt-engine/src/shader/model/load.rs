@@ -1,105 +1,728 @@
-use crate::shader::source::{Bvh, Model, Triangle};
+use crate::shader::{
+    model::{ModelLoadError, ModelLoadStats},
+    source::{Bvh, Material, Model, Triangle},
+    Transform, Winding,
+};
+use std::path::{Path, PathBuf};
 use vulkano::padded::Padded;
 
+/// The material index of the cached faces without material, such as the faces before the first `usemtl`
+/// or using a material that could not be loaded, which use the default material of their model.
+pub(super) const NO_MATERIAL: u32 = u32::MAX;
+
 impl Model {
-    #[must_use]
-    /// Load a model from the given `.obj` source file
+    /// Load a model from the given `.obj` source file, or `.gltf` and `.glb` files with the `gltf` feature.
+    ///
+    /// The vertices are scaled and rotated by `transform` on load, while its translation
+    /// is applied by the shader, the triangles being stored relative to it.
+    ///
+    /// Faces using the `k`-th material of the MTL files of the model use the material at index `material_id + k`
+    /// in the materials of the scene, and faces without material, or whose material could not be loaded,
+    /// the one at index `default_material_id`.
+    ///
+    /// Also returns statistics about the loading of the model.
+    ///
+    /// The triangles and the BVH are cached next to the source file, in a file with the additional
+    /// extension `.bvhcache`. As long as the cache is newer than the source file, its material libraries
+    /// are unchanged, and it was written with the same winding, rotation and scale by the same version
+    /// of the engine, the model is loaded from it instead of being parsed and built again.
+    /// A cache that cannot be written is only reported.
+    ///
+    /// Vertices without texture coordinates get null ones, and meshes without vertex normals
+    /// are flat shaded with the normals of their faces, so models only need positions.
+    /// Materials that cannot be loaded are only reported, as they are given by the scene.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns a [`ModelLoadError`] if the file cannot be read or parsed,
+    /// if a face references a vertex without position, or if a glTF file is given without the `gltf` feature.
     ///
     /// ## Panics
     ///
-    /// This function panics if the model cannot be loaded, typically due to an invalid `.obj` file.
+    /// This function panics if the scene contains more than `u32::MAX` triangles or BVH nodes,
+    /// or if the model has more than `u32::MAX` materials.
     pub fn load(
-        triangles: &mut Vec<Padded<Triangle, 8>>,
+        triangles: &mut Vec<Padded<Triangle, 4>>,
         bvhs: &mut Vec<Padded<Bvh, 4>>,
         src: &str,
-        position: &[f32; 3],
-    ) -> Self {
+        transform: &Transform,
+        material_id: u32,
+        default_material_id: u32,
+        winding: Winding,
+    ) -> Result<(Self, ModelLoadStats), ModelLoadError> {
         let triangle_offset = triangles.len();
         let bvh_index = u32::try_from(bvhs.len()).expect("too many BVHs");
 
         let start = std::time::Instant::now();
 
-        let (models, materials) =
-            tobj::load_obj(src, &tobj::GPU_LOAD_OPTIONS).expect("failed to load OBJ file");
-        // TODO: Materials
-        let _materials = materials.expect("failed to load materials");
+        if let Some(cached) = super::cache::read(src, winding, transform) {
+            return Ok(Self::from_cache(
+                triangles,
+                bvhs,
+                cached,
+                src,
+                &transform.translation,
+                material_id,
+                default_material_id,
+                start,
+            ));
+        }
+
+        let path = Path::new(src);
+        let is_gltf = path
+            .extension()
+            .and_then(std::ffi::OsStr::to_str)
+            .is_some_and(|extension| {
+                extension.eq_ignore_ascii_case("gltf") || extension.eq_ignore_ascii_case("glb")
+            });
+
+        let models = if is_gltf {
+            #[cfg(feature = "gltf")]
+            {
+                super::gltf_import::load(src)?
+            }
+            #[cfg(not(feature = "gltf"))]
+            {
+                return Err(ModelLoadError::MissingFeature("gltf"));
+            }
+        } else {
+            let file = std::fs::File::open(path).map_err(|_| ModelLoadError::NotFound)?;
+            let directory = path.parent().unwrap_or_else(|| Path::new(""));
+            // Only the indices of the materials are needed, their values being loaded on their own
+            // by `Model::load_materials`, as models loaded from the cache are not parsed.
+            // tobj reads several libraries on a single line as a single file name
+            let (models, _) = tobj::load_obj_buf(
+                &mut std::io::BufReader::new(file),
+                &tobj::GPU_LOAD_OPTIONS,
+                |libraries| {
+                    let (materials, names) =
+                        load_libraries(directory, libraries.to_string_lossy().split_whitespace());
+                    Ok((materials, names.into_iter().collect()))
+                },
+            )
+            .map_err(|error| ModelLoadError::Parse(error.to_string()))?;
+            models
+        };
+
+        let clockwise = match winding {
+            Winding::CounterClockwise => false,
+            Winding::Clockwise => true,
+            Winding::Auto => {
+                let clockwise = Self::is_clockwise(&models);
+                tracing::trace!(
+                    "Detected {} winding for {src}",
+                    if clockwise {
+                        "clockwise"
+                    } else {
+                        "counter-clockwise"
+                    }
+                );
+                clockwise
+            }
+        };
+
+        // Swapping two vertices turns a clockwise triangle into a counter-clockwise one,
+        // and mirroring the model turns counter-clockwise triangles into clockwise ones
+        let swap_vertices = clockwise != transform.is_mirroring();
+        let linear_part = transform.linear_part();
 
         for model in &models {
             let mesh = &model.mesh;
+            let vertex_count = mesh.positions.len() / 3;
+            if mesh
+                .indices
+                .iter()
+                .any(|&index| index as usize >= vertex_count)
+            {
+                return Err(ModelLoadError::MissingAttribute("position"));
+            }
+            if mesh.texcoords.len() < vertex_count * 2 {
+                tracing::debug!(
+                    "Mesh {} of {src} lacks texture coordinates, null ones are used",
+                    model.name
+                );
+            }
+            let has_normals = mesh.normals.len() >= vertex_count * 3;
+            if !has_normals {
+                tracing::debug!(
+                    "Mesh {} of {src} lacks vertex normals, it is flat shaded",
+                    model.name
+                );
+            }
+            // The normals are computed from the transformed vertices, which gives the same direction
+            // as transforming the normals by the inverse-transpose, even with a non-uniform scale
+            let positions = mesh
+                .positions
+                .chunks_exact(3)
+                .map(|position| {
+                    linear_part.map(|row| {
+                        row[2].mul_add(
+                            position[2],
+                            row[0].mul_add(position[0], row[1] * position[1]),
+                        )
+                    })
+                })
+                .collect::<Vec<_>>();
+            // Normals are transformed by the inverse-transpose of the linear part, which is the rotation
+            // divided by the scale, or the linear part divided twice by the scale
+            let normals = if has_normals {
+                mesh.normals
+                    .chunks_exact(3)
+                    .map(|normal| {
+                        let unscaled: [f32; 3] = std::array::from_fn(|i| {
+                            normal[i] / (transform.scale[i] * transform.scale[i])
+                        });
+                        let mut transformed = linear_part.map(|row| {
+                            row[2].mul_add(
+                                unscaled[2],
+                                row[0].mul_add(unscaled[0], row[1] * unscaled[1]),
+                            )
+                        });
+                        normalize(&mut transformed).then_some(transformed)
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                Vec::new()
+            };
+            let uv = |i: usize| {
+                mesh.texcoords
+                    .get(i * 2..i * 2 + 2)
+                    .map_or([0.0; 2], |uv| [uv[0], uv[1]])
+            };
+
+            // The indices are relative to the model until the model is cached
+            let triangle_material_id = mesh.material_id.map_or(NO_MATERIAL, |id| {
+                u32::try_from(id).expect("too many materials")
+            });
             for i in (0..mesh.indices.len()).step_by(3) {
                 let a = mesh.indices[i] as usize;
-                let b = mesh.indices[i + 1] as usize;
-                let c = mesh.indices[i + 2] as usize;
+                let (b, c) = if swap_vertices {
+                    (mesh.indices[i + 2] as usize, mesh.indices[i + 1] as usize)
+                } else {
+                    (mesh.indices[i + 1] as usize, mesh.indices[i + 2] as usize)
+                };
+
+                let (pa, pb, pc) = (positions[a], positions[b], positions[c]);
+                let normal = {
+                    let ab = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+                    let ac = [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]];
+                    [
+                        ab[1].mul_add(ac[2], -(ab[2] * ac[1])),
+                        ab[2].mul_add(ac[0], -(ab[0] * ac[2])),
+                        ab[0].mul_add(ac[1], -(ab[1] * ac[0])),
+                    ]
+                };
+                // Vertices without a valid normal use the one of the face
+                let face_normal = {
+                    let mut face_normal = normal;
+                    normalize(&mut face_normal);
+                    face_normal
+                };
+                let vertex_normal = |vertex: usize| {
+                    normals
+                        .get(vertex)
+                        .copied()
+                        .flatten()
+                        .unwrap_or(face_normal)
+                };
 
                 let triangle = Triangle {
-                    vertices: [
-                        [
-                            mesh.positions[a * 3] + position[0],
-                            mesh.positions[a * 3 + 1] + position[1],
-                            mesh.positions[a * 3 + 2] + position[2],
-                        ]
-                        .into(),
-                        [
-                            mesh.positions[b * 3] + position[0],
-                            mesh.positions[b * 3 + 1] + position[1],
-                            mesh.positions[b * 3 + 2] + position[2],
-                        ]
-                        .into(),
-                        [
-                            mesh.positions[c * 3] + position[0],
-                            mesh.positions[c * 3 + 1] + position[1],
-                            mesh.positions[c * 3 + 2] + position[2],
-                        ]
-                        .into(),
-                    ],
-                    normal: {
-                        let ab = [
-                            mesh.positions[b * 3] - mesh.positions[a * 3],
-                            mesh.positions[b * 3 + 1] - mesh.positions[a * 3 + 1],
-                            mesh.positions[b * 3 + 2] - mesh.positions[a * 3 + 2],
-                        ];
-                        let ac = [
-                            mesh.positions[c * 3] - mesh.positions[a * 3],
-                            mesh.positions[c * 3 + 1] - mesh.positions[a * 3 + 1],
-                            mesh.positions[c * 3 + 2] - mesh.positions[a * 3 + 2],
-                        ];
-                        [
-                            ab[1].mul_add(ac[2], -(ab[2] * ac[1])),
-                            ab[2].mul_add(ac[0], -(ab[0] * ac[2])),
-                            ab[0].mul_add(ac[1], -(ab[1] * ac[0])),
-                        ]
-                    }
-                    .into(),
-                    uv: [
-                        [mesh.texcoords[a * 2], mesh.texcoords[a * 2 + 1]],
-                        [mesh.texcoords[b * 2], mesh.texcoords[b * 2 + 1]],
-                        [mesh.texcoords[c * 2], mesh.texcoords[c * 2 + 1]],
+                    vertices: [pa.into(), pb.into(), pc.into()],
+                    normal: normal.into(),
+                    vertex_normals: [
+                        vertex_normal(a).into(),
+                        vertex_normal(b).into(),
+                        vertex_normal(c).into(),
                     ],
+                    uv: [uv(a), uv(b), uv(c)],
+                    material_id: triangle_material_id,
                 };
 
                 triangles.push(triangle.into());
             }
         }
 
+        let parse_time = start.elapsed();
+        let bvh_start = std::time::Instant::now();
+
         Bvh::build(
             bvhs,
             &mut triangles[triangle_offset..],
             u32::try_from(triangle_offset).expect("too many triangles"),
         );
 
-        let bvh_count = u32::try_from(bvhs.len()).expect("too many BVHs") - bvh_index;
+        let bvh_build_time = bvh_start.elapsed();
+
+        super::cache::write(
+            src,
+            winding,
+            transform,
+            &Self::cached(
+                &triangles[triangle_offset..],
+                &bvhs[bvh_index as usize..],
+                u32::try_from(triangle_offset).expect("too many triangles"),
+                bvh_index,
+            ),
+        );
+        resolve_materials(
+            &mut triangles[triangle_offset..],
+            material_id,
+            default_material_id,
+        );
+
+        let stats = ModelLoadStats {
+            path: src.to_owned(),
+            parse_time,
+            bvh_build_time,
+            triangle_count: triangles.len() - triangle_offset,
+            bvh_node_count: bvhs.len() - bvh_index as usize,
+        };
 
         tracing::trace!(
-            "Model loaded in {:?} with {} triangles and {} BVH nodes",
+            "Model loaded in {:?} ({:?} parsing, {:?} building the BVH) with {} triangles and {} BVH nodes",
             start.elapsed(),
-            triangles.len() - triangle_offset,
-            bvh_count
+            stats.parse_time,
+            stats.bvh_build_time,
+            stats.triangle_count,
+            stats.bvh_node_count
         );
 
-        Self {
-            bvh_index,
-            // TODO: Material ID
-            material_id: 0,
+        Ok((
+            Self {
+                translation: transform.translation,
+                bvh_index,
+                basis: super::placement::identity(),
+                inverse_basis: super::placement::identity(),
+                material_id,
+            },
+            stats,
+        ))
+    }
+
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    /// Appends the cached triangles and BVH of a model to the given buffers,
+    /// offsetting their indices like [`Model::load`] would.
+    ///
+    /// ## Panics
+    ///
+    /// This function panics if the scene contains more than `u32::MAX` triangles or BVH nodes.
+    fn from_cache(
+        triangles: &mut Vec<Padded<Triangle, 4>>,
+        bvhs: &mut Vec<Padded<Bvh, 4>>,
+        mut cached: super::cache::Cached,
+        src: &str,
+        position: &[f32; 3],
+        material_id: u32,
+        default_material_id: u32,
+        start: std::time::Instant,
+    ) -> (Self, ModelLoadStats) {
+        let triangle_offset = u32::try_from(triangles.len()).expect("too many triangles");
+        let bvh_index = u32::try_from(bvhs.len()).expect("too many BVHs");
+
+        resolve_materials(&mut cached.triangles, material_id, default_material_id);
+        Bvh::relocate(&mut cached.bvhs, triangle_offset, bvh_index);
+
+        let stats = ModelLoadStats {
+            path: src.to_owned(),
+            parse_time: start.elapsed(),
+            bvh_build_time: std::time::Duration::ZERO,
+            triangle_count: cached.triangles.len(),
+            bvh_node_count: cached.bvhs.len(),
+        };
+        triangles.extend(cached.triangles);
+        bvhs.extend(cached.bvhs);
+
+        tracing::trace!(
+            "Model loaded from cache in {:?} with {} triangles and {} BVH nodes",
+            stats.parse_time,
+            stats.triangle_count,
+            stats.bvh_node_count
+        );
+
+        (
+            Self {
+                translation: *position,
+                bvh_index,
+                basis: super::placement::identity(),
+                inverse_basis: super::placement::identity(),
+                material_id,
+            },
+            stats,
+        )
+    }
+
+    #[must_use]
+    /// Returns the triangles and BVH of a model as they are cached,
+    /// with indices relative to the model instead of the buffers.
+    ///
+    /// `triangle_offset` and `bvh_index` are the indices of the first triangle and node of the model in the buffers,
+    /// whose material indices must not be resolved yet.
+    fn cached(
+        triangles: &[Padded<Triangle, 4>],
+        bvhs: &[Padded<Bvh, 4>],
+        triangle_offset: u32,
+        bvh_index: u32,
+    ) -> super::cache::Cached {
+        super::cache::Cached {
+            triangles: triangles.to_vec(),
+            bvhs: bvhs
+                .iter()
+                .map(|&bvh| {
+                    let mut bvh = bvh;
+                    bvh.triangle_offset -= triangle_offset;
+                    if bvh.left_offset != 0 {
+                        bvh.left_offset -= bvh_index;
+                        bvh.right_offset -= bvh_index;
+                    }
+                    bvh
+                })
+                .collect(),
         }
     }
+
+    #[must_use]
+    /// Returns whether the triangles of the models are given in clockwise order,
+    /// by comparing their face normals to the normals given in the file.
+    ///
+    /// Models without normals, or with normals for only some of their vertices,
+    /// are assumed to be in counter-clockwise order.
+    fn is_clockwise(models: &[tobj::Model]) -> bool {
+        let mut agreeing = 0_usize;
+        let mut disagreeing = 0_usize;
+
+        for model in models {
+            let mesh = &model.mesh;
+            if mesh.normals.len() < mesh.positions.len() {
+                continue;
+            }
+
+            let position = |i: usize| {
+                [
+                    mesh.positions[i * 3],
+                    mesh.positions[i * 3 + 1],
+                    mesh.positions[i * 3 + 2],
+                ]
+            };
+
+            for face in mesh.indices.chunks_exact(3) {
+                let [a, b, c] = [face[0] as usize, face[1] as usize, face[2] as usize];
+                let (pa, pb, pc) = (position(a), position(b), position(c));
+
+                let ab = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+                let ac = [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]];
+                let face_normal = [
+                    ab[1].mul_add(ac[2], -(ab[2] * ac[1])),
+                    ab[2].mul_add(ac[0], -(ab[0] * ac[2])),
+                    ab[0].mul_add(ac[1], -(ab[1] * ac[0])),
+                ];
+
+                let vertex_normal = |axis: usize| {
+                    mesh.normals[a * 3 + axis]
+                        + mesh.normals[b * 3 + axis]
+                        + mesh.normals[c * 3 + axis]
+                };
+                let alignment = face_normal[2].mul_add(
+                    vertex_normal(2),
+                    face_normal[0].mul_add(vertex_normal(0), face_normal[1] * vertex_normal(1)),
+                );
+
+                if alignment > 0.0 {
+                    agreeing += 1;
+                } else if alignment < 0.0 {
+                    disagreeing += 1;
+                }
+            }
+        }
+
+        disagreeing > agreeing
+    }
+
+    #[must_use]
+    /// Loads the materials of the MTL files referenced by the given `.obj` file,
+    /// in the order the faces of the model index them, with the paths of their diffuse textures.
+    ///
+    /// The diffuse color, the emitted color, the shininess, the dissolve and the index of refraction are read,
+    /// with the defaults of a light gray matte material. The diffuse textures (`map_Kd`) are resolved
+    /// against the directory of their MTL file, and are only given with the `image` feature,
+    /// which is needed to load them.
+    ///
+    /// Other models, files that cannot be read and MTL files that cannot be loaded give no materials,
+    /// the latter being reported.
+    pub(crate) fn load_materials(src: &str) -> Vec<(Material, Option<PathBuf>)> {
+        let path = Path::new(src);
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let libraries = material_libraries(path);
+        let (materials, _) = load_libraries(directory, libraries.iter().map(String::as_str));
+
+        if !materials.is_empty() {
+            tracing::trace!("{} materials loaded for {src}", materials.len());
+        }
+        materials
+            .iter()
+            .map(|material| {
+                let texture = material.diffuse_texture.as_ref().and_then(|texture| {
+                    if cfg!(feature = "image") {
+                        Some(PathBuf::from(texture))
+                    } else {
+                        tracing::warn!(
+                            "Texture {texture} of {src} ignored, textures require the `image` feature"
+                        );
+                        None
+                    }
+                });
+                (convert_material(material), texture)
+            })
+            .collect()
+    }
+}
+
+/// Replaces the material indices of the given triangles, relative to their model as they are cached,
+/// by the indices of their materials in the scene, as described by [`Model::load`].
+fn resolve_materials(
+    triangles: &mut [Padded<Triangle, 4>],
+    material_id: u32,
+    default_material_id: u32,
+) {
+    for triangle in triangles {
+        triangle.material_id = if triangle.material_id == NO_MATERIAL {
+            default_material_id
+        } else {
+            material_id.saturating_add(triangle.material_id)
+        };
+    }
+}
+
+/// Loads the given MTL libraries of a model, resolved against its directory like tobj does,
+/// and merges their materials in order, like tobj does for the libraries of different lines.
+///
+/// The diffuse textures of the materials are resolved against the directory of their library.
+/// Also returns the index of the material of each name.
+/// Libraries that cannot be loaded give no materials, so that the faces using them have none,
+/// and are reported.
+fn load_libraries<'a>(
+    directory: &Path,
+    libraries: impl IntoIterator<Item = &'a str>,
+) -> (Vec<tobj::Material>, Vec<(String, usize)>) {
+    let mut merged = Vec::new();
+    let mut names = Vec::new();
+    for library in libraries {
+        let library = directory.join(library);
+        match tobj::load_mtl(&library) {
+            Ok((mut materials, library_names)) => {
+                let library_directory = library.parent().unwrap_or_else(|| Path::new(""));
+                for texture in materials
+                    .iter_mut()
+                    .filter_map(|material| material.diffuse_texture.as_mut())
+                {
+                    // Options such as `-bm 0.5` come before the file name, which is then the last word
+                    let name = if texture.starts_with('-') {
+                        texture.split_whitespace().last().unwrap_or_default()
+                    } else {
+                        texture.as_str()
+                    };
+                    *texture = library_directory.join(name).to_string_lossy().into_owned();
+                }
+                let offset = merged.len();
+                names.extend(
+                    library_names
+                        .into_iter()
+                        .map(|(name, index)| (name, index + offset)),
+                );
+                merged.extend(materials);
+            }
+            Err(error) => {
+                tracing::warn!("Failed to load materials {}: {error}", library.display());
+            }
+        }
+    }
+    (merged, names)
+}
+
+#[must_use]
+/// Returns the names of the MTL libraries referenced by the `.obj` file at the given path, in order,
+/// as they are written in the file, relative to its directory.
+///
+/// A `mtllib` line can reference several libraries, separated by whitespace.
+/// Other models and files that cannot be read reference no libraries.
+pub(super) fn material_libraries(path: &Path) -> Vec<String> {
+    use std::io::BufRead;
+
+    if !path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("obj"))
+    {
+        return Vec::new();
+    }
+    let Ok(file) = std::fs::File::open(path) else {
+        return Vec::new();
+    };
+
+    std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .flat_map(|line| {
+            let mut words = line.split_whitespace();
+            if words.next() == Some("mtllib") {
+                words.map(str::to_owned).collect()
+            } else {
+                Vec::new()
+            }
+        })
+        .collect()
+}
+
+#[must_use]
+/// Converts a material of an MTL file to the material of the shader.
+///
+/// A non-black emitted color (`Ke`) makes the material emissive, its brightest component giving the strength.
+/// The shininess (`Ns`, up to 1000) gives the smoothness of materials with a specular color (`Ks`),
+/// the dissolve (`d`) gives the opacity, and illumination models 4, 6, 7 and 9 make the material
+/// a dielectric with the index of refraction `Ni`.
+fn convert_material(material: &tobj::Material) -> Material {
+    let default = Material::matte([0.8; 3]);
+
+    let emission = material
+        .unknown_param
+        .get("Ke")
+        .map(|value| {
+            let mut components = value.split_whitespace().map(str::parse::<f32>);
+            std::array::from_fn::<_, 3, _>(|_| {
+                components.next().and_then(Result::ok).unwrap_or(0.0)
+            })
+        })
+        .unwrap_or_default();
+    let emission_strength = emission.into_iter().fold(0.0, f32::max);
+
+    let is_specular = material
+        .specular
+        .is_some_and(|specular| specular.iter().any(|&component| component > 0.0));
+    let smoothness = match material.shininess {
+        Some(shininess) if is_specular => (shininess / 1000.0).clamp(0.0, 1.0).sqrt(),
+        _ => default.smoothness,
+    };
+    let is_dielectric = matches!(material.illumination_model, Some(4 | 6 | 7 | 9));
+
+    Material {
+        // An emissive material is seen with the color of its light
+        color: if emission_strength > 0.0 {
+            emission.map(|component| component / emission_strength)
+        } else {
+            material.diffuse.unwrap_or(default.color)
+        },
+        emission_strength,
+        smoothness,
+        opacity: material
+            .dissolve
+            .map_or(default.opacity, |dissolve| dissolve.clamp(0.0, 1.0)),
+        transmission: if is_dielectric {
+            1.0
+        } else {
+            default.transmission
+        },
+        ior: material.optical_density.unwrap_or(default.ior),
+        ..default
+    }
+}
+
+/// Normalizes the given vector in place, returning whether it could be normalized.
+///
+/// Null and non-finite vectors are left untouched.
+fn normalize(vector: &mut [f32; 3]) -> bool {
+    let length = vector[2]
+        .mul_add(
+            vector[2],
+            vector[0].mul_add(vector[0], vector[1] * vector[1]),
+        )
+        .sqrt();
+    if !length.is_normal() {
+        return false;
+    }
+    for component in vector {
+        *component /= length;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Only `mtllib` lines reference libraries, each of them possibly several.
+    fn material_libraries_are_split_on_whitespace() {
+        let directory =
+            std::env::temp_dir().join(format!("rt-engine-libraries-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let model = directory.join("model.obj");
+        std::fs::write(
+            &model,
+            "mtllib a.mtl  b.mtl\nmtllibfoo c.mtl\n  mtllib d.mtl\n# mtllib e.mtl\n",
+        )
+        .unwrap();
+
+        let libraries = material_libraries(&model);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(libraries, ["a.mtl", "b.mtl", "d.mtl"]);
+    }
+
+    #[test]
+    /// Faces without material, or whose library could not be loaded, use the default material of the model,
+    /// whether it is parsed or loaded from its cache.
+    fn faces_without_material_use_the_default_one() {
+        let directory =
+            std::env::temp_dir().join(format!("rt-engine-materials-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let model = directory.join("model.obj");
+        std::fs::write(
+            directory.join("present.mtl"),
+            "newmtl red\nKd 1 0 0\nmap_Kd -bm 1 textures/red.png\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &model,
+            "mtllib missing.mtl present.mtl\n\
+             v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             f 1 2 3\n\
+             usemtl missing\nf 1 2 3\n\
+             usemtl red\nf 1 2 3\n",
+        )
+        .unwrap();
+        let src = model.to_string_lossy();
+
+        let material_ids = || {
+            let mut triangles = Vec::new();
+            let mut bvhs = Vec::new();
+            Model::load(
+                &mut triangles,
+                &mut bvhs,
+                &src,
+                &Transform::from([0.0; 3]),
+                5,
+                2,
+                Winding::CounterClockwise,
+            )
+            .unwrap();
+            let mut material_ids = triangles
+                .iter()
+                .map(|triangle| triangle.material_id)
+                .collect::<Vec<_>>();
+            material_ids.sort_unstable();
+            material_ids
+        };
+        let parsed = material_ids();
+        let cached = material_ids();
+        let materials = Model::load_materials(&src);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(parsed, [2, 2, 5]);
+        assert_eq!(cached, [2, 2, 5]);
+        assert_eq!(materials.len(), 1);
+        assert_eq!(
+            materials[0].1,
+            cfg!(feature = "image").then(|| directory.join("textures").join("red.png"))
+        );
+    }
 }
@@ -0,0 +1,89 @@
+use crate::shader::{source::Model, Transform};
+use vulkano::padded::Padded;
+
+/// The columns of a 3x3 matrix, laid out as a `mat3` of the shader.
+type Basis = [Padded<[f32; 3], 4>; 3];
+
+#[must_use]
+/// Returns the columns of the given matrix, given row by row.
+fn columns(rows: [[f32; 3]; 3]) -> Basis {
+    std::array::from_fn(|column| Padded::from(rows.map(|row| row[column])))
+}
+
+#[must_use]
+/// Returns the basis of the models that are only translated, whose triangles are not transformed by the shader.
+pub(super) fn identity() -> Basis {
+    columns([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+}
+
+impl Model {
+    #[must_use]
+    /// Returns the model placed by the given transform, whose translation replaces the one of the model.
+    ///
+    /// Its rotation and scale are applied by the shader on top of the ones baked into the triangles of the model,
+    /// so they must not have a null factor.
+    pub(crate) fn placed(self, transform: &Transform) -> Self {
+        let linear = transform.linear_part();
+        // The inverse of a scale followed by a rotation is the transposed rotation followed by the inverse scale
+        let inverse = std::array::from_fn(|row| {
+            std::array::from_fn(|column| {
+                linear[column][row] / (transform.scale[row] * transform.scale[row])
+            })
+        });
+
+        Self {
+            translation: transform.translation,
+            basis: columns(linear),
+            inverse_basis: columns(inverse),
+            ..self
+        }
+    }
+
+    #[must_use]
+    /// Returns the given vector of the space of the triangles of the model, rotated and scaled into the scene.
+    pub(crate) fn transform_vector(&self, vector: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|axis| {
+            self.basis[2][axis].mul_add(
+                vector[2],
+                self.basis[0][axis].mul_add(vector[0], self.basis[1][axis] * vector[1]),
+            )
+        })
+    }
+
+    #[must_use]
+    /// Returns the given point of the space of the triangles of the model, placed in the scene.
+    pub(crate) fn transform_point(&self, point: [f32; 3]) -> [f32; 3] {
+        let vector = self.transform_vector(point);
+        std::array::from_fn(|axis| vector[axis] + self.translation[axis])
+    }
+
+    #[must_use]
+    /// Returns the bounding box in the scene of the given bounding box of the triangles of the model.
+    ///
+    /// Empty boxes, whose minimum exceeds their maximum, are returned as is.
+    pub(crate) fn world_bounds(
+        &self,
+        (min_bound, max_bound): ([f32; 3], [f32; 3]),
+    ) -> ([f32; 3], [f32; 3]) {
+        if (0..3).any(|axis| min_bound[axis] > max_bound[axis]) {
+            return (min_bound, max_bound);
+        }
+
+        let center = self.transform_point(std::array::from_fn(|axis| {
+            (min_bound[axis] + max_bound[axis]) * 0.5
+        }));
+        // Each axis of the box, once transformed, stretches the box in the scene along every axis
+        let extent: [f32; 3] = std::array::from_fn(|axis| {
+            (0..3)
+                .map(|column| {
+                    self.basis[column][axis].abs() * (max_bound[column] - min_bound[column]) * 0.5
+                })
+                .sum()
+        });
+
+        (
+            std::array::from_fn(|axis| center[axis] - extent[axis]),
+            std::array::from_fn(|axis| center[axis] + extent[axis]),
+        )
+    }
+}
@@ -0,0 +1,714 @@
+use std::f32::consts::PI;
+
+use vulkano::padded::Padded;
+
+use super::source::{Bvh, Camera, Material, Model, Triangle};
+use super::{Background, Light, Projection, ShaderDescriptor, ToneMap};
+
+/// A 3D vector.
+type Vec3 = [f32; 3];
+
+/// Represents the data of a scene read by the reference path tracer,
+/// laid out as in the buffers of the shader.
+pub struct Scene<'a> {
+    /// The triangles of the models.
+    pub triangles: &'a [Padded<Triangle, 4>],
+    /// The BVHs of the models.
+    pub bvhs: &'a [Padded<Bvh, 4>],
+    /// The models and instances of the scene.
+    pub models: &'a [Padded<Model, 12>],
+    /// The top-level BVH over the models.
+    pub tlas: Vec<Padded<Bvh, 4>>,
+    /// The materials of the scene.
+    pub materials: &'a [Material],
+    /// The lights of the scene.
+    pub lights: &'a [Light],
+}
+
+#[derive(Clone, Copy)]
+/// Represents a ray.
+struct Ray {
+    /// The origin of the ray.
+    origin: Vec3,
+    /// The direction of the ray, normalized in the scene but not in the space of the triangles of a model.
+    direction: Vec3,
+    /// Whether the ray travels inside a dielectric, where back faces are hit.
+    inside: bool,
+}
+
+/// Represents the closest intersection of a ray with the scene.
+struct Hit {
+    /// The distance along the ray.
+    t: f32,
+    /// The point hit, in world space.
+    point: Vec3,
+    /// The shading normal, interpolated from the normals of the vertices.
+    normal: Vec3,
+    /// The normalized face normal.
+    geometric_normal: Vec3,
+    /// The index of the material of the triangle hit.
+    material_id: u32,
+}
+
+/// Renders the given scene from the given camera on the CPU, returning the RGBA8 pixels of the image.
+///
+/// It follows the same light transport as the shader, with its own random numbers,
+/// tracing as many samples per pixel as the shader does per frame.
+pub fn render(
+    scene: &Scene<'_>,
+    camera: &Camera,
+    descriptor: &ShaderDescriptor,
+    (width, height): (u32, u32),
+) -> Vec<u8> {
+    let tracer = Tracer {
+        scene,
+        camera,
+        descriptor,
+    };
+    let samples = descriptor.samples.max(1);
+
+    #[allow(clippy::cast_precision_loss)]
+    let (dim_x, dim_y) = (width as f32, height as f32);
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let mut color = [0.0; 3];
+            for sample in 0..samples {
+                let mut rng = Rng::new(descriptor.seed, x, y, u32::from(sample));
+
+                // Samples are spread uniformly over the pixel
+                #[allow(clippy::cast_precision_loss)]
+                let uv = [
+                    (x as f32 + rng.random()) / dim_x,
+                    (y as f32 + rng.random()) / dim_y,
+                ];
+                let ray = tracer.primary_ray(uv, dim_x / dim_y, &mut rng);
+                color = add(color, tracer.trace(ray, &mut rng));
+            }
+
+            let color = tonemap(scale(color, 1.0 / f32::from(samples)), descriptor);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            pixels.extend(
+                color
+                    .map(|channel| (channel.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8)
+                    .into_iter()
+                    .chain([u8::MAX]),
+            );
+        }
+    }
+
+    pixels
+}
+
+/// Traces the rays of a render.
+struct Tracer<'a> {
+    /// The scene rendered.
+    scene: &'a Scene<'a>,
+    /// The camera the scene is rendered from.
+    camera: &'a Camera,
+    /// The shader parameters.
+    descriptor: &'a ShaderDescriptor,
+}
+
+impl Tracer<'_> {
+    #[must_use]
+    /// Returns the primary ray going through the given point of the image, in `[0, 1]`.
+    fn primary_ray(&self, uv: [f32; 2], aspect_ratio: f32, rng: &mut Rng) -> Ray {
+        let camera = self.camera;
+        let trans = uv.map(|coordinate| 2.0_f32.mul_add(coordinate, -1.0));
+
+        let direction = match self.descriptor.projection {
+            Projection::Equirectangular => {
+                let longitude = trans[0] * PI;
+                let latitude = -trans[1] * PI / 2.0;
+                let horizontal = add(
+                    scale(normalize(*camera.view), longitude.cos()),
+                    scale(normalize(camera.right), longitude.sin()),
+                );
+                add(
+                    scale(horizontal, latitude.cos()),
+                    scale(normalize(*camera.up), latitude.sin()),
+                )
+            }
+            Projection::Perspective => {
+                let half_height = (camera.fov.to_radians() * 0.5).tan();
+                let offset = sub(scale(camera.right, trans[0]), scale(*camera.up, trans[1]));
+                mul(
+                    add(*camera.view, scale(offset, half_height)),
+                    [aspect_ratio, 1.0, aspect_ratio],
+                )
+            }
+        };
+
+        let mut ray = Ray {
+            origin: *camera.position,
+            direction: normalize(direction),
+            inside: false,
+        };
+
+        if camera.aperture > 0.0 {
+            // Rays leave from a point of the lens and converge on the focus plane
+            let focus_t = match self.descriptor.projection {
+                Projection::Equirectangular => camera.focus_distance,
+                Projection::Perspective => camera.focus_distance / dot(ray.direction, *camera.view),
+            };
+            let focus_point = add(ray.origin, scale(ray.direction, focus_t));
+
+            let [lens_x, lens_y] = rng.in_circle().map(|v| v * camera.aperture);
+            ray.origin = add(
+                ray.origin,
+                add(scale(camera.right, lens_x), scale(*camera.up, lens_y)),
+            );
+            ray.direction = normalize(sub(focus_point, ray.origin));
+        }
+
+        ray
+    }
+
+    #[must_use]
+    /// Returns the distances between which the given primary ray can hit the scene.
+    fn primary_ray_range(&self, ray: &Ray) -> (f32, f32) {
+        let clip_scale = match self.descriptor.projection {
+            Projection::Equirectangular => 1.0,
+            Projection::Perspective => {
+                1.0 / dot(ray.direction, normalize(*self.camera.view)).max(1e-6)
+            }
+        };
+        (
+            self.descriptor
+                .near_plane
+                .max(self.camera.near * clip_scale),
+            self.descriptor
+                .max_distance
+                .min(self.camera.far * clip_scale),
+        )
+    }
+
+    #[must_use]
+    /// Returns the light brought back by the given primary ray.
+    fn trace(&self, mut ray: Ray, rng: &mut Rng) -> Vec3 {
+        let descriptor = self.descriptor;
+        let epsilon = descriptor.ray_epsilon;
+        let mut incoming_light = [0.0; 3];
+        let mut color = [1.0; 3];
+        let (primary_t_min, primary_t_max) = self.primary_ray_range(&ray);
+
+        for bounce in 0..descriptor.max_bounces {
+            let (t_min, t_max) = if bounce == 0 {
+                (primary_t_min, primary_t_max)
+            } else {
+                (0.0, descriptor.max_distance)
+            };
+
+            let Some(mut hit) = self.hit_scene(&ray, t_min, t_max) else {
+                incoming_light = add(incoming_light, mul(color, self.background(ray.direction)));
+                break;
+            };
+
+            // Back faces are only hit from inside a dielectric, the normals are turned towards the ray
+            let front_face = dot(ray.direction, hit.geometric_normal) < 0.0;
+            if !front_face {
+                hit.normal = scale(hit.normal, -1.0);
+                hit.geometric_normal = scale(hit.geometric_normal, -1.0);
+            }
+            let material = &self.scene.materials[hit.material_id as usize];
+
+            // Pass through the surface
+            if rng.random() >= material.opacity {
+                ray.origin = sub(hit.point, scale(hit.normal, epsilon));
+                continue;
+            }
+
+            if material.transmission > 0.0 && rng.random() < material.transmission {
+                let eta = if front_face {
+                    1.0 / material.ior
+                } else {
+                    material.ior
+                };
+                let cos_theta = dot(scale(ray.direction, -1.0), hit.normal).min(1.0);
+                let sin_theta = cos_theta.mul_add(-cos_theta, 1.0).sqrt();
+
+                // Schlick's approximation of the Fresnel reflectance
+                let r0 = ((1.0 - eta) / (1.0 + eta)).powi(2);
+                let reflectance = (1.0 - r0).mul_add((1.0 - cos_theta).powi(5), r0);
+
+                // Total internal reflection when the ray cannot be refracted
+                ray = if eta * sin_theta > 1.0 || rng.random() < reflectance {
+                    Ray {
+                        origin: add(hit.point, scale(hit.normal, epsilon)),
+                        direction: reflect(ray.direction, hit.normal),
+                        inside: ray.inside,
+                    }
+                } else {
+                    Ray {
+                        origin: sub(hit.point, scale(hit.normal, epsilon)),
+                        direction: refract(ray.direction, hit.normal, eta),
+                        inside: front_face,
+                    }
+                };
+
+                color = mul(color, material.color);
+                continue;
+            }
+
+            let diffuse_direction = normalize(add(hit.normal, rng.direction()));
+            let specular_direction = reflect(ray.direction, hit.normal);
+            let direction = normalize(mix(
+                diffuse_direction,
+                specular_direction,
+                material.smoothness,
+            ));
+
+            // Emissive surfaces are only found by the bounces, which is slower but simpler
+            let emitted_light = scale(material.color, material.emission_strength);
+            incoming_light = add(incoming_light, mul(emitted_light, color));
+
+            let diffuse = material.albedo * (1.0 - material.smoothness);
+            let lit = scale(mul(color, material.color), diffuse);
+            incoming_light = add(incoming_light, mul(lit, self.sample_lights(&hit, rng)));
+            color = scale(mul(color, material.color), material.albedo);
+
+            // Russian roulette
+            let p = color[0].max(color[1]).max(color[2]);
+            if rng.random() > p {
+                break;
+            }
+            color = scale(color, 1.0 / p);
+
+            ray = Ray {
+                origin: add(hit.point, scale(hit.normal, epsilon)),
+                direction,
+                inside: ray.inside,
+            };
+        }
+
+        incoming_light
+    }
+
+    #[must_use]
+    /// Returns the diffuse light received at the given hit from the lights of the scene,
+    /// with one shadow ray per light.
+    fn sample_lights(&self, hit: &Hit, rng: &mut Rng) -> Vec3 {
+        let origin = add(hit.point, scale(hit.normal, self.descriptor.ray_epsilon));
+        let mut received_light = [0.0; 3];
+
+        for &light in self.scene.lights {
+            let (to_light, light_distance, irradiance) = match light {
+                Light::Directional {
+                    direction,
+                    color,
+                    intensity,
+                } => (
+                    scale(normalize(direction), -1.0),
+                    self.descriptor.max_distance,
+                    scale(color, intensity),
+                ),
+                Light::Point {
+                    position,
+                    color,
+                    intensity,
+                    radius,
+                } => {
+                    // Targeting a random point of the light's sphere gives soft shadows
+                    let target = add(position, scale(rng.direction(), radius));
+                    let to_light = sub(target, origin);
+                    let light_distance = dot(to_light, to_light).sqrt();
+                    (
+                        scale(to_light, 1.0 / light_distance),
+                        light_distance,
+                        scale(
+                            color,
+                            intensity / (light_distance * light_distance).max(1e-4),
+                        ),
+                    )
+                }
+            };
+
+            let cos_theta = dot(hit.normal, to_light);
+            if cos_theta <= 0.0 || irradiance.iter().all(|&channel| channel <= 0.0) {
+                continue;
+            }
+
+            let shadow_ray = Ray {
+                origin,
+                direction: to_light,
+                inside: false,
+            };
+            if !self.descriptor.shadows
+                || self.hit_scene(&shadow_ray, 0.0, light_distance).is_none()
+            {
+                received_light = add(received_light, scale(irradiance, cos_theta / PI));
+            }
+        }
+
+        received_light
+    }
+
+    #[must_use]
+    /// Returns the color seen by the rays missing the scene in the given direction.
+    fn background(&self, direction: Vec3) -> Vec3 {
+        match self.descriptor.background {
+            Background::Sky => sky_color(direction),
+            Background::Color(color) => color,
+            Background::Gradient { top, bottom } => {
+                mix(bottom, top, direction[1].mul_add(0.5, 0.5))
+            }
+        }
+    }
+
+    #[must_use]
+    /// Returns the closest hit of the ray with the models between `t_min` and `t_max`,
+    /// traversing the top-level BVH down to the BVHs of the models.
+    fn hit_scene(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        let scene = self.scene;
+        let mut closest: Option<Hit> = None;
+        let mut stack = vec![0];
+
+        while let Some(index) = stack.pop() {
+            let node = &scene.tlas[index];
+            let closest_t = closest.as_ref().map_or(t_max, |hit| hit.t);
+
+            if node.left_offset == 0 {
+                let first = node.triangle_offset as usize;
+                for model in &scene.models[first..first + node.triangle_count as usize] {
+                    let t_closest = closest.as_ref().map_or(t_max, |hit| hit.t);
+                    if let Some(hit) = self.hit_model(ray, model, t_min, t_closest) {
+                        closest = Some(hit);
+                    }
+                }
+            } else {
+                for child in [node.left_offset, node.right_offset] {
+                    if intersect_box(ray, &scene.tlas[child as usize]) < closest_t {
+                        stack.push(child as usize);
+                    }
+                }
+            }
+        }
+
+        closest
+    }
+
+    #[must_use]
+    /// Returns the closest hit of the ray with the given model between `t_min` and `t_max`.
+    fn hit_model(&self, ray: &Ray, model: &Model, t_min: f32, t_max: f32) -> Option<Hit> {
+        let scene = self.scene;
+        // Moving the ray instead of the model, without normalizing its direction, keeps the distances unchanged
+        let local_ray = Ray {
+            origin: to_local(model, sub(ray.origin, model.translation)),
+            direction: to_local(model, ray.direction),
+            ..*ray
+        };
+        let mut closest: Option<(f32, [f32; 3], &Triangle)> = None;
+        let mut stack = vec![model.bvh_index as usize];
+
+        while let Some(index) = stack.pop() {
+            let node = &scene.bvhs[index];
+            let closest_t = closest.map_or(t_max, |(t, _, _)| t);
+
+            if node.left_offset == 0 {
+                let first = node.triangle_offset as usize;
+                let triangles = &scene.triangles[first..first + node.triangle_count as usize];
+                for triangle in triangles.iter().map(|triangle| &**triangle) {
+                    let t_closest = closest.map_or(t_max, |(t, _, _)| t);
+                    if let Some((t, barycentric)) = intersect_triangle(
+                        &local_ray,
+                        triangle,
+                        self.descriptor.intersection_epsilon,
+                    ) {
+                        if t >= t_min && t < t_closest {
+                            closest = Some((t, barycentric, triangle));
+                        }
+                    }
+                }
+            } else {
+                for child in [node.left_offset, node.right_offset] {
+                    if intersect_box(&local_ray, &scene.bvhs[child as usize]) < closest_t {
+                        stack.push(child as usize);
+                    }
+                }
+            }
+        }
+
+        closest.map(|(t, [w, u, v], triangle)| {
+            let geometric_normal = normal_to_world(model, *triangle.normal);
+            let shading_normal = normal_to_world(
+                model,
+                add(
+                    add(
+                        scale(*triangle.vertex_normals[0], w),
+                        scale(*triangle.vertex_normals[1], u),
+                    ),
+                    scale(*triangle.vertex_normals[2], v),
+                ),
+            );
+            Hit {
+                t,
+                point: add(ray.origin, scale(ray.direction, t)),
+                // Inconsistent vertex normals must not turn the surface around
+                normal: if dot(shading_normal, geometric_normal) > 0.0 {
+                    shading_normal
+                } else {
+                    geometric_normal
+                },
+                geometric_normal,
+                material_id: triangle.material_id,
+            }
+        })
+    }
+}
+
+#[must_use]
+/// Returns the distance along the ray and the barycentric coordinates of its intersection
+/// with the given triangle, if any.
+fn intersect_triangle(ray: &Ray, triangle: &Triangle, epsilon: f32) -> Option<(f32, [f32; 3])> {
+    let determinant = -dot(ray.direction, *triangle.normal);
+
+    // Rejects back faces, unless the ray is inside a dielectric, and rays nearly parallel to the triangle
+    let facing = if ray.inside {
+        determinant.abs()
+    } else {
+        determinant
+    };
+    if facing < epsilon {
+        return None;
+    }
+
+    let edge1 = sub(*triangle.vertices[1], *triangle.vertices[0]);
+    let edge2 = sub(*triangle.vertices[2], *triangle.vertices[0]);
+    let ao = sub(ray.origin, *triangle.vertices[0]);
+    let dao = cross(ao, ray.direction);
+
+    let inv_determinant = 1.0 / determinant;
+    let t = dot(ao, *triangle.normal) * inv_determinant;
+    let u = dot(edge2, dao) * inv_determinant;
+    let v = -dot(edge1, dao) * inv_determinant;
+    let w = 1.0 - u - v;
+
+    (t >= 0.0 && u >= 0.0 && v >= 0.0 && w >= 0.0).then_some((t, [w, u, v]))
+}
+
+#[must_use]
+/// Returns the given vector of the scene in the space of the triangles of the given model.
+fn to_local(model: &Model, vector: Vec3) -> Vec3 {
+    std::array::from_fn(|axis| {
+        (0..3)
+            .map(|column| model.inverse_basis[column][axis] * vector[column])
+            .sum()
+    })
+}
+
+#[must_use]
+/// Returns the given normal of the space of the triangles of the given model in the scene, normalized.
+///
+/// The transposed inverse of the basis of the model keeps the normals orthogonal to the surfaces.
+fn normal_to_world(model: &Model, normal: Vec3) -> Vec3 {
+    normalize(std::array::from_fn(|axis| {
+        dot(*model.inverse_basis[axis], normal)
+    }))
+}
+
+#[must_use]
+/// Returns the distance along the ray to the bounding box of the given node,
+/// 0 if the ray starts inside it and infinity if it misses it.
+fn intersect_box(ray: &Ray, node: &Bvh) -> f32 {
+    let mut t_min = f32::NEG_INFINITY;
+    let mut t_max = f32::INFINITY;
+    for axis in 0..3 {
+        let t0 = (node.min_bound[axis] - ray.origin[axis]) / ray.direction[axis];
+        let t1 = (node.max_bound[axis] - ray.origin[axis]) / ray.direction[axis];
+        t_min = t_min.max(t0.min(t1));
+        t_max = t_max.min(t0.max(t1));
+    }
+
+    if t_max >= t_min && t_max > 0.0 {
+        t_min.max(0.0)
+    } else {
+        f32::INFINITY
+    }
+}
+
+#[must_use]
+/// Returns the color of the procedural sky in the given direction.
+fn sky_color(direction: Vec3) -> Vec3 {
+    const GROUND: Vec3 = [0.07, 0.2, 0.1];
+    const HORIZON: Vec3 = [0.15, 0.2, 0.4];
+    const ZENITH: Vec3 = [0.08, 0.37, 0.73];
+
+    let gradient = smoothstep(0.0, 0.4, direction[1]).powf(0.35);
+    let ground_sky_blend = smoothstep(-0.001, 0.0, direction[1]);
+    let sky = mix(GROUND, mix(HORIZON, ZENITH, gradient), ground_sky_blend);
+
+    if ground_sky_blend >= 1.0 {
+        let sun = direction[1].max(0.0).powf(1.5) * 0.5;
+        add(sky, [sun, sun, sun * 0.9])
+    } else {
+        sky
+    }
+}
+
+#[must_use]
+/// Maps a linear color to the displayable range with the operator of the shader parameters.
+fn tonemap(color: Vec3, descriptor: &ShaderDescriptor) -> Vec3 {
+    let [r, g, b, _] = descriptor.color_scale();
+    let color = mul(color, [r, g, b]);
+    match descriptor.tonemap {
+        ToneMap::None => color,
+        ToneMap::Reinhard => color.map(|c| c / (1.0 + c)),
+        // Krzysztof Narkowicz's fit of the ACES filmic curve
+        ToneMap::AcesFilmic => color.map(|c| {
+            (c * 2.51_f32.mul_add(c, 0.03) / c.mul_add(2.43_f32.mul_add(c, 0.59), 0.14))
+                .clamp(0.0, 1.0)
+        }),
+    }
+}
+
+/// Represents the random number generator of a sample, a PCG hash like the one of the shader.
+struct Rng(u32);
+
+impl Rng {
+    #[must_use]
+    /// Creates the generator of the given sample of the given pixel.
+    const fn new(seed: u64, x: u32, y: u32, sample: u32) -> Self {
+        #[allow(clippy::cast_possible_truncation)]
+        let seed = (seed ^ (seed >> 32)) as u32;
+        let mut rng = Self(
+            sample
+                .wrapping_mul(685_743)
+                .wrapping_add(x.wrapping_mul(9841))
+                .wrapping_add(y)
+                ^ seed,
+        );
+        rng.hash();
+        rng
+    }
+
+    /// Advances the state and returns its hash.
+    const fn hash(&mut self) -> u32 {
+        self.0 = self.0.wrapping_mul(747_796_405).wrapping_add(2_891_336_453);
+        let state = self.0;
+        let result = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277_803_737);
+        (result >> 22) ^ result
+    }
+
+    #[must_use]
+    /// Returns a random number in `[0, 1)`.
+    fn random(&mut self) -> f32 {
+        // The 24 upper bits fill the mantissa
+        #[allow(clippy::cast_precision_loss)]
+        let value = (self.hash() >> 8) as f32;
+        value / 16_777_216.0
+    }
+
+    #[must_use]
+    /// Returns a random number with a standard normal distribution.
+    fn normal(&mut self) -> f32 {
+        let theta = 2.0 * PI * self.random();
+        // 1 - x is in (0, 1], whose logarithm is finite
+        (-2.0 * (1.0 - self.random()).ln()).sqrt() * theta.cos()
+    }
+
+    #[must_use]
+    /// Returns a random direction, uniformly distributed on the unit sphere.
+    fn direction(&mut self) -> Vec3 {
+        normalize([self.normal(), self.normal(), self.normal()])
+    }
+
+    #[must_use]
+    /// Returns a random point, uniformly distributed in the unit disk.
+    fn in_circle(&mut self) -> [f32; 2] {
+        let angle = 2.0 * PI * self.random();
+        let radius = self.random().sqrt();
+        [angle.cos() * radius, angle.sin() * radius]
+    }
+}
+
+#[must_use]
+#[inline]
+/// Returns the Hermite interpolation of `x` between the two edges, like `smoothstep` in GLSL.
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * 2.0_f32.mul_add(-t, 3.0)
+}
+
+#[must_use]
+#[inline]
+/// Returns the sum of two vectors.
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    std::array::from_fn(|i| a[i] + b[i])
+}
+
+#[must_use]
+#[inline]
+/// Returns the difference of two vectors.
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    std::array::from_fn(|i| a[i] - b[i])
+}
+
+#[must_use]
+#[inline]
+/// Returns the component-wise product of two vectors.
+fn mul(a: Vec3, b: Vec3) -> Vec3 {
+    std::array::from_fn(|i| a[i] * b[i])
+}
+
+#[must_use]
+#[inline]
+/// Returns the vector multiplied by the given factor.
+fn scale(a: Vec3, factor: f32) -> Vec3 {
+    a.map(|component| component * factor)
+}
+
+#[must_use]
+#[inline]
+/// Returns the linear interpolation between two vectors.
+fn mix(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    std::array::from_fn(|i| (b[i] - a[i]).mul_add(t, a[i]))
+}
+
+#[must_use]
+#[inline]
+/// Returns the dot product of two vectors.
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[2].mul_add(b[2], a[0].mul_add(b[0], a[1] * b[1]))
+}
+
+#[must_use]
+#[inline]
+/// Returns the cross product of two vectors.
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1].mul_add(b[2], -(a[2] * b[1])),
+        a[2].mul_add(b[0], -(a[0] * b[2])),
+        a[0].mul_add(b[1], -(a[1] * b[0])),
+    ]
+}
+
+#[must_use]
+#[inline]
+/// Returns the normalized vector.
+fn normalize(a: Vec3) -> Vec3 {
+    scale(a, 1.0 / dot(a, a).sqrt())
+}
+
+#[must_use]
+#[inline]
+/// Returns the direction reflected by the surface of the given normal, like `reflect` in GLSL.
+fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
+    sub(direction, scale(normal, 2.0 * dot(normal, direction)))
+}
+
+#[must_use]
+#[inline]
+/// Returns the direction refracted by the surface of the given normal with the given ratio
+/// of indices of refraction, like `refract` in GLSL.
+fn refract(direction: Vec3, normal: Vec3, eta: f32) -> Vec3 {
+    let cos_incident = dot(normal, direction);
+    let k = (eta * eta).mul_add(-cos_incident.mul_add(-cos_incident, 1.0), 1.0);
+    if k < 0.0 {
+        return [0.0; 3];
+    }
+    sub(
+        scale(direction, eta),
+        scale(normal, eta.mul_add(cos_incident, k.sqrt())),
+    )
+}
@@ -0,0 +1,434 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::source::Material;
+use super::{Instance, Light, SceneDescriptor, TextureFilter, Transform, Winding};
+use crate::control::camera::{first_person::FirstPerson, Camera};
+
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+/// Represents the reason a scene file could not be loaded.
+pub enum SceneFileError {
+    /// The file could not be read.
+    Io(PathBuf, std::io::Error),
+    /// The extension of the file is neither `.ron` nor `.json`.
+    UnsupportedFormat(PathBuf),
+    /// The file is not a valid scene description, the parser giving the cause.
+    Parse(PathBuf, String),
+}
+
+impl std::fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, error) => write!(f, "failed to read {}: {error}", path.display()),
+            Self::UnsupportedFormat(path) => write!(
+                f,
+                "unsupported scene file {}, expected a .ron or .json file",
+                path.display()
+            ),
+            Self::Parse(path, cause) => write!(f, "invalid scene file {}: {cause}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+/// Represents a scene loaded from a file, see [`SceneDescriptor::from_file`].
+pub struct SceneFile {
+    /// The models, materials, lights and environment of the scene.
+    pub scene: SceneDescriptor,
+    /// The pose of the camera given by the file, if any.
+    pub camera: Option<CameraPose>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+/// Represents the pose of a camera described by a scene file.
+pub struct CameraPose {
+    /// The position of the camera.
+    pub position: [f32; 3],
+    /// The point the camera looks at.
+    pub target: [f32; 3],
+    #[serde(default)]
+    /// The vertical field of view of the camera, in degrees.
+    pub fov: Option<f32>,
+}
+
+impl CameraPose {
+    #[must_use]
+    /// Creates a first person camera with this pose.
+    pub fn first_person(&self) -> FirstPerson {
+        let mut camera = FirstPerson::from_position_yaw_pitch(self.position, 0.0, 0.0);
+        camera.look_at(self.target);
+        if let Some(fov) = self.fov {
+            camera.set_fov(fov);
+        }
+        camera
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+/// The root of a scene file.
+struct SceneEntry {
+    /// The models of the scene.
+    models: Vec<ModelEntry>,
+    #[serde(default)]
+    /// The additional placements of the models.
+    instances: Vec<InstanceEntry>,
+    #[serde(default)]
+    /// The materials of the scene, a default one being used if there are none.
+    materials: Vec<MaterialEntry>,
+    #[serde(default)]
+    /// The lights of the scene.
+    lights: Vec<LightEntry>,
+    #[serde(default)]
+    /// The pose of the camera.
+    camera: Option<CameraPose>,
+    #[serde(default)]
+    /// The path to the environment map.
+    environment: Option<PathBuf>,
+    #[serde(default)]
+    /// The paths to the diffuse textures.
+    textures: Vec<PathBuf>,
+    #[serde(default)]
+    /// The filter used when sampling the textures.
+    texture_filter: TextureFilterEntry,
+    #[serde(default)]
+    /// The order of the vertices of the triangles of the models.
+    winding: WindingEntry,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+/// A model of a scene file.
+struct ModelEntry {
+    /// The path to the model.
+    path: PathBuf,
+    #[serde(default)]
+    /// The translation of the model.
+    translation: [f32; 3],
+    #[serde(default)]
+    /// The rotation of the model, in degrees around the X, Y and Z axes.
+    rotation: [f32; 3],
+    #[serde(default)]
+    /// The scale of the model, uniform or along each axis.
+    scale: ScaleEntry,
+    #[serde(default)]
+    /// The index of the first material of the model, 0 if not given.
+    material: Option<u32>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(untagged)]
+/// The scale of a model of a scene file.
+enum ScaleEntry {
+    /// The same factor along the three axes.
+    Uniform(f32),
+    /// A factor along each axis.
+    Axes([f32; 3]),
+}
+
+impl Default for ScaleEntry {
+    fn default() -> Self {
+        Self::Uniform(1.0)
+    }
+}
+
+impl ScaleEntry {
+    #[must_use]
+    /// Returns the factors along the X, Y and Z axes.
+    const fn factors(self) -> [f32; 3] {
+        match self {
+            Self::Uniform(scale) => [scale; 3],
+            Self::Axes(scale) => scale,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+/// An instance of a scene file.
+struct InstanceEntry {
+    /// The index of the model in the models of the file.
+    model: usize,
+    #[serde(default)]
+    /// The translation of the instance.
+    translation: [f32; 3],
+    #[serde(default)]
+    /// The rotation of the instance, in degrees around the X, Y and Z axes,
+    /// applied on top of the one of its model.
+    rotation: [f32; 3],
+    #[serde(default)]
+    /// The scale of the instance, uniform or along each axis, applied on top of the one of its model.
+    scale: ScaleEntry,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// A material of a scene file, whose missing fields are the ones of a light gray matte material.
+struct MaterialEntry {
+    /// The color of the material.
+    color: [f32; 3],
+    /// The strength of the light emitted by the material.
+    emission_strength: f32,
+    /// The fraction of the light reflected by the material.
+    albedo: f32,
+    /// The smoothness of the material, from diffuse to mirror.
+    smoothness: f32,
+    /// The probability for a ray to be scattered by the surface.
+    opacity: f32,
+    /// The index of the texture of the material.
+    texture: Option<i32>,
+    /// The probability for a ray to go through the material as a dielectric.
+    transmission: f32,
+    /// The index of refraction of the dielectric.
+    ior: f32,
+    /// The alpha of the texture under which the surface is cut out.
+    alpha_cutout: f32,
+}
+
+impl Default for MaterialEntry {
+    fn default() -> Self {
+        let Material {
+            color,
+            emission_strength,
+            albedo,
+            smoothness,
+            opacity,
+            transmission,
+            ior,
+            alpha_cutout,
+            ..
+        } = Material::matte([0.8; 3]);
+
+        Self {
+            color,
+            emission_strength,
+            albedo,
+            smoothness,
+            opacity,
+            texture: None,
+            transmission,
+            ior,
+            alpha_cutout,
+        }
+    }
+}
+
+impl From<MaterialEntry> for Material {
+    fn from(entry: MaterialEntry) -> Self {
+        Self {
+            color: entry.color,
+            emission_strength: entry.emission_strength,
+            albedo: entry.albedo,
+            smoothness: entry.smoothness,
+            opacity: entry.opacity,
+            texture_index: entry.texture.unwrap_or(-1),
+            transmission: entry.transmission,
+            ior: entry.ior,
+            alpha_cutout: entry.alpha_cutout,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+/// A light of a scene file.
+enum LightEntry {
+    /// A light infinitely far away.
+    Directional {
+        /// The direction the light travels in.
+        direction: [f32; 3],
+        /// The color of the light.
+        color: [f32; 3],
+        /// The intensity of the light.
+        intensity: f32,
+    },
+    /// A light emitted from a small sphere.
+    Point {
+        /// The position of the center of the light.
+        position: [f32; 3],
+        /// The color of the light.
+        color: [f32; 3],
+        /// The intensity of the light at a distance of 1.
+        intensity: f32,
+        #[serde(default)]
+        /// The radius of the light.
+        radius: f32,
+    },
+}
+
+impl From<LightEntry> for Light {
+    fn from(entry: LightEntry) -> Self {
+        match entry {
+            LightEntry::Directional {
+                direction,
+                color,
+                intensity,
+            } => Self::Directional {
+                direction,
+                color,
+                intensity,
+            },
+            LightEntry::Point {
+                position,
+                color,
+                intensity,
+                radius,
+            } => Self::Point {
+                position,
+                color,
+                intensity,
+                radius,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+/// The texture filter of a scene file.
+enum TextureFilterEntry {
+    #[default]
+    /// See [`TextureFilter::Linear`].
+    Linear,
+    /// See [`TextureFilter::Nearest`].
+    Nearest,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+/// The winding of a scene file.
+enum WindingEntry {
+    #[default]
+    /// See [`Winding::CounterClockwise`].
+    CounterClockwise,
+    /// See [`Winding::Clockwise`].
+    Clockwise,
+    /// See [`Winding::Auto`].
+    Auto,
+}
+
+impl SceneDescriptor {
+    /// Loads the scene described by the given RON or JSON file, chosen by its extension.
+    ///
+    /// The file lists the models with their path, transform and first material,
+    /// the instances, materials, lights, environment map and textures of the scene,
+    /// and the pose of the camera. Relative paths are resolved against the directory of the file.
+    ///
+    /// A minimal JSON scene looks like:
+    ///
+    /// ```json
+    /// {
+    ///     "models": [{ "path": "models/bunny.obj", "scale": 2.0, "material": 0 }],
+    ///     "materials": [{ "color": [0.8, 0.2, 0.2], "smoothness": 0.5 }],
+    ///     "lights": [{ "type": "directional", "direction": [0.0, -1.0, 0.0], "color": [1.0, 1.0, 1.0], "intensity": 2.0 }],
+    ///     "camera": { "position": [0.0, 1.0, 5.0], "target": [0.0, 0.0, 0.0] }
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the file cannot be read, has an unsupported extension,
+    /// or is not a valid scene description, such as one with unknown fields.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<SceneFile, SceneFileError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| SceneFileError::Io(path.to_path_buf(), error))?;
+
+        let entry: SceneEntry = match path.extension().and_then(std::ffi::OsStr::to_str) {
+            Some("ron") => ron::from_str(&contents).map_err(|error| error.to_string()),
+            Some("json") => serde_json::from_str(&contents).map_err(|error| error.to_string()),
+            _ => return Err(SceneFileError::UnsupportedFormat(path.to_path_buf())),
+        }
+        .map_err(|cause| SceneFileError::Parse(path.to_path_buf(), cause))?;
+
+        let SceneEntry {
+            models,
+            instances,
+            materials,
+            lights,
+            camera,
+            environment,
+            textures,
+            texture_filter,
+            winding,
+        } = entry;
+
+        // Joining an absolute path replaces the directory
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let resolve = |path: PathBuf| directory.join(path);
+
+        // Material indices are only needed if a model does not use the first material
+        let material_indices = if models.iter().all(|model| model.material.is_none()) {
+            Vec::new()
+        } else {
+            models
+                .iter()
+                .map(|model| model.material.unwrap_or(0))
+                .collect()
+        };
+        let transforms = models
+            .iter()
+            .map(|model| Transform {
+                translation: model.translation,
+                rotation_euler: model.rotation,
+                scale: model.scale.factors(),
+            })
+            .collect();
+        let model_paths = models
+            .into_iter()
+            .map(|model| resolve(model.path).to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        tracing::debug!(
+            "Scene file {} describes {} models",
+            path.display(),
+            model_paths.len()
+        );
+
+        Ok(SceneFile {
+            scene: Self {
+                model_paths,
+                transforms,
+                instances: instances
+                    .into_iter()
+                    .map(|instance| Instance {
+                        model: instance.model,
+                        transform: Transform {
+                            translation: instance.translation,
+                            rotation_euler: instance.rotation,
+                            scale: instance.scale.factors(),
+                        },
+                    })
+                    .collect(),
+                materials: materials.into_iter().map(Material::from).collect(),
+                material_indices,
+                lights: lights.into_iter().map(Light::from).collect(),
+                environment: environment.map(resolve),
+                textures: textures.into_iter().map(resolve).collect(),
+                texture_filter: match texture_filter {
+                    TextureFilterEntry::Linear => TextureFilter::Linear,
+                    TextureFilterEntry::Nearest => TextureFilter::Nearest,
+                },
+                winding: match winding {
+                    WindingEntry::CounterClockwise => Winding::CounterClockwise,
+                    WindingEntry::Clockwise => Winding::Clockwise,
+                    WindingEntry::Auto => Winding::Auto,
+                },
+            },
+            camera,
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use vulkano::{
+    buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer},
+    memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
+};
+
+use super::source::SobolBuffer;
+
+/// The number of dimensions of the Sobol sequence, which must match `SOBOL_DIMENSIONS` in the shader.
+///
+/// The random numbers drawn past these dimensions by a sample fall back to white noise.
+const DIMENSIONS: usize = 16;
+/// The number of bits of the direction numbers, one direction number per bit of the sample index.
+const BITS: usize = 32;
+
+/// The degree, the coefficients and the initial direction numbers of the primitive polynomials
+/// of the dimensions after the first one, from the `new-joe-kuo-6.21201` table of Joe and Kuo.
+const POLYNOMIALS: [(u32, u32, &[u32]); DIMENSIONS - 1] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+];
+
+#[must_use]
+/// Computes the direction numbers of the Sobol sequence, `BITS` per dimension, dimension by dimension.
+fn direction_numbers() -> [u32; DIMENSIONS * BITS] {
+    let mut directions = [0; DIMENSIONS * BITS];
+
+    // The first dimension is the van der Corput sequence in base 2
+    for (bit, direction) in directions[..BITS].iter_mut().enumerate() {
+        *direction = 1 << (BITS - 1 - bit);
+    }
+
+    for (dimension, &(degree, coefficients, initial)) in POLYNOMIALS.iter().enumerate() {
+        let degree = degree as usize;
+        let directions = &mut directions[(dimension + 1) * BITS..(dimension + 2) * BITS];
+
+        for (bit, &m) in initial.iter().enumerate() {
+            directions[bit] = m << (BITS - 1 - bit);
+        }
+        // Recurrence given by the primitive polynomial
+        for bit in degree..BITS {
+            let mut direction = directions[bit - degree] ^ (directions[bit - degree] >> degree);
+            for k in 1..degree {
+                if (coefficients >> (degree - 1 - k)) & 1 != 0 {
+                    direction ^= directions[bit - k];
+                }
+            }
+            directions[bit] = direction;
+        }
+    }
+
+    directions
+}
+
+#[must_use]
+/// Creates the buffer holding the direction numbers of the Sobol sequence, drawn from by the shader
+/// when Sobol sampling is selected.
+///
+/// ## Panics
+///
+/// This function panics if the buffer cannot be allocated.
+pub fn new_buffer(memory_allocator: &Arc<StandardMemoryAllocator>) -> Subbuffer<SobolBuffer> {
+    Buffer::from_data(
+        memory_allocator.clone(),
+        BufferCreateInfo {
+            usage: BufferUsage::STORAGE_BUFFER,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ..Default::default()
+        },
+        SobolBuffer {
+            sobol_directions: direction_numbers(),
+        },
+    )
+    .unwrap()
+}
@@ -0,0 +1,212 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use vulkano::{
+    command_buffer::allocator::StandardCommandBufferAllocator,
+    device::Queue,
+    image::view::{ImageView, ImageViewCreateInfo, ImageViewType},
+    memory::allocator::StandardMemoryAllocator,
+};
+
+/// Loads the diffuse textures at the given paths and sends them to the device as the layers
+/// of a single texture array, returning a view of the array and the send future.
+///
+/// The textures must be PNG images, which requires the `image` feature.
+/// As all the layers of an array have the same size, the textures are resampled to the size
+/// of the largest one.
+///
+/// ## Errors
+///
+/// This function returns the path of the first texture that cannot be loaded, the cause being logged.
+///
+/// ## Panics
+///
+/// This function panics if no path is given or if the array cannot be sent to the device.
+pub fn load_array(
+    paths: &[PathBuf],
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: &Arc<StandardCommandBufferAllocator>,
+    queue: &Arc<Queue>,
+) -> Result<(Arc<ImageView>, crate::buffer::SendBufferFuture), PathBuf> {
+    assert!(
+        !paths.is_empty(),
+        "a texture array needs at least one texture"
+    );
+    let start = std::time::Instant::now();
+
+    let max_dimension = queue
+        .device()
+        .physical_device()
+        .properties()
+        .max_image_dimension2_d;
+    let textures = paths
+        .iter()
+        .map(|path| {
+            let (width, height, texels) = decode(path).ok_or_else(|| path.clone())?;
+            if width == 0 || height == 0 || width > max_dimension || height > max_dimension {
+                tracing::error!(
+                    "Texture {} has an unsupported size of {width}x{height}",
+                    path.display()
+                );
+                return Err(path.clone());
+            }
+            Ok((width, height, texels))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let width = textures.iter().map(|&(width, _, _)| width).max().unwrap();
+    let height = textures.iter().map(|&(_, height, _)| height).max().unwrap();
+
+    let mut texels = Vec::with_capacity(width as usize * height as usize * textures.len());
+    for (path, (texture_width, texture_height, texture_texels)) in paths.iter().zip(textures) {
+        if (texture_width, texture_height) == (width, height) {
+            texels.extend(texture_texels);
+        } else {
+            tracing::debug!(
+                "Resampling texture {} from {texture_width}x{texture_height} to {width}x{height}",
+                path.display()
+            );
+            texels.extend(resample(
+                (texture_width, texture_height),
+                &texture_texels,
+                (width, height),
+            ));
+        }
+    }
+
+    let layers = u32::try_from(paths.len()).expect("too many textures");
+    let (image, future) = crate::buffer::send_image_to_device(
+        memory_allocator,
+        command_buffer_allocator,
+        queue,
+        vulkano::format::Format::R8G8B8A8_SRGB,
+        [width, height, layers],
+        &texels,
+    )
+    .unwrap();
+
+    // Arrays of a single layer must still be viewed as arrays by the shader
+    let view = ImageView::new(
+        image.clone(),
+        ImageViewCreateInfo {
+            view_type: ImageViewType::Dim2dArray,
+            ..ImageViewCreateInfo::from_image(&image)
+        },
+    )
+    .unwrap();
+
+    tracing::trace!(
+        "{layers} textures of {width}x{height} loaded in {:?}",
+        start.elapsed()
+    );
+
+    Ok((view, future))
+}
+
+#[cfg(feature = "image")]
+#[must_use]
+/// Decodes a PNG image, returning its size and its RGBA8 texels row by row, starting from the top.
+///
+/// Returns `None` if the image cannot be decoded, the cause being logged.
+fn decode(path: &Path) -> Option<(u32, u32, Vec<[u8; 4]>)> {
+    let file = std::fs::File::open(path)
+        .map_err(|error| tracing::error!("Failed to open texture {}: {error}", path.display()))
+        .ok()?;
+
+    // Palettes and 16-bit channels are converted to 8-bit channels
+    let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|error| tracing::error!("Failed to read texture {}: {error}", path.display()))
+        .ok()?;
+
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut data)
+        .map_err(|error| tracing::error!("Failed to decode texture {}: {error}", path.display()))
+        .ok()?;
+    data.truncate(info.buffer_size());
+
+    let texels = match info.color_type {
+        png::ColorType::Rgba => data
+            .chunks_exact(4)
+            .map(|texel| [texel[0], texel[1], texel[2], texel[3]])
+            .collect(),
+        png::ColorType::Rgb => data
+            .chunks_exact(3)
+            .map(|texel| [texel[0], texel[1], texel[2], u8::MAX])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .map(|texel| [texel[0], texel[0], texel[0], texel[1]])
+            .collect(),
+        png::ColorType::Grayscale => data
+            .iter()
+            .map(|&gray| [gray, gray, gray, u8::MAX])
+            .collect(),
+        png::ColorType::Indexed => {
+            tracing::error!("Texture {} has an unexpanded palette", path.display());
+            return None;
+        }
+    };
+
+    Some((info.width, info.height, texels))
+}
+
+#[cfg(not(feature = "image"))]
+#[must_use]
+/// Always fails, as decoding PNG images requires the `image` feature.
+fn decode(path: &Path) -> Option<(u32, u32, Vec<[u8; 4]>)> {
+    tracing::error!(
+        "Loading texture {} requires the `image` feature",
+        path.display()
+    );
+    None
+}
+
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+/// Resamples RGBA8 texels to the given size with bilinear filtering, wrapping around the edges
+/// like the sampler of the textures.
+fn resample(
+    (width, height): (u32, u32),
+    texels: &[[u8; 4]],
+    (new_width, new_height): (u32, u32),
+) -> Vec<[u8; 4]> {
+    let texel = |x: i64, y: i64| {
+        let x = x.rem_euclid(i64::from(width)) as usize;
+        let y = y.rem_euclid(i64::from(height)) as usize;
+        texels[y * width as usize + x].map(f32::from)
+    };
+
+    (0..new_height)
+        .flat_map(|y| (0..new_width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            // Texel centers are at half-integer coordinates
+            let u = (x as f32 + 0.5).mul_add(width as f32 / new_width as f32, -0.5);
+            let v = (y as f32 + 0.5).mul_add(height as f32 / new_height as f32, -0.5);
+            let (x0, y0) = (u.floor() as i64, v.floor() as i64);
+            let (fx, fy) = (u - u.floor(), v - v.floor());
+
+            let [top_left, top_right, bottom_left, bottom_right] = [
+                texel(x0, y0),
+                texel(x0 + 1, y0),
+                texel(x0, y0 + 1),
+                texel(x0 + 1, y0 + 1),
+            ];
+            std::array::from_fn(|channel| {
+                let top = (top_right[channel] - top_left[channel]).mul_add(fx, top_left[channel]);
+                let bottom = (bottom_right[channel] - bottom_left[channel])
+                    .mul_add(fx, bottom_left[channel]);
+                (bottom - top).mul_add(fy, top).round() as u8
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,62 @@
+//! Moves of the models of a scene, which need a Vulkan device and are therefore ignored by default.
+//!
+//! Run with `cargo test -p rt-engine --features image -- --ignored`.
+
+#![cfg(feature = "image")]
+
+use rt_engine::control::camera::first_person::FirstPerson;
+use rt_engine::render::image::{ImageDescriptor, ImageFormat};
+use rt_engine::render::RenderError;
+use rt_engine::shader::{Material, SceneDescriptor};
+use rt_engine::{RayTracingApp, RayTracingAppConfig};
+
+/// A single triangle, so that the scene has a single model.
+const SCENE: &str = "\
+v -1 0 -1
+v 1 0 -1
+v 0 0 1
+f 1 3 2
+";
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+/// Moving a model that is not in the scene returns an error instead of panicking.
+fn out_of_range_model_is_an_error() {
+    let directory =
+        std::env::temp_dir().join(format!("rt-engine-model-transform-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let model_path = directory.join("scene.obj");
+    std::fs::write(&model_path, SCENE).unwrap();
+
+    let config = RayTracingAppConfig::builder()
+        .image(ImageDescriptor {
+            path: directory.join("render.png"),
+            format: ImageFormat::Png8,
+            width: 16,
+            height: 16,
+            accumulated_frames: 1,
+            save_auxiliary_outputs: false,
+            denoise: false,
+        })
+        .camera(FirstPerson::from_position_yaw_pitch(
+            [0.0, 1.0, 3.0],
+            270.0,
+            -20.0,
+        ))
+        .scene(SceneDescriptor {
+            model_paths: vec![model_path.to_string_lossy().into_owned()],
+            materials: vec![Material::matte([0.8, 0.8, 0.8])],
+            ..Default::default()
+        })
+        .build();
+
+    let mut app = RayTracingApp::try_new(config).expect("the application could not be created");
+    let moved = app.update_model_transform(0, [0.0, 1.0, 0.0]);
+    let out_of_range = app.update_model_transform(1, [0.0, 1.0, 0.0]);
+    drop(app);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(moved, Ok(()));
+    assert_eq!(out_of_range, Err(RenderError::ModelOutOfRange(1, 1)));
+}
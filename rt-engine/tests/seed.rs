@@ -0,0 +1,88 @@
+//! Renders of whole scenes, which need a Vulkan device and are therefore ignored by default.
+//!
+//! Run with `cargo test -p rt-engine --features image -- --ignored`.
+
+#![cfg(feature = "image")]
+
+use std::path::{Path, PathBuf};
+
+use rt_engine::control::camera::first_person::FirstPerson;
+use rt_engine::render::image::{ImageDescriptor, ImageFormat};
+use rt_engine::shader::{Material, SceneDescriptor, ShaderDescriptor};
+use rt_engine::{RayTracingApp, RayTracingAppConfig};
+
+/// A floor and a wall, so that the bounces between them and towards the sky give a noisy image.
+const SCENE: &str = "\
+v -4 0 -4
+v 4 0 -4
+v 4 0 4
+v -4 0 4
+v -4 0 -2
+v 4 0 -2
+v 4 4 -2
+v -4 4 -2
+f 1 3 2
+f 1 4 3
+f 5 6 7
+f 5 7 8
+";
+
+#[must_use]
+/// Renders the scene at `model_path` to a PNG file at `path` with the given seed, and returns its bytes.
+fn render(model_path: &Path, path: PathBuf, seed: u64) -> Vec<u8> {
+    let config = RayTracingAppConfig::builder()
+        .image(ImageDescriptor {
+            path: path.clone(),
+            format: ImageFormat::Png8,
+            width: 64,
+            height: 48,
+            accumulated_frames: 4,
+            save_auxiliary_outputs: false,
+            denoise: false,
+        })
+        .camera(FirstPerson::from_position_yaw_pitch(
+            [0.0, 1.5, 3.0],
+            270.0,
+            -10.0,
+        ))
+        .scene(SceneDescriptor {
+            model_paths: vec![model_path.to_string_lossy().into_owned()],
+            materials: vec![Material::matte([0.8, 0.8, 0.8])],
+            ..Default::default()
+        })
+        .shader(ShaderDescriptor {
+            max_bounces: 4,
+            seed,
+            ..Default::default()
+        })
+        .build();
+
+    let mut app = RayTracingApp::try_new(config).expect("the application could not be created");
+    assert!(!app.try_pump().expect("the image could not be rendered"));
+    drop(app);
+
+    std::fs::read(path).expect("the image was not saved")
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+/// The same scene rendered from the same camera with the same seed gives byte-identical images.
+fn same_seed_renders_identical_images() {
+    let directory = std::env::temp_dir().join(format!("rt-engine-seed-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let model_path = directory.join("scene.obj");
+    std::fs::write(&model_path, SCENE).unwrap();
+
+    let first = render(&model_path, directory.join("first.png"), 42);
+    let second = render(&model_path, directory.join("second.png"), 42);
+    let other = render(&model_path, directory.join("other.png"), 43);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(first == second, "the renders with the same seed differ");
+    // Otherwise the images would be identical whatever the seed, and the test would prove nothing
+    assert!(
+        first != other,
+        "the renders with different seeds are identical"
+    );
+}
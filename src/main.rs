@@ -1,82 +1,75 @@
 //! RayTracing Engine
 
-// Hide console window on Windows in release builds
-#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+// Hide console window on Windows in release builds, unless the `console` feature is enabled
+#![cfg_attr(
+    all(not(debug_assertions), not(feature = "console")),
+    windows_subsystem = "windows"
+)]
 
 use rt_engine::RayTracingApp;
 
 fn main() {
-    tracing_subscriber::fmt()
-        .with_max_level(if cfg!(debug_assertions) {
-            tracing::Level::TRACE
-        } else {
-            tracing::Level::INFO
-        })
-        .init();
+    rt_engine::init_tracing(if cfg!(debug_assertions) {
+        tracing::Level::TRACE
+    } else {
+        tracing::Level::INFO
+    });
 
-    let first_person_camera = Box::new(
+    let first_person_camera =
         rt_engine::control::camera::first_person::FirstPerson::from_position_yaw_pitch(
             [5.0, 0.0, 3.0],
             240.0,
             0.0,
-        ),
-    );
+        );
 
-    let keyboard = Box::new(rt_engine::control::controller::keyboard::Keyboard::default());
-    let mouse = Box::new(rt_engine::control::controller::mouse::Mouse::default());
+    let scene_descriptor = rt_engine::shader::SceneDescriptor {
+        model_paths: vec![
+            "assets/models/cottage/cottage_FREE.obj".to_string(),
+            "assets/models/gun/Pistol_02.obj".to_string(),
+        ],
+        transforms: vec![[0.0, -3.0, -10.0].into(), [0.0, 0.0, 0.0].into()],
+        materials: vec![
+            rt_engine::shader::Material::rough_plastic([0.8, 0.6, 0.6]),
+            rt_engine::shader::Material::polished_metal([0.6, 0.6, 0.65]),
+        ],
+        material_indices: vec![0, 1],
+        ..Default::default()
+    };
 
-    let config = rt_engine::RayTracingAppConfig {
-        render_surface_type: rt_engine::RenderSurfaceType::Window(
-            rt_engine::render::window::WindowDescriptor {
-                width: 1024,
-                height: 720,
-                title: "RayTracer".to_string(),
-                cursor_visible: false,
-                cursor_locked: true,
-                position: None,
-                resizable: false,
-                mode: rt_engine::render::window::Mode::Windowed,
-                present_mode: rt_engine::render::window::PresentMode::Fifo,
-                resize_constraints: rt_engine::render::window::ResizeConstraints::default(),
-            },
-        ),
-        camera: first_person_camera,
-        controllers: vec![keyboard, mouse],
-        scene_descriptor: rt_engine::shader::SceneDescriptor {
-            model_paths: vec![
-                "assets/models/cottage/cottage_FREE.obj".to_string(),
-                "assets/models/gun/Pistol_02.obj".to_string(),
-            ],
-            positions: vec![[0.0, -3.0, -10.0], [0.0, 0.0, 0.0]],
-        },
-        shader_descriptor: rt_engine::shader::ShaderDescriptor {
+    let config = rt_engine::RayTracingAppConfig::builder()
+        .window(rt_engine::render::window::WindowDescriptor {
+            width: 1024,
+            height: 720,
+            title: "RayTracer".to_string(),
+            cursor_visible: false,
+            cursor_locked: true,
+            resizable: false,
+            ..Default::default()
+        })
+        .camera(first_person_camera)
+        .add_controller(rt_engine::control::controller::keyboard::Keyboard::default())
+        .add_controller(rt_engine::control::controller::mouse::Mouse::default())
+        .scene(scene_descriptor)
+        .shader(rt_engine::shader::ShaderDescriptor {
             max_bounces: 6,
             samples: 10,
-        },
-    };
+            ..Default::default()
+        })
+        .build();
 
-    // let config = rt_engine::RayTracingAppConfig {
-    //     render_surface_type: rt_engine::RenderSurfaceType::Image(
-    //         rt_engine::render::image::ImageDescriptor {
-    //             path: "output.png".into(),
-    //             width: 3840,
-    //             height: 2160,
-    //         },
-    //     ),
-    //     camera: first_person_camera,
-    //     controllers: vec![],
-    //     scene_descriptor: rt_engine::shader::SceneDescriptor {
-    //         model_paths: vec![
-    //             "assets/models/cottage/cottage_FREE.obj".to_string(),
-    //             "assets/models/gun/Pistol_02.obj".to_string(),
-    //         ],
-    //         positions: vec![[0.0, -3.0, -10.0], [0.0, 0.0, 0.0]],
-    //     },
-    //     shader_descriptor: rt_engine::shader::ShaderDescriptor {
-    //         max_bounces: 6,
-    //         samples: 10,
-    //     },
-    // };
+    // let config = rt_engine::RayTracingAppConfig::builder()
+    //     .image(rt_engine::render::image::ImageDescriptor {
+    //         path: "output.png".into(),
+    //         format: rt_engine::render::image::ImageFormat::Png8,
+    //         width: 3840,
+    //         height: 2160,
+    //         accumulated_frames: 64,
+    //         save_auxiliary_outputs: false,
+    //         denoise: false,
+    //     })
+    //     .camera(first_person_camera)
+    //     .scene(scene_descriptor)
+    //     .build();
 
     let app = RayTracingApp::new(config);
 